@@ -1,15 +1,16 @@
 use bevy::prelude::*;
+use slither_common::skins::SkinId;
 
 use crate::constants::BOT_SPAWN_INTERVAL;
-use crate::utils::generate_random_color;
+use crate::utils::generate_random_skin;
 
 #[derive(Component, Clone, Debug)]
 pub struct Bot
 {
-    pub color: Color,
+    pub skin: SkinId,
     pub target_position: Option<Vec2>,
     pub decision_timer: Timer,
-    pub score: u32,
+    pub orb_spawn_timer: f32,
 }
 
 impl Default for Bot
@@ -17,10 +18,10 @@ impl Default for Bot
     fn default() -> Self
     {
         Self {
-            color: generate_random_color(),
+            skin: generate_random_skin(),
             target_position: None,
             decision_timer: Timer::from_seconds(BOT_SPAWN_INTERVAL, TimerMode::Repeating),
-            score: 0,
+            orb_spawn_timer: 0.0,
         }
     }
 }
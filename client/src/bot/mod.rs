@@ -4,13 +4,27 @@ pub mod systems;
 use bevy::prelude::*;
 pub use systems::*;
 
+use crate::config::resources::Config;
+use crate::core::events::{MatchStarted, SnakeDied};
+use crate::core::systems::update_arena_radius;
+use crate::mode::systems::start_match;
+
 pub struct BotPlugin;
 
 impl Plugin for BotPlugin
 {
     fn build(&self, app: &mut App)
     {
-        app.add_systems(Startup, spawn_bots)
-            .add_systems(Update, (bot_movement, bot_eating));
+        app.add_systems(
+            Update,
+            (
+                sync_bot_count.after(start_match).run_if(
+                    resource_changed::<Config>
+                        .or_else(on_event::<SnakeDied>())
+                        .or_else(on_event::<MatchStarted>()),
+                ),
+                bot_movement.before(update_arena_radius),
+            ),
+        );
     }
 }
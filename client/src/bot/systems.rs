@@ -1,40 +1,73 @@
-use std::collections::VecDeque;
 use std::time::Duration;
 
 use bevy::prelude::*;
 use bevy::sprite::MaterialMesh2dBundle;
 use rand::Rng;
+use slither_common::config::BotConfig;
 
 use super::components::Bot;
+use crate::config::resources::Config;
 use crate::constants::*;
-use crate::core::components::{Segment, SegmentPositionHistory, Snake, SnakeSegment};
-use crate::core::resources::GlobalGameState;
+use crate::core::components::{
+    has_power_up, PowerUp, PowerUps, SegmentPositionHistory, Snake, SnakeBody, SpawnProtection, Team,
+};
+use crate::core::events::{BoostEnded, BoostStarted, MatchStarted, SegmentsRemoved, SnakeSpawned};
+use crate::core::resources::{ArenaRadius, RenderHandles, SnakeGrowth};
+use crate::map::resources::ArenaFeatures;
+use crate::map::systems::SpawnSelector;
 use crate::orb::components::Orb;
+use crate::player::systems::{drop_boost_orb, shed_mass};
 use crate::utils::*;
 
-pub fn spawn_bots(
+/// Spawns or removes bots until the arena holds as many as the config asks for. Runs at the start of the
+/// match, whenever the bot settings change and, in modes that respawn bots, whenever a snake dies. Modes that
+/// don't respawn bots only fill the arena as the match starts, so an edit mid-match can take bots away but never
+/// brings eliminated ones back.
+pub fn sync_bot_count(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
+    mut render_handles: ResMut<RenderHandles>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    mut global_game_state: ResMut<GlobalGameState>,
+    mut spawn_events: EventWriter<SnakeSpawned>,
+    mut started_events: EventReader<MatchStarted>,
+    config: Res<Config>,
+    spawn_selector: SpawnSelector,
+    bot_query: Query<Entity, With<Bot>>,
+    mut synced_settings: Local<Option<BotConfig>>,
 )
 {
-    if !SPAWN_BOTS_ENABLED {
+    let match_started = started_events.read().count() > 0;
+    let settings_changed = synced_settings.as_ref() != Some(&config.bots);
+    if settings_changed {
+        *synced_settings = Some(config.bots.clone());
+    }
+
+    let respawns = config.rules.mode.respawns_bots();
+    if !match_started && !settings_changed && !respawns {
+        return;
+    }
+
+    let target = if config.bots.enabled { config.bots.spawn_amount } else { 0 };
+    let current = bot_query.iter().count();
+
+    remove_extra_bots(&mut commands, bot_query.iter().skip(target));
+    if !match_started && !respawns {
         return;
     }
 
-    // Spawn initial bots
-    for _ in 0..BOT_DEFAULT_SPAWN_AMOUNT {
-        let random_position = generate_random_position_within_radius(MAP_RADIUS);
+    let mut spawned = Vec::new();
+    for _ in current..target {
+        let random_position = spawn_selector.pick(&spawned);
+        spawned.push(random_position);
         let bot_size = Vec3::new(PLAYER_DEFAULT_RADIUS, PLAYER_DEFAULT_RADIUS, Z_BOT_SEGMENTS);
 
         let bot = Bot::default();
+        let snake = Snake::new(bot.skin);
 
         let bot_entity = commands
             .spawn((
                 MaterialMesh2dBundle {
-                    mesh: meshes.add(Circle::new(1.0)).into(),
-                    material: materials.add(ColorMaterial::from(bot.color.clone())),
+                    mesh: render_handles.circle.clone().into(),
+                    material: render_handles.material(&mut materials, snake.color),
                     transform: Transform {
                         translation: random_position.extend(Z_BOT_SEGMENTS),
                         scale: bot_size,
@@ -43,252 +76,278 @@ pub fn spawn_bots(
                     ..default()
                 },
                 bot.clone(),
-                Snake::new(bot.color),
+                snake,
+                Name::new(generate_bot_name()),
                 SegmentPositionHistory::default(),
+                SnakeBody::default(),
+                PowerUps::default(),
+                SpawnProtection::default(),
             ))
             .id();
 
-        // Spawn initial segments for the bot
-        let mut snake_segments = VecDeque::new();
-        for i in 0..PLAYER_DEFAULT_LENGTH {
-            let segment_entity = commands
-                .spawn((
-                    Segment {
-                        index: i,
-                        radius: PLAYER_DEFAULT_RADIUS,
-                    },
-                    SnakeSegment { owner: bot_entity },
-                    MaterialMesh2dBundle {
-                        mesh: meshes.add(Circle::new(1.0)).into(),
-                        material: materials.add(ColorMaterial::from(bot.color)),
-                        transform: Transform {
-                            translation: Vec3::new(
-                                random_position.x - (i as f32 * SEGMENT_SPACING),
-                                random_position.y,
-                                Z_BOT_SEGMENTS,
-                            ),
-                            scale: bot_size,
-                            ..default()
-                        },
-                        ..default()
-                    },
-                ))
-                .id();
-            snake_segments.push_back(segment_entity);
-        }
-
-        // Add segments to the Snake component
-        if let Some(mut snake) = commands.get_entity(bot_entity) {
-            snake.insert(Snake {
-                length: PLAYER_DEFAULT_LENGTH,
-                segments: snake_segments,
-                color: bot.color,
-            });
-        }
+        spawn_events.send(SnakeSpawned { snake: bot_entity });
+    }
+}
 
-        global_game_state.total_snakes += 1;
+/// Takes bots out of the arena without a death: they drop no orbs, and the kill feed and the match standings never
+/// hear about them.
+fn remove_extra_bots(commands: &mut Commands, bots: impl Iterator<Item = Entity>)
+{
+    for bot in bots {
+        commands.entity(bot).despawn_recursive();
     }
 }
 
 pub fn bot_movement(
+    mut commands: Commands,
+    mut render_handles: ResMut<RenderHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
     time: Res<Time>,
-    mut query_set: ParamSet<(
-        Query<(Entity, &mut Transform, &mut Bot, &mut SegmentPositionHistory)>,
-        Query<(&mut Transform, &Segment, &SnakeSegment)>,
-        Query<&Transform, With<Orb>>,
+    growth: Res<SnakeGrowth>,
+    config: Res<Config>,
+    arena: Res<ArenaRadius>,
+    features: Res<ArenaFeatures>,
+    mut bot_query: Query<(
+        Entity,
+        &mut Transform,
+        &mut Bot,
+        &mut SegmentPositionHistory,
+        &mut Snake,
+        Option<&PowerUps>,
     )>,
+    body_query: Query<(Entity, &SnakeBody, Option<&Team>)>,
+    team_query: Query<&Team>,
+    orb_query: Query<&Transform, (With<Orb>, Without<Bot>)>,
+    mut boost_started_events: EventWriter<BoostStarted>,
+    mut boost_ended_events: EventWriter<BoostEnded>,
+    mut removed_events: EventWriter<SegmentsRemoved>,
 )
 {
-    let mut bot_movements: Vec<(Entity, Vec3, Vec<Vec3>)> = Vec::new();
     let mut rng = rand::thread_rng();
+    let map_radius = arena.radius;
 
     // First, collect orb positions
-    let nearby_orbs: Vec<Vec2> = query_set.p2().iter().map(|t| t.translation.truncate()).collect();
+    let nearby_orbs: Vec<Vec2> = orb_query.iter().map(|t| t.translation.truncate()).collect();
 
-    // First, collect all segment positions and their owners into a Vec
-    let segments_data: Vec<(Vec2, Entity)> = query_set
-        .p1()
+    // Then collect all body positions with their owners and teams
+    let segments_data: Vec<(Vec2, Entity, Option<Team>)> = body_query
         .iter()
-        .map(|(transform, _, snake_segment)| (transform.translation.truncate(), snake_segment.owner))
+        .flat_map(|(owner, body, team)| body.points.iter().map(move |&point| (point, owner, team.copied())))
         .collect();
 
-    // Then update bot positions
-    {
-        let mut bot_query = query_set.p0();
-        for (bot_entity, mut transform, mut bot, mut segment_history) in bot_query.iter_mut() {
-            bot.decision_timer.tick(time.delta());
-
-            let current_pos = transform.translation.truncate();
-
-            // Calculate danger direction using the collected segments
-            let mut danger_direction = Vec2::ZERO;
-            for (segment_pos, owner) in &segments_data {
-                if *owner != bot_entity {
-                    let distance = current_pos.distance(*segment_pos);
-                    let danger_radius = PLAYER_DEFAULT_RADIUS * 3.0;
-                    if distance < danger_radius {
-                        let away_vector = (current_pos - *segment_pos).normalize();
-                        let strength = 1.0 - (distance / danger_radius);
-                        danger_direction += away_vector * strength;
-                    }
+    for (bot_entity, mut transform, mut bot, mut segment_history, mut snake, power_ups) in bot_query.iter_mut() {
+        bot.decision_timer.tick(time.delta());
+
+        let current_pos = transform.translation.truncate();
+        let bot_team = team_query.get(bot_entity).ok().copied();
+        let is_teammate = |team: &Option<Team>| bot_team.is_some() && *team == bot_team;
+
+        // Calculate danger direction using the collected segments. Without friendly fire teammates are harmless,
+        // with it bots keep well clear of them so neither cuts the other off.
+        let mut danger_direction = Vec2::ZERO;
+        for (segment_pos, owner, team) in &segments_data {
+            if *owner != bot_entity && (config.rules.friendly_fire || !is_teammate(team)) {
+                let distance = current_pos.distance(*segment_pos);
+                let danger_radius = if is_teammate(team) {
+                    PLAYER_DEFAULT_RADIUS * 6.0
+                } else {
+                    PLAYER_DEFAULT_RADIUS * 3.0
+                };
+                if distance < danger_radius {
+                    let away_vector = (current_pos - *segment_pos).normalize();
+                    let strength = 1.0 - (distance / danger_radius);
+                    danger_direction += away_vector * strength;
                 }
             }
+        }
+
+        // Obstacles are as deadly as snake bodies
+        for obstacle in &features.obstacles {
+            let center = Vec2::from_array(obstacle.center);
+            let gap = current_pos.distance(center) - obstacle.radius;
+            let danger_radius = PLAYER_DEFAULT_RADIUS * 3.0;
+            if gap < danger_radius {
+                let strength = 1.0 - gap.max(0.0) / danger_radius;
+                danger_direction += (current_pos - center).normalize_or_zero() * strength;
+            }
+        }
 
-            if bot.decision_timer.just_finished()
-                || bot
-                    .target_position
-                    .map_or(true, |target| current_pos.distance(target) < PLAYER_DEFAULT_RADIUS)
-            {
-                let current_pos = transform.translation.truncate();
-                // Filter nearby orbs based on current bot position
-                let nearby_orbs: Vec<Vec2> = nearby_orbs
+        if bot.decision_timer.just_finished()
+            || bot
+                .target_position
+                .is_none_or(|target| current_pos.distance(target) < PLAYER_DEFAULT_RADIUS)
+        {
+            let current_pos = transform.translation.truncate();
+            // Filter nearby orbs based on current bot position
+            let nearby_orbs: Vec<Vec2> = nearby_orbs
+                .iter()
+                .filter(|pos| {
+                    // Only consider orbs that are not too close to other snakes
+                    let is_safe = segments_data.iter().all(|(other_pos, snake_segment_owner, team)| {
+                        if *snake_segment_owner != bot_entity && (config.rules.friendly_fire || !is_teammate(team)) {
+                            let distance = other_pos.distance(**pos);
+                            distance > PLAYER_DEFAULT_RADIUS * 2.5
+                        } else {
+                            true
+                        }
+                    });
+                    current_pos.distance(**pos) < map_radius * 0.5 && is_safe
+                })
+                .copied()
+                .collect();
+
+            if !nearby_orbs.is_empty() && rng.gen_bool(0.7) {
+                let closest_orb = nearby_orbs
                     .iter()
-                    .filter(|pos| {
-                        // Only consider orbs that are not too close to other snakes
-                        let is_safe = segments_data.iter().all(|(other_pos, snake_segment_owner)| {
-                            if *snake_segment_owner != bot_entity {
-                                let distance = other_pos.distance(**pos);
-                                distance > PLAYER_DEFAULT_RADIUS * 2.5
-                            } else {
-                                true
-                            }
-                        });
-                        current_pos.distance(**pos) < MAP_RADIUS * 0.5 && is_safe
+                    .min_by(|a, b| {
+                        let dist_a = current_pos.distance(**a);
+                        let dist_b = current_pos.distance(**b);
+                        dist_a.partial_cmp(&dist_b).unwrap()
                     })
-                    .copied()
-                    .collect();
-
-                if !nearby_orbs.is_empty() && rng.gen_bool(0.7) {
-                    let closest_orb = nearby_orbs
-                        .iter()
-                        .min_by(|a, b| {
-                            let dist_a = current_pos.distance(**a);
-                            let dist_b = current_pos.distance(**b);
-                            dist_a.partial_cmp(&dist_b).unwrap()
-                        })
-                        .unwrap();
-                    bot.target_position = Some(*closest_orb);
-                } else {
-                    let safe_radius = MAP_RADIUS * 0.9;
-                    let random_position = generate_random_position_within_radius(safe_radius);
-                    bot.target_position = Some(random_position);
-                }
-
-                bot.decision_timer
-                    .set_duration(Duration::from_secs_f32(BOT_SPAWN_INTERVAL + rng.gen_range(-0.2..0.2)));
+                    .unwrap();
+                bot.target_position = Some(*closest_orb);
+            } else {
+                let safe_radius = map_radius * 0.9;
+                let random_position = generate_random_position_within_radius(safe_radius);
+                // Wandering into an obstacle or a corner outside the arena is pointless, so wait for the next decision
+                let reachable = arena.contains(random_position, PLAYER_DEFAULT_RADIUS)
+                    && !features.hits_obstacle(random_position.to_array(), PLAYER_DEFAULT_RADIUS);
+                bot.target_position = reachable.then_some(random_position);
             }
 
-            if let Some(target) = bot.target_position {
-                let current_pos = transform.translation.truncate();
-                let mut direction = (target - current_pos).normalize();
+            bot.decision_timer
+                .set_duration(Duration::from_secs_f32(BOT_SPAWN_INTERVAL + rng.gen_range(-0.2..0.2)));
+        }
 
-                // Apply danger avoidance if there are nearby snakes
-                if danger_direction != Vec2::ZERO {
-                    let avoid_weight = 0.8; // Prioritize avoiding collisions
-                    let target_weight = 0.2;
-                    direction = (direction * target_weight + danger_direction * avoid_weight).normalize();
-                }
+        // Bots burst away from snakes closing in on them, as long as they can afford it
+        let is_boosting = bot.target_position.is_some()
+            && danger_direction.length() > BOT_BOOST_DANGER
+            && snake.mass >= config.snake.mass_needed_for_boosting;
+        if is_boosting != snake.is_boosting {
+            snake.is_boosting = is_boosting;
+            if is_boosting {
+                boost_started_events.send(BoostStarted { snake: bot_entity });
+            } else {
+                boost_ended_events.send(BoostEnded { snake: bot_entity });
+            }
+        }
+        if !is_boosting {
+            bot.orb_spawn_timer = 0.0;
+        }
 
-                let wobble = Vec2::new(rng.gen_range(-0.2..0.2), rng.gen_range(-0.2..0.2));
-                direction = (direction + wobble * 0.1).normalize();
+        if let Some(target) = bot.target_position {
+            let current_pos = transform.translation.truncate();
+            let mut direction = (target - current_pos).normalize();
 
-                transform.translation += direction.extend(0.0) * BOT_SPEED * time.delta_seconds();
+            // Apply danger avoidance if there are nearby snakes
+            if danger_direction != Vec2::ZERO {
+                let avoid_weight = 0.8; // Prioritize avoiding collisions
+                let target_weight = 0.2;
+                direction = (direction * target_weight + danger_direction * avoid_weight).normalize();
+            }
+            snake.steering = direction;
 
-                let angle = direction.y.atan2(direction.x);
-                transform.rotation = Quat::from_rotation_z(angle);
+            let wobble = Vec2::new(rng.gen_range(-0.2..0.2), rng.gen_range(-0.2..0.2));
+            direction = (direction + wobble * 0.1).normalize();
 
-                let distance_from_center = transform.translation.truncate().length();
-                if distance_from_center > MAP_RADIUS - PLAYER_DEFAULT_RADIUS {
-                    let clamped_position =
-                        transform.translation.truncate().normalize() * (MAP_RADIUS - PLAYER_DEFAULT_RADIUS);
-                    transform.translation = clamped_position.extend(transform.translation.z);
-                    bot.target_position = None;
+            let mut speed = growth.speed(snake.mass) * features.speed_multiplier_at(current_pos.to_array());
+            if has_power_up(power_ups, PowerUp::Speed) {
+                speed *= POWER_UP_SPEED_MULTIPLIER;
+            }
+            if let (true, Ok((_, body, _))) = (is_boosting, body_query.get(bot_entity)) {
+                speed *= config.snake.boost_speed_multiplier;
+
+                let positions = shed_mass(&mut snake, body, &growth, config.snake.boost_mass_cost * time.delta_seconds());
+                if !positions.is_empty() {
+                    removed_events.send(SegmentsRemoved {
+                        snake: bot_entity,
+                        positions,
+                        radius: body.radius,
+                        color: snake.color,
+                    });
                 }
 
-                segment_history.positions.push_front(transform.translation);
-                if segment_history.positions.len() > MAX_SEGMENT_HISTORY {
-                    segment_history.positions.pop_back();
+                bot.orb_spawn_timer += time.delta_seconds();
+                if bot.orb_spawn_timer >= ORB_SPAWN_INTERVAL {
+                    drop_boost_orb(
+                        &mut commands,
+                        &mut render_handles,
+                        &mut materials,
+                        &config,
+                        bot_entity,
+                        snake.color,
+                        current_pos,
+                        direction,
+                        body.radius,
+                    );
+                    bot.orb_spawn_timer -= ORB_SPAWN_INTERVAL;
                 }
-
-                bot_movements.push((bot_entity, transform.translation, segment_history.positions.clone().into()));
             }
-        }
-    }
-
-    // Finally, update segment positions
-    let mut segment_query = query_set.p1();
-    for (bot_entity, _bot_pos, history) in bot_movements {
-        for (mut segment_transform, segment, snake_segment) in segment_query.iter_mut() {
-            if snake_segment.owner == bot_entity {
-                let index = (segment.index + 1) * POSITIONS_PER_SEGMENT;
-                if index < history.len() as u32 {
-                    segment_transform.translation = history[index as usize];
-                }
+            let next_pos = current_pos + direction * speed * time.delta_seconds();
+            let radius = growth.radius(snake.mass);
+            let confined = arena.confine(next_pos, radius);
+            transform.translation = confined.extend(transform.translation.z);
+            // Pressed against the edge, so the target is not worth chasing
+            if confined != next_pos {
+                bot.target_position = None;
             }
-        }
-    }
-}
 
-pub fn bot_eating(
-    mut commands: Commands,
-    mut bot_query: Query<(Entity, &Transform, &mut Snake, &Bot), With<Bot>>,
-    food_query: Query<(Entity, &Transform), With<Orb>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-)
-{
-    for (bot_entity, bot_transform, mut snake, bot) in bot_query.iter_mut() {
-        for (food_entity, food_transform) in food_query.iter() {
-            let distance = bot_transform.translation.distance(food_transform.translation);
-
-            if distance < PLAYER_DEFAULT_RADIUS + ORB_RADIUS {
-                commands.entity(food_entity).despawn();
-
-                // Add new segment
-                let segment_entity = add_segment(
-                    &mut commands,
-                    &mut meshes,
-                    &mut materials,
-                    bot_transform.translation,
-                    snake.length,
-                    bot.color,
-                    bot_entity,
-                );
-
-                snake.segments.push_back(segment_entity);
-                snake.length += 1;
-            }
+            let angle = direction.y.atan2(direction.x);
+            transform.rotation = Quat::from_rotation_z(angle);
+
+            segment_history.record(transform.translation, growth.segment_count(snake.mass));
         }
     }
 }
 
-fn add_segment(
-    commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    position: Vec3,
-    index: u32,
-    color: Color,
-    owner: Entity,
-) -> Entity
+#[cfg(test)]
+mod tests
 {
-    commands
-        .spawn((
-            Segment {
-                index,
-                radius: PLAYER_DEFAULT_RADIUS,
-            },
-            SnakeSegment { owner },
-            MaterialMesh2dBundle {
-                mesh: meshes.add(Circle::new(1.0)).into(),
-                material: materials.add(ColorMaterial::from(color)),
-                transform: Transform {
-                    translation: position,
-                    scale: Vec3::new(PLAYER_DEFAULT_RADIUS, PLAYER_DEFAULT_RADIUS, Z_BOT_SEGMENTS),
-                    ..default()
-                },
-                ..default()
-            },
-        ))
-        .id()
+    use slither_common::config::GameMode;
+
+    use super::*;
+    use crate::core::events::SnakeDied;
+
+    #[test]
+    fn eliminated_bots_stay_out_when_unrelated_settings_change()
+    {
+        let mut config = Config::default();
+        config.rules.mode = GameMode::BattleRoyale;
+        config.bots.enabled = true;
+        config.bots.spawn_amount = 3;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(config)
+            .init_resource::<ArenaRadius>()
+            .init_resource::<ArenaFeatures>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .init_resource::<RenderHandles>()
+            .add_event::<MatchStarted>()
+            .add_event::<SnakeSpawned>()
+            .add_event::<SnakeDied>()
+            .add_systems(Update, sync_bot_count);
+        let bot_count = |app: &mut App| app.world_mut().query::<&Bot>().iter(app.world()).count();
+
+        app.world_mut().send_event(MatchStarted);
+        app.update();
+        assert_eq!(bot_count(&mut app), 3);
+
+        let mut bots = app.world_mut().query_filtered::<Entity, With<Bot>>();
+        let eliminated = bots.iter(app.world()).next().unwrap();
+        app.world_mut().despawn(eliminated);
+
+        app.world_mut().resource_mut::<Config>().camera.zoom.min *= 0.5;
+        app.update();
+        assert_eq!(bot_count(&mut app), 2);
+
+        app.world_mut().resource_mut::<Config>().bots.spawn_amount = 1;
+        app.update();
+        assert_eq!(bot_count(&mut app), 1);
+
+        app.world_mut().resource_mut::<Config>().bots.spawn_amount = 4;
+        app.update();
+        assert_eq!(bot_count(&mut app), 1);
+    }
 }
@@ -0,0 +1,5 @@
+use bevy::prelude::*;
+
+/// Tells the designer why their last config edit was rejected.
+#[derive(Component)]
+pub struct ConfigErrorText;
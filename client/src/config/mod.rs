@@ -0,0 +1,42 @@
+pub mod components;
+pub mod resources;
+pub mod systems;
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use resources::{Config, ConfigReloader};
+use slither_common::config::GameConfig;
+use systems::*;
+
+/// Loads the balance settings from a RON file, reloads them whenever the file is edited and keeps the resources
+/// derived from them up to date.
+pub struct ConfigPlugin
+{
+    pub path: PathBuf,
+}
+
+impl Plugin for ConfigPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        let mut reloader = ConfigReloader::new(self.path.clone());
+        let config = match GameConfig::load(&self.path) {
+            Ok(config) => config,
+            Err(err) => {
+                error!("Failed to load config {}, using the defaults: {err}", self.path.display());
+                reloader.error = Some(err.to_string());
+                GameConfig::default()
+            }
+        };
+
+        app.insert_resource(Config(config))
+            .insert_resource(reloader)
+            .add_systems(Startup, spawn_config_error_text)
+            .add_systems(
+                PreUpdate,
+                (reload_config, apply_config.run_if(resource_changed::<Config>)).chain(),
+            )
+            .add_systems(Update, update_config_error_text);
+    }
+}
@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use slither_common::config::{ConfigWatcher, GameConfig};
+
+use crate::constants::*;
+
+/// The balance settings the match is played with.
+#[derive(Resource, Clone, Debug, Default, Deref, DerefMut)]
+pub struct Config(pub GameConfig);
+
+/// Checks the config file for edits a few times a second.
+#[derive(Resource)]
+pub struct ConfigReloader
+{
+    pub watcher: ConfigWatcher,
+    pub timer: Timer,
+    /// Why the file could not be used, shown on screen until it is fixed.
+    pub error: Option<String>,
+    /// Set once a server sends its own rules, which then win over any local edits.
+    pub overridden: bool,
+}
+
+impl ConfigReloader
+{
+    pub fn new(path: PathBuf) -> Self
+    {
+        Self {
+            watcher: ConfigWatcher::new(path),
+            timer: Timer::from_seconds(CONFIG_POLL_INTERVAL, TimerMode::Repeating),
+            error: None,
+            overridden: false,
+        }
+    }
+}
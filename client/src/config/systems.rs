@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+
+use super::components::ConfigErrorText;
+use super::resources::{Config, ConfigReloader};
+use crate::constants::*;
+use crate::core::resources::{GlobalGameState, OrbMagnet, SnakeGrowth};
+use crate::orb::resources::OrbTable;
+
+/// Picks up edits to the config file. Broken edits are rejected and the last good config stays in use.
+pub fn reload_config(time: Res<Time>, mut reloader: ResMut<ConfigReloader>, mut config: ResMut<Config>)
+{
+    if reloader.overridden || !reloader.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    match reloader.watcher.poll() {
+        Some(Ok(new_config)) => {
+            info!("Reloaded config from {}", reloader.watcher.path().display());
+            reloader.error = None;
+            // Saving the file without changing it should not rebuild everything derived from it
+            if new_config != config.0 {
+                config.0 = new_config;
+            }
+        }
+        Some(Err(err)) => {
+            warn!("Rejected config edit in {}: {err}", reloader.watcher.path().display());
+            reloader.error = Some(err.to_string());
+        }
+        None => {}
+    }
+}
+
+pub fn spawn_config_error_text(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    commands.spawn((
+        ConfigErrorText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 18.0,
+                color: CONFIG_ERROR_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            bottom: Val::Px(50.0),
+            max_width: Val::Percent(60.0),
+            ..default()
+        }),
+    ));
+}
+
+pub fn update_config_error_text(reloader: Res<ConfigReloader>, mut text_query: Query<&mut Text, With<ConfigErrorText>>)
+{
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    let message = match &reloader.error {
+        Some(error) => format!("Config not applied, still using the last good one:\n{error}"),
+        None => String::new(),
+    };
+    if text.sections[0].value != message {
+        text.sections[0].value = message;
+    }
+}
+
+/// Rebuilds the resources derived from the config whenever it changes.
+pub fn apply_config(
+    config: Res<Config>,
+    mut growth: ResMut<SnakeGrowth>,
+    mut magnet: ResMut<OrbMagnet>,
+    mut global_game_state: ResMut<GlobalGameState>,
+    orb_table: Option<ResMut<OrbTable>>,
+)
+{
+    *growth = SnakeGrowth::from(&config.0);
+    *magnet = OrbMagnet::from(&config.0);
+    global_game_state.total_orbs = config.orbs.max_count;
+
+    if let Some(mut orb_table) = orb_table {
+        orb_table.power_orb_chance = config.orbs.power_orb_chance;
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::path::Path;
+
+    use slither_common::config::GameConfig;
+
+    use super::*;
+    use crate::constants::CONFIG_PATH;
+
+    #[test]
+    fn shipped_config_is_valid()
+    {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(CONFIG_PATH);
+        assert_eq!(GameConfig::load(path).unwrap(), GameConfig::default());
+    }
+
+    #[test]
+    fn config_changes_reach_the_derived_resources()
+    {
+        let mut app = App::new();
+        app.insert_resource(Config::default())
+            .init_resource::<SnakeGrowth>()
+            .init_resource::<OrbMagnet>()
+            .init_resource::<GlobalGameState>()
+            .add_systems(Update, apply_config.run_if(resource_changed::<Config>));
+        app.update();
+
+        let mut config = app.world_mut().resource_mut::<Config>();
+        config.snake.speed.base = 150.0;
+        config.snake.speed.max = 150.0;
+        config.orbs.magnet_pull_speed = 400.0;
+        app.update();
+
+        assert_eq!(app.world().resource::<SnakeGrowth>().speed(0.0), 150.0);
+        assert_eq!(app.world().resource::<OrbMagnet>().pull_speed, 400.0);
+    }
+}
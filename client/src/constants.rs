@@ -1,10 +1,12 @@
 use bevy::color::Color;
+use slither_common::skins::SkinId;
 
-/// Feature flags
-pub const SPAWN_BOTS_ENABLED: bool = true;
+/// Balance values designers tune, like speeds, orb counts and the map size, live in the game config instead
+pub const CONFIG_PATH: &str = "assets/config/game.ron";
+pub const CONFIG_POLL_INTERVAL: f32 = 0.5; // Seconds between checks for edits to the config file
+pub const CONFIG_ERROR_COLOR: Color = Color::srgb(1.0, 0.35, 0.3);
 
-/// Player movement and growth constants
-pub const PLAYER_SPEED: f32 = 100.; // Pixels per second
+/// Player constants
 pub const PLAYER_DEFAULT_RADIUS: f32 = 12.5;
 pub const PLAYER_DEFAULT_LENGTH: u32 = 1; // Number of segments the player starts with
 
@@ -15,50 +17,165 @@ pub const MAX_SEGMENT_HISTORY: usize = 100_000; // The max size a plyaer can be
 
 /// Orb constants
 pub const BOOST_ORB_RADIUS: f32 = 4.0;
-pub const MAX_ORB_SPAWN_COUNT: usize = 5000;
 
 /// Orb spawning and scoring constants
-pub const ORB_SPAWN_PER_PLAYER: usize = 50;
 pub const ORB_SPAWN_INTERVAL: f32 = 0.6; // Orb spawn interval during boosting
 pub const ORB_SPAWN_DISTANCE_MARGIN: f32 = 1.0;
-pub const SCORE_NEEDED_FOR_BOOSTING: u32 = 5;
 
 /// Bot constants
 pub const BOT_SPAWN_INTERVAL: f32 = 0.5;
-pub const BOT_DEFAULT_SPAWN_AMOUNT: usize = 5;
-pub const MAX_BOT_SPAWN_COUNT: usize = 25;
-pub const BOT_SPEED: f32 = 100.0;
-
-/// Radius growth constants
-pub const RADIUS_GROWTH_PER_STAGE: f32 = 2.0; // Amount to increase radius per stage
-pub const SCORE_PER_RADIUS_STAGE: u32 = 250; // Score required to increase radius
-pub const MIN_PLAYER_RADIUS: f32 = 12.5; // Minimum player radius
+pub const BOT_BOOST_DANGER: f32 = 1.5; // How much danger a bot has to sense before it boosts away
 
 /// Color constants
 pub const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
 pub const LEADERBOARD_COLOR: Color = Color::srgb(1.0, 0.84, 0.0);
 pub const LEADERBOARD_ENTRY_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+pub const RANKED_LIST_SHOWN: usize = 5; // Entries per top list shown between rounds
 
 pub const BLACK_COLOR: Color = Color::srgb(0.0, 0.0, 0.0);
 
+/// Colours orbs are picked from, matching the solid snake skins
+pub const SNAKE_PALETTE: [Color; 8] = [
+    Color::srgb(1.0, 0.0, 0.0),  // Red
+    Color::srgb(0.0, 1.0, 0.0),  // Green
+    Color::srgb(0.0, 0.0, 1.0),  // Blue
+    Color::srgb(1.0, 1.0, 0.0),  // Yellow
+    Color::srgb(1.0, 0.65, 0.0), // Orange
+    Color::srgb(0.5, 0.0, 0.5),  // Purple
+    Color::srgb(0.0, 1.0, 1.0),  // Cyan
+    Color::srgb(1.0, 0.75, 0.8), // Pink
+];
+
+/// Snake eye constants, relative to the head radius
+pub const EYE_SIZE: f32 = 0.35;
+pub const EYE_FORWARD_OFFSET: f32 = 0.35; // How far in front of the head centre the eyes sit
+pub const EYE_SIDE_OFFSET: f32 = 0.45; // How far apart the eyes are
+pub const PUPIL_SIZE: f32 = 0.55; // Relative to the eye
+pub const PUPIL_OFFSET: f32 = 0.4; // How far the pupils move towards the steering direction, relative to the eye
+pub const EYE_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+pub const PUPIL_COLOR: Color = Color::srgb(0.05, 0.05, 0.05);
+
+/// Orb table constants
+pub const MEDIUM_ORB_RADIUS: f32 = 7.0;
+pub const MEDIUM_ORB_VALUE: u32 = 2;
+pub const LARGE_ORB_RADIUS: f32 = 10.0;
+pub const LARGE_ORB_VALUE: u32 = 4;
+
+/// Orb lifetime and respawn constants
+pub const ORB_FADE_DURATION: f32 = 2.0; // Seconds orbs take to shrink away at the end of their lifetime
+pub const ORB_DENSITY_CELL_SIZE: f32 = 200.0; // Pixels, the grid used to find sparse regions
+pub const ORB_SPAWN_CANDIDATES: usize = 4; // Random spots tried per natural orb, the sparsest one wins
+
+/// Power orb constants
+pub const POWER_ORB_RADIUS: f32 = 8.0;
+pub const POWER_ORB_GLOW: f32 = 4.0; // Brightness multiplier picked up by the bloom
+pub const POWER_UP_SPEED_DURATION: f32 = 5.0; // Seconds
+pub const POWER_UP_SPEED_MULTIPLIER: f32 = 1.5;
+pub const POWER_UP_MAGNET_DURATION: f32 = 10.0; // Seconds
+pub const POWER_UP_MAGNET_MULTIPLIER: f32 = 3.0;
+pub const POWER_UP_GHOST_DURATION: f32 = 4.0; // Seconds
+pub const GHOST_ALPHA: f32 = 0.35; // Body opacity while a snake is a ghost
+pub const POWER_UP_SPEED_COLOR: Color = Color::srgb(1.0, 0.9, 0.2);
+pub const POWER_UP_MAGNET_COLOR: Color = Color::srgb(0.3, 0.5, 1.0);
+pub const POWER_UP_GHOST_COLOR: Color = Color::srgb(0.9, 0.9, 1.0);
+
+/// Orb magnet constants
+pub const ORB_MAGNET_RELEASE_FACTOR: f32 = 1.5; // Orbs are let go once this many magnet radii away
+pub const ORB_DROP_MAGNET_IMMUNITY: f32 = 1.0; // Seconds before a snake can pull in orbs it dropped itself
+
+/// Orb animation constants
+pub const DEATH_BURST_DURATION: f32 = 0.6; // Seconds for death orbs to fly out of the body
+pub const DEATH_BURST_DISTANCE: f32 = 30.0; // How far death orbs fly at most, in pixels
+pub const ORB_SUCK_DURATION: f32 = 0.15; // Seconds an eaten orb takes to reach the head
+pub const ORB_PULSE_SPEED: f32 = 3.0; // Radians per second
+pub const ORB_PULSE_AMOUNT: f32 = 0.15; // How much idle orbs grow and shrink, relative to their radius
+
+/// Boost effect constants
+pub const BOOST_GLOW_STRENGTH: f32 = 1.5; // Extra body brightness at full boost glow
+pub const BOOST_GLOW_PULSE_SPEED: f32 = 10.0; // Radians per second
+pub const BOOST_GLOW_FADE_SPEED: f32 = 4.0; // Glow change per second when boosting starts or stops
+pub const BOOST_PARTICLE_INTERVAL: f32 = 0.03; // Seconds between trail particles
+pub const BOOST_PARTICLE_LIFETIME: f32 = 0.5;
+pub const BOOST_PARTICLE_SPEED: f32 = 60.0; // Pixels per second
+pub const BOOST_PARTICLE_SIZE: f32 = 0.4; // Relative to the snake radius
+pub const BOOST_PARTICLE_GLOW: f32 = 3.0; // Brightness multiplier picked up by the bloom
+pub const SEGMENT_POP_DURATION: f32 = 0.3; // Seconds a removed segment takes to pop
+pub const SEGMENT_POP_GROWTH: f32 = 0.3; // How much a removed segment swells before shrinking away
+pub const PICKUP_SPARKS_PER_VALUE: u32 = 2; // Sparks thrown off for each point an eaten orb was worth
+pub const PICKUP_SPARKS_MAX: u32 = 12;
+pub const PICKUP_SPARK_SIZE: f32 = 1.5;
+
+/// Main menu constants
+pub const MENU_SKIN_SWATCHES: usize = 12; // Segments shown in the skin preview
+pub const MENU_SWATCH_SIZE: f32 = 24.; // Pixels
+pub const MENU_BACKGROUND_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.7);
+
+/// Game mode constants
+pub const TEAM_SKINS: [SkinId; 4] = [0, 2, 1, 3]; // Red, blue, green and yellow, one per team
+pub const PODIUM_SIZE: usize = 3; // Snakes ranked when a match ends
+
+/// Map feature constants
+pub const MAPS_DIR: &str = "assets/maps";
+pub const ARENA_CIRCLE_RESOLUTION: usize = 128; // Corners of the circular arena mesh
+pub const SPAWN_CANDIDATES: usize = 16; // Random spots a new snake may enter at, the safest one wins
+pub const SPAWN_SAFE_EDGE_DISTANCE: f32 = 300.0; // Further from the edge than this counts as equally safe
+pub const SPAWN_PROTECTION_DURATION: f32 = 3.0; // Seconds a new snake cannot be killed by other snakes
+pub const SPAWN_PROTECTION_BLINK_SPEED: f32 = 12.0; // How fast a protected snake's body blinks
+pub const SPAWN_PROTECTION_ALPHA: f32 = 0.4; // Body opacity on the faded half of the blink
+pub const OBSTACLE_COLOR: Color = Color::srgb(0.35, 0.35, 0.35);
+pub const SLOW_ZONE_COLOR: Color = Color::srgb(0.12, 0.12, 0.22);
+pub const SPEED_LANE_COLOR: Color = Color::srgb(0.25, 0.2, 0.08);
+pub const FEEDING_GROUND_COLOR: Color = Color::srgb(0.1, 0.2, 0.1);
+
 /// Screen and map constants
 pub const SCREEN_WIDTH: f32 = 1000.;
 pub const SCREEN_HEIGHT: f32 = 700.;
 
-pub const MAP_RADIUS: f32 = 1000.;
-
 /// Camera constants
 pub const CAM_LERP_FACTOR: f32 = 5.;
 
+/// Kill feed constants
+pub const KILL_FEED_MAX_ENTRIES: usize = 5;
+pub const KILL_FEED_ENTRY_DURATION: f32 = 5.0; // Seconds each entry stays on screen
+pub const KILL_NOTIFICATION_DURATION: f32 = 2.0; // Seconds the "you killed X" message stays on screen
+
+/// Minimap constants
+pub const MINIMAP_SIZE: f32 = 160.; // Pixels
+pub const MINIMAP_DOT_SIZE: f32 = 4.; // Pixels
+pub const MINIMAP_TOP_SNAKES: usize = 10; // How many of the largest snakes are shown
+pub const MINIMAP_REFRESH_INTERVAL: f32 = 0.5; // Seconds between summaries
+pub const MINIMAP_BACKGROUND_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+pub const MINIMAP_BORDER_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+pub const MINIMAP_SNAKE_COLOR: Color = Color::srgb(0.9, 0.3, 0.3);
+pub const MINIMAP_PLAYER_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+pub const MINIMAP_BOUNDARY_DOTS: usize = 48; // Dots tracing the arena's current edge
+pub const MINIMAP_BOUNDARY_DOT_SIZE: f32 = 2.; // Pixels
+pub const MINIMAP_BOUNDARY_COLOR: Color = Color::srgb(0.9, 0.7, 0.3);
+
+/// Free camera constants (replays and spectating)
+pub const FREE_CAMERA_SPEED: f32 = 600.; // Pixels per second at zoom 1
+pub const FREE_CAMERA_ZOOM_STEP: f32 = 0.1; // Zoom change per mouse wheel notch
+
+/// Replay constants
+pub const REPLAY_DIRECTORY: &str = "replays";
+pub const REPLAY_SNAPSHOT_INTERVAL: f32 = 0.1; // Seconds between recorded frames
+pub const REPLAY_FLUSH_FRAMES: usize = 50; // Frames recorded between two writes to disk
+pub const REPLAY_SEEK_STEP: f32 = 5.0; // Seconds skipped per seek key press
+pub const REPLAY_MIN_SPEED: f32 = 0.25;
+pub const REPLAY_MAX_SPEED: f32 = 8.0;
+
 /// Window settings constants
 pub const WINDOW_TITLE: &str = "Slither Wars Client";
 pub const WINDOW_NAME: &str = "slither-wars.app";
 
 // Z-Ordering Constants
 pub const Z_BACKGROUND: f32 = -10.0;
+pub const Z_MAP_ZONES: f32 = -9.0;
+pub const Z_OBSTACLES: f32 = -8.0;
 pub const Z_ORBS: f32 = 0.0;
 pub const Z_BOT_SEGMENTS: f32 = 1.0;
 pub const Z_PLAYER_SEGMENTS: f32 = 2.0;
+pub const Z_EFFECTS: f32 = 0.5;
 
 /// Segment size constants
 pub const SEGMENT_SIZE: f32 = 20.0;
@@ -66,7 +183,10 @@ pub const ORB_RADIUS: f32 = 5.0;
 pub const ORB_VALUE: u32 = 1;
 
 /// Camera zoom constants
-pub const CAMERA_ZOOM_FACTOR: f32 = 0.5; // How much to zoom out per radius increase
 pub const CAMERA_ZOOM_LERP_FACTOR: f32 = 2.0; // How fast the camera zooms
 pub const MIN_CAMERA_ZOOM: f32 = 0.5; // Maximum zoom in
 pub const MAX_CAMERA_ZOOM: f32 = 3.0; // Maximum zoom out
+
+/// Profile constants
+pub const PROFILE_PATH: &str = "profile.ron";
+pub const STATS_SCREEN_Z_INDEX: i32 = 10; // Above the main menu
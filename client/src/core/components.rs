@@ -1,8 +1,10 @@
 use std::collections::VecDeque;
 
 use bevy::prelude::*;
+use slither_common::skins::{skin, SkinId};
 
-use crate::constants::PLAYER_DEFAULT_LENGTH;
+use crate::constants::*;
+use crate::utils::rgb_to_color;
 
 #[derive(Component)]
 pub struct GameWorld;
@@ -10,54 +12,195 @@ pub struct GameWorld;
 #[derive(Component)]
 pub struct Snake
 {
-    pub length: u32,
-    pub segments: VecDeque<Entity>,
+    /// Everything about the snake's size is derived from its mass through [`SnakeGrowth`].
+    ///
+    /// [`SnakeGrowth`]: super::resources::SnakeGrowth
+    pub mass: f32,
+    /// The main colour of the skin, used wherever the snake is shown as a single colour.
     pub color: Color,
+    pub skin: SkinId,
+    pub kills: u32,
+    /// The direction the snake is trying to go, which its eyes look towards.
+    pub steering: Vec2,
+    pub is_boosting: bool,
 }
 
 impl Snake
 {
-    pub fn new(color: Color) -> Self
+    pub fn new(skin_id: SkinId) -> Self
     {
         Self {
-            length: PLAYER_DEFAULT_LENGTH,
-            segments: VecDeque::new(),
-            color,
+            mass: 0.0,
+            color: rgb_to_color(skin(skin_id).primary_color()),
+            skin: skin_id,
+            kills: 0,
+            steering: Vec2::ZERO,
+            is_boosting: false,
         }
     }
+
+    /// The score shown to players is the mass eaten so far.
+    pub fn score(&self) -> u32
+    {
+        self.mass as u32
+    }
 }
 
-#[derive(Component)]
-pub struct Segment
+#[derive(Component, Clone, Debug, Default)]
+pub struct SegmentPositionHistory
 {
-    pub index: u32,
-    pub radius: f32,
+    pub positions: VecDeque<Vec3>,
 }
 
+impl SegmentPositionHistory
+{
+    /// Records the newest head position and forgets positions the body no longer reaches.
+    pub fn record(&mut self, position: Vec3, length: u32)
+    {
+        self.positions.push_front(position);
+
+        let needed = ((length as usize + 1) * POSITIONS_PER_SEGMENT as usize + 1).min(MAX_SEGMENT_HISTORY);
+        self.positions.truncate(needed);
+    }
+}
+
+/// Positions of the body segments behind the head, rebuilt from the position history every frame.
+///
+/// Segments are not entities, so collisions, bots and rendering all read the body from here.
 #[derive(Component, Clone, Debug)]
-pub struct SegmentPositionHistory
+pub struct SnakeBody
 {
-    pub positions: VecDeque<Vec3>,
+    /// Segment positions, the one closest to the head first.
+    pub points: Vec<Vec2>,
+    pub radius: f32,
 }
 
-impl Default for SegmentPositionHistory
+impl Default for SnakeBody
 {
     fn default() -> Self
     {
         Self {
-            positions: VecDeque::new(),
+            points: Vec::new(),
+            radius: PLAYER_DEFAULT_RADIUS,
         }
     }
 }
 
-#[derive(Component)]
-pub struct SnakeSegment
+/// Temporary effects granted by power orbs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PowerUp
 {
-    pub owner: Entity,
+    /// Moves faster.
+    Speed,
+    /// Pulls in orbs from further away.
+    Magnet,
+    /// Passes through other snakes without killing or being killed.
+    Ghost,
+}
+
+impl PowerUp
+{
+    pub const ALL: [PowerUp; 3] = [PowerUp::Speed, PowerUp::Magnet, PowerUp::Ghost];
+
+    /// Seconds the effect lasts.
+    pub fn duration(self) -> f32
+    {
+        match self {
+            PowerUp::Speed => POWER_UP_SPEED_DURATION,
+            PowerUp::Magnet => POWER_UP_MAGNET_DURATION,
+            PowerUp::Ghost => POWER_UP_GHOST_DURATION,
+        }
+    }
+
+    pub fn color(self) -> Color
+    {
+        match self {
+            PowerUp::Speed => POWER_UP_SPEED_COLOR,
+            PowerUp::Magnet => POWER_UP_MAGNET_COLOR,
+            PowerUp::Ghost => POWER_UP_GHOST_COLOR,
+        }
+    }
+
+    pub fn label(self) -> &'static str
+    {
+        match self {
+            PowerUp::Speed => "Speed",
+            PowerUp::Magnet => "Magnet",
+            PowerUp::Ghost => "Ghost",
+        }
+    }
+}
+
+/// The power ups a snake currently has, each with the time it has left.
+#[derive(Component, Clone, Debug, Default)]
+pub struct PowerUps
+{
+    pub active: Vec<(PowerUp, Timer)>,
+}
+
+impl PowerUps
+{
+    /// Starts an effect, or restarts it if the snake already has it.
+    pub fn grant(&mut self, power_up: PowerUp)
+    {
+        match self.active.iter_mut().find(|(active, _)| *active == power_up) {
+            Some((_, timer)) => timer.reset(),
+            None => self
+                .active
+                .push((power_up, Timer::from_seconds(power_up.duration(), TimerMode::Once))),
+        }
+    }
+
+    pub fn has(&self, power_up: PowerUp) -> bool
+    {
+        self.active.iter().any(|(active, _)| *active == power_up)
+    }
+}
+
+/// Shorthand for the optional [`PowerUps`] in queries.
+pub fn has_power_up(power_ups: Option<&PowerUps>, power_up: PowerUp) -> bool
+{
+    power_ups.is_some_and(|power_ups| power_ups.has(power_up))
+}
+
+/// Keeps a newly spawned snake out of collisions with other snakes until the timer runs out.
+#[derive(Component, Clone, Debug)]
+pub struct SpawnProtection(pub Timer);
+
+impl Default for SpawnProtection
+{
+    fn default() -> Self
+    {
+        Self(Timer::from_seconds(SPAWN_PROTECTION_DURATION, TimerMode::Once))
+    }
+}
+
+/// The team a snake plays for in team mode. Teammates can only eat each other with friendly fire on.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Team(pub u8);
+
+impl Team
+{
+    /// Every member wears the team's skin.
+    pub fn skin(self) -> SkinId
+    {
+        TEAM_SKINS[self.0 as usize % TEAM_SKINS.len()]
+    }
+
+    pub fn name(self) -> &'static str
+    {
+        skin(self.skin()).name
+    }
+
+    pub fn color(self) -> Color
+    {
+        rgb_to_color(skin(self.skin()).primary_color())
+    }
 }
 
 #[derive(Component)]
 pub struct DeadSnake
 {
-    pub killer: Entity,
+    /// `None` when the snake died to the arena edge.
+    pub killer: Option<Entity>,
 }
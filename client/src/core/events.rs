@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use slither_common::profile::GameRecord;
+use slither_protocol::messages::ServerMessage;
+
+/// Sent when a snake dies. `killer` is the snake it ran into, if any.
+///
+/// The victim's final stats are copied in because the victim entity is despawned right after.
+#[derive(Event, Clone, Debug)]
+pub struct SnakeDied
+{
+    pub victim: Entity,
+    pub victim_name: String,
+    pub victim_score: u32,
+    pub victim_length: u32,
+    pub victim_kills: u32,
+    pub position: Vec2,
+    pub killer: Option<Entity>,
+    pub killer_name: Option<String>,
+}
+
+/// Sent when a match starts, on joining and again for every rematch. The arena is cleared out for it, so systems
+/// that fill it again run after the one sending this.
+#[derive(Event, Clone, Debug)]
+pub struct MatchStarted;
+
+#[derive(Event, Clone, Debug)]
+pub struct SnakeSpawned
+{
+    pub snake: Entity,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct OrbCollected
+{
+    pub snake: Entity,
+    pub value: u32,
+    pub position: Vec2,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct SnakeGrew
+{
+    pub snake: Entity,
+    /// Length of the snake after growing.
+    pub length: u32,
+}
+
+/// Sent when a snake loses segments, e.g. by boosting, with where the lost tail segments were.
+#[derive(Event, Clone, Debug)]
+pub struct SegmentsRemoved
+{
+    pub snake: Entity,
+    pub positions: Vec<Vec2>,
+    pub radius: f32,
+    pub color: Color,
+}
+
+/// Sent when a game of the local player ends, whether they died or the match did.
+#[derive(Event, Clone, Debug)]
+pub struct GameFinished(pub GameRecord);
+
+/// A message that arrived from the server.
+#[derive(Event, Clone, Debug)]
+pub struct ServerMessageReceived(pub ServerMessage);
+
+#[derive(Event, Clone, Debug)]
+pub struct BoostStarted
+{
+    pub snake: Entity,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct BoostEnded
+{
+    pub snake: Entity,
+}
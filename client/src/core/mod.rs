@@ -1,5 +1,7 @@
 pub mod components;
+pub mod events;
 pub mod resources;
+pub mod states;
 pub mod systems;
 
 use bevy::prelude::*;
@@ -11,17 +13,47 @@ impl Plugin for CorePlugin
 {
     fn build(&self, app: &mut App)
     {
-        app.init_resource::<resources::GlobalGameState>()
-            .add_systems(Startup, (spawn_camera, spawn_game_world))
+        app.init_state::<states::GameState>()
+            .init_resource::<resources::GlobalGameState>()
+            .init_resource::<resources::CameraTarget>()
+            .init_resource::<resources::RenderHandles>()
+            .init_resource::<resources::SelectedSkin>()
+            .init_resource::<resources::OrbMagnet>()
+            .init_resource::<resources::SnakeGrowth>()
+            .init_resource::<resources::ArenaRadius>()
+            .add_event::<events::SnakeDied>()
+            .add_event::<events::SnakeSpawned>()
+            .add_event::<events::MatchStarted>()
+            .add_event::<events::GameFinished>()
+            .add_event::<events::ServerMessageReceived>()
+            .add_event::<events::OrbCollected>()
+            .add_event::<events::SnakeGrew>()
+            .add_event::<events::SegmentsRemoved>()
+            .add_event::<events::BoostStarted>()
+            .add_event::<events::BoostEnded>()
+            .add_systems(Startup, spawn_camera)
             .add_systems(
                 Update,
                 (
                     make_window_visible,
-                    check_snake_collisions,
+                    // Snakes the edge passes over are gone before anything else can hit them
+                    (
+                        update_arena_radius,
+                        update_arena_boundary,
+                        kill_snakes_outside_arena,
+                        check_snake_collisions,
+                    )
+                        .chain(),
                     cleanup_dead_snakes,
-                    orb_collection,
-                    update_segment_sizes,
+                    record_kills,
+                    track_snake_count,
+                    tick_power_ups,
+                    tick_spawn_protection,
+                    (magnetize_orbs, pull_magnetized_orbs, orb_collection).chain(),
+                    update_follow_camera.run_if(camera_has_target),
+                    update_free_camera.run_if(not(camera_has_target)),
                 ),
-            );
+            )
+            .add_systems(PostUpdate, update_snake_bodies);
     }
 }
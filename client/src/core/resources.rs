@@ -1,4 +1,9 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
+use slither_common::config::{ArenaConfig, GameConfig, GrowthCurve, OrbConfig};
+use slither_common::map::ArenaShape;
+use slither_common::skins::SkinId;
 
 use crate::constants::*;
 
@@ -15,7 +20,278 @@ impl Default for GlobalGameState
     {
         Self {
             total_snakes: 0,
-            total_orbs: MAX_ORB_SPAWN_COUNT,
+            total_orbs: OrbConfig::default().max_count,
+        }
+    }
+}
+
+/// Where the arena currently ends. Holds the full size of the map, or closes in on the configured schedule
+/// while the game mode shrinks the arena.
+#[derive(Resource, Clone, Debug)]
+pub struct ArenaRadius
+{
+    /// The map's outline at full size.
+    pub shape: ArenaShape,
+    /// Distance from the centre to the furthest point of the arena right now.
+    pub radius: f32,
+    /// The radius one frame ago, to tell when the edge is closing in.
+    pub previous: f32,
+    /// Seconds since the arena started shrinking, `None` while it keeps its full size.
+    pub shrink_elapsed: Option<f32>,
+}
+
+impl ArenaRadius
+{
+    pub fn is_closing(&self) -> bool
+    {
+        self.radius < self.previous
+    }
+
+    /// How far the arena has shrunk, 1 at full size.
+    pub fn scale(&self) -> f32
+    {
+        self.radius / self.shape.radius()
+    }
+
+    /// Whether a circle of `margin` at `point` is inside the arena.
+    pub fn contains(&self, point: Vec2, margin: f32) -> bool
+    {
+        self.edge_distance(point) >= margin
+    }
+
+    /// Distance from `point` to the current edge, negative outside the arena.
+    pub fn edge_distance(&self, point: Vec2) -> f32
+    {
+        let scale = self.scale();
+        self.shape.edge_distance((point / scale).to_array()) * scale
+    }
+
+    /// Where something of `margin` heading for `to` ends up. It slides along the current edge.
+    pub fn confine(&self, to: Vec2, margin: f32) -> Vec2
+    {
+        if self.contains(to, margin) {
+            return to;
+        }
+        let scale = self.scale();
+        Vec2::from_array(self.shape.confine((to / scale).to_array(), margin / scale)) * scale
+    }
+}
+
+impl Default for ArenaRadius
+{
+    fn default() -> Self
+    {
+        let radius = ArenaConfig::default().map_radius;
+        Self {
+            shape: ArenaShape::Circle(radius),
+            radius,
+            previous: radius,
+            shrink_elapsed: None,
+        }
+    }
+}
+
+/// The snake the camera follows. `None` leaves the camera free to pan and zoom.
+#[derive(Resource, Default)]
+pub struct CameraTarget(pub Option<Entity>);
+
+/// How strongly snake heads pull in nearby orbs. Game modes can tune this.
+#[derive(Resource, Clone, Debug)]
+pub struct OrbMagnet
+{
+    pub base_radius: f32,
+    pub radius_per_snake_radius: f32,
+    pub pull_speed: f32,
+}
+
+impl OrbMagnet
+{
+    /// Bigger snakes reach further.
+    pub fn radius_for(&self, snake_radius: f32) -> f32
+    {
+        self.base_radius + snake_radius * self.radius_per_snake_radius
+    }
+}
+
+impl From<&GameConfig> for OrbMagnet
+{
+    fn from(config: &GameConfig) -> Self
+    {
+        Self {
+            base_radius: config.orbs.magnet_base_radius,
+            radius_per_snake_radius: config.orbs.magnet_radius_per_snake_radius,
+            pull_speed: config.orbs.magnet_pull_speed,
+        }
+    }
+}
+
+impl Default for OrbMagnet
+{
+    fn default() -> Self
+    {
+        Self::from(&GameConfig::default())
+    }
+}
+
+/// Curves deriving a snake's length, radius, speed and camera zoom from its mass. Game modes can tune this.
+#[derive(Resource, Clone, Debug)]
+pub struct SnakeGrowth
+{
+    pub segments: GrowthCurve,
+    pub radius: GrowthCurve,
+    pub speed: GrowthCurve,
+    pub camera_zoom: GrowthCurve,
+}
+
+impl SnakeGrowth
+{
+    /// Number of body segments behind the head.
+    pub fn segment_count(&self, mass: f32) -> u32
+    {
+        self.segments.at(mass) as u32
+    }
+
+    pub fn radius(&self, mass: f32) -> f32
+    {
+        self.radius.at(mass)
+    }
+
+    /// Pixels per second before boosting and power ups.
+    pub fn speed(&self, mass: f32) -> f32
+    {
+        self.speed.at(mass)
+    }
+
+    pub fn camera_zoom(&self, mass: f32) -> f32
+    {
+        self.camera_zoom.at(mass)
+    }
+}
+
+impl From<&GameConfig> for SnakeGrowth
+{
+    fn from(config: &GameConfig) -> Self
+    {
+        Self {
+            segments: config.snake.segments,
+            radius: config.snake.radius,
+            speed: config.snake.speed,
+            camera_zoom: config.camera.zoom,
         }
     }
 }
+
+impl Default for SnakeGrowth
+{
+    fn default() -> Self
+    {
+        Self::from(&GameConfig::default())
+    }
+}
+
+/// The skin picked in the main menu, worn by the player's snake.
+#[derive(Resource, Default)]
+pub struct SelectedSkin(pub SkinId);
+
+/// One unit circle mesh and one material per colour, shared by every segment and orb.
+///
+/// Circles are sized through their transform scale, so asset counts stay flat no matter how many
+/// segments and orbs get spawned over a session.
+#[derive(Resource)]
+pub struct RenderHandles
+{
+    pub circle: Handle<Mesh>,
+    materials: HashMap<[u32; 4], Handle<ColorMaterial>>,
+}
+
+impl RenderHandles
+{
+    /// Returns the shared material for `color`, creating it the first time a colour outside the palette is used.
+    pub fn material(&mut self, materials: &mut Assets<ColorMaterial>, color: Color) -> Handle<ColorMaterial>
+    {
+        self.materials
+            .entry(color_key(color))
+            .or_insert_with(|| materials.add(ColorMaterial::from(color)))
+            .clone()
+    }
+}
+
+impl FromWorld for RenderHandles
+{
+    fn from_world(world: &mut World) -> Self
+    {
+        let circle = world.resource_mut::<Assets<Mesh>>().add(Circle::new(1.0));
+
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        let materials = SNAKE_PALETTE
+            .iter()
+            .map(|&color| (color_key(color), materials.add(ColorMaterial::from(color))))
+            .collect();
+
+        Self { circle, materials }
+    }
+}
+
+fn color_key(color: Color) -> [u32; 4]
+{
+    color.to_srgba().to_f32_array().map(f32::to_bits)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn a_new_snake_starts_at_the_defaults()
+    {
+        // The default config has to agree with the sizes snakes are spawned with
+        let growth = SnakeGrowth::default();
+        assert_eq!(growth.segment_count(0.0), PLAYER_DEFAULT_LENGTH);
+        assert_eq!(growth.radius(0.0), PLAYER_DEFAULT_RADIUS);
+        assert_eq!(growth.camera_zoom(0.0), 1.0);
+        assert!(growth.camera_zoom.min >= MIN_CAMERA_ZOOM && growth.camera_zoom.max <= MAX_CAMERA_ZOOM);
+    }
+
+    #[test]
+    fn gaining_mass_never_makes_a_snake_smaller()
+    {
+        let growth = SnakeGrowth::default();
+        let masses: Vec<f32> = (0..2000).map(|step| step as f32 * 12.5).collect();
+
+        for pair in masses.windows(2) {
+            let (smaller, bigger) = (pair[0], pair[1]);
+            assert!(growth.segment_count(bigger) >= growth.segment_count(smaller));
+            assert!(growth.radius(bigger) >= growth.radius(smaller));
+            assert!(growth.camera_zoom(bigger) >= growth.camera_zoom(smaller));
+            assert!(growth.speed(bigger) <= growth.speed(smaller));
+        }
+    }
+
+    #[test]
+    fn every_orb_value_adds_that_many_segments()
+    {
+        let growth = SnakeGrowth::default();
+        let mut mass = 0.0;
+
+        for value in [ORB_VALUE, MEDIUM_ORB_VALUE, LARGE_ORB_VALUE] {
+            let before = growth.segment_count(mass);
+            mass += value as f32;
+            assert_eq!(growth.segment_count(mass), before + value);
+        }
+    }
+
+    #[test]
+    fn huge_snakes_stay_within_limits()
+    {
+        let growth = SnakeGrowth::default();
+        let mass = 1.0e9;
+
+        assert!(growth.segment_count(mass) as usize * (POSITIONS_PER_SEGMENT as usize) < MAX_SEGMENT_HISTORY);
+        assert_eq!(growth.radius(mass), growth.radius.max);
+        assert_eq!(growth.speed(mass), growth.speed.min);
+        assert_eq!(growth.camera_zoom(mass), growth.camera_zoom.max);
+        // Negative mass never happens, but it must not shrink a snake below its starting size
+        assert_eq!(growth.segment_count(-10.0), PLAYER_DEFAULT_LENGTH);
+    }
+}
@@ -0,0 +1,10 @@
+use bevy::prelude::*;
+
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GameState
+{
+    /// The match keeps running behind the menu while the player picks a skin.
+    #[default]
+    MainMenu,
+    Playing,
+}
@@ -2,34 +2,81 @@ use std::collections::HashSet;
 
 use bevy::core::FrameCount;
 use bevy::core_pipeline::bloom::BloomSettings;
+use bevy::ecs::system::SystemParam;
+use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
 use bevy::sprite::MaterialMesh2dBundle;
+use rand::Rng;
 
 use super::components::*;
-use crate::bot::components::Bot;
+use super::events::*;
+use super::resources::{ArenaRadius, CameraTarget, GlobalGameState, OrbMagnet, RenderHandles, SnakeGrowth};
+use crate::config::resources::Config;
 use crate::constants::*;
-use crate::orb::components::Orb;
-use crate::player::components::Player;
+use crate::map::resources::ArenaFeatures;
+use crate::orb::components::{Orb, OrbBurst, OrbDroppedBy, OrbLifetime, OrbMagnetized, PowerOrb};
+use crate::orb::systems::{orb_radius_for_value, start_orb_suck};
 
-pub fn spawn_game_world(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+/// Moves the arena edge along the shrink schedule, or back to the full size of the map.
+pub fn update_arena_radius(
+    time: Res<Time>,
+    config: Res<Config>,
+    features: Res<ArenaFeatures>,
+    mut arena: ResMut<ArenaRadius>,
 )
 {
-    commands.spawn((
-        GameWorld,
-        Name::new("Map Boundary"),
-        MaterialMesh2dBundle {
-            mesh: meshes.add(Circle::new(MAP_RADIUS)).into(),
-            material: materials.add(Color::srgb(0.1, 0.1, 0.1)),
-            transform: Transform {
-                translation: Vec3::new(0.0, 0.0, Z_BACKGROUND),
-                ..default()
-            },
-            ..default()
-        },
-    ));
+    if features.is_changed() {
+        arena.shape = features.shape.clone();
+    }
+
+    arena.previous = arena.radius;
+
+    if let Some(elapsed) = arena.shrink_elapsed.as_mut() {
+        *elapsed += time.delta_seconds();
+    }
+
+    let full_radius = arena.shape.radius();
+    arena.radius = match arena.shrink_elapsed {
+        Some(elapsed) => config.arena.radius_at(full_radius, elapsed),
+        None => full_radius,
+    };
+}
+
+/// The world mesh is built at the map's full size and scaled down as the arena shrinks.
+pub fn update_arena_boundary(arena: Res<ArenaRadius>, mut world_query: Query<&mut Transform, With<GameWorld>>)
+{
+    let scale = arena.scale();
+    for mut transform in world_query.iter_mut() {
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+}
+
+/// Kills every snake the edge passes over as the arena closes in. Only the part of the body still inside the
+/// arena drops orbs.
+pub fn kill_snakes_outside_arena(
+    mut killer: SnakeKiller,
+    arena: Res<ArenaRadius>,
+    snake_query: Query<(Entity, &Snake, &Transform, &SnakeBody)>,
+)
+{
+    if !arena.is_closing() {
+        return;
+    }
+
+    for (snake_entity, snake, transform, body) in snake_query.iter() {
+        let head = transform.translation.truncate();
+        if arena.contains(head, body.radius) {
+            continue;
+        }
+
+        let inside: Vec<Vec2> = body
+            .points
+            .iter()
+            .copied()
+            .filter(|&point| arena.contains(point, 0.0))
+            .collect();
+        killer.kill(snake_entity, snake, head, &inside, None);
+    }
 }
 
 pub fn spawn_camera(mut commands: Commands)
@@ -46,6 +93,86 @@ pub fn spawn_camera(mut commands: Commands)
     ));
 }
 
+/// Updates the camera to follow the targeted snake in the world, zooming out as it gains mass
+pub fn update_follow_camera(
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+    snake_query: Query<(&Transform, Option<&Snake>), Without<Camera2d>>,
+    camera_target: Res<CameraTarget>,
+    growth: Res<SnakeGrowth>,
+    time: Res<Time>,
+)
+{
+    let Ok((mut camera_transform, mut projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let Some(Ok((target_transform, snake))) = camera_target.0.map(|target| snake_query.get(target)) else {
+        return;
+    };
+
+    // Update camera position with lerp
+    let target_pos = Vec3::new(
+        target_transform.translation.x,
+        target_transform.translation.y,
+        camera_transform.translation.z,
+    );
+    camera_transform.translation = camera_transform
+        .translation
+        .lerp(target_pos, time.delta_seconds() * CAM_LERP_FACTOR);
+
+    let target_scale = snake.map_or(1.0, |snake| growth.camera_zoom(snake.mass));
+
+    // Smoothly interpolate to the target scale
+    let current_scale = projection.scale;
+    projection.scale = lerp(current_scale, target_scale, time.delta_seconds() * CAMERA_ZOOM_LERP_FACTOR);
+}
+
+/// WASD pans the camera and the mouse wheel zooms it, used when the camera has no snake to follow.
+pub fn update_free_camera(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut scroll_events: EventReader<MouseWheel>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+)
+{
+    let Ok((mut camera_transform, mut projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let mut direction = Vec2::ZERO;
+    if keyboard_input.pressed(KeyCode::KeyW) {
+        direction.y += 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::KeyS) {
+        direction.y -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::KeyA) {
+        direction.x -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::KeyD) {
+        direction.x += 1.0;
+    }
+
+    let pan = direction.normalize_or_zero() * FREE_CAMERA_SPEED * projection.scale * time.delta_seconds();
+    camera_transform.translation += pan.extend(0.0);
+
+    for event in scroll_events.read() {
+        projection.scale =
+            (projection.scale * (1.0 - event.y * FREE_CAMERA_ZOOM_STEP)).clamp(MIN_CAMERA_ZOOM, MAX_CAMERA_ZOOM);
+    }
+}
+
+pub fn camera_has_target(camera_target: Res<CameraTarget>) -> bool
+{
+    camera_target.0.is_some()
+}
+
+// Helper function for linear interpolation
+fn lerp(start: f32, end: f32, t: f32) -> f32
+{
+    start + (end - start) * t
+}
+
 /// We use this to avoid the white window that shows up before the GPU is ready to render the app.
 /// This happens so fast the the user will not see it.
 pub fn make_window_visible(mut window: Query<&mut Window>, frames: Res<FrameCount>)
@@ -57,126 +184,176 @@ pub fn make_window_visible(mut window: Query<&mut Window>, frames: Res<FrameCoun
 }
 
 pub fn check_snake_collisions(
-    mut commands: Commands,
-    snake_query: Query<(Entity, &Snake, &Transform)>,
-    segment_query: Query<(Entity, &SnakeSegment, &Transform)>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut snake_killer: SnakeKiller,
+    snake_query: Query<(
+        Entity,
+        &Snake,
+        &Transform,
+        &SnakeBody,
+        Option<&PowerUps>,
+        Option<&Team>,
+        Has<SpawnProtection>,
+    )>,
+    config: Res<Config>,
 )
 {
     let mut processed_deaths: HashSet<Entity> = HashSet::new();
 
-    for (segment_entity, segment, segment_transform) in segment_query.iter() {
-        if processed_deaths.contains(&segment.owner) {
+    for (snake_entity, snake, snake_transform, body, power_ups, team, protected) in snake_query.iter() {
+        // Ghosts and freshly spawned snakes pass through everything
+        if has_power_up(power_ups, PowerUp::Ghost) || protected {
             continue;
         }
 
-        for (other_segment_entity, other_segment, other_transform) in segment_query.iter() {
-            if segment_entity == other_segment_entity || segment.owner == other_segment.owner {
-                continue;
-            }
-
-            if processed_deaths.contains(&other_segment.owner) {
-                continue;
-            }
+        let head = snake_transform.translation.truncate();
 
-            let collision = collide(
-                segment_transform.translation,
-                Vec2::new(SEGMENT_SIZE, SEGMENT_SIZE),
-                other_transform.translation,
-                Vec2::new(SEGMENT_SIZE, SEGMENT_SIZE),
-            );
-
-            if collision.is_some() {
-                if let Ok((snake_entity, snake, _)) = snake_query.get(segment.owner) {
-                    // First, despawn all segments
-                    for &segment_entity in &snake.segments {
-                        commands.entity(segment_entity).despawn_recursive();
+        // A snake dies when its head touches the head or body of another snake
+        let killer = snake_query
+            .iter()
+            .find(
+                |(other_entity, _, other_transform, other_body, other_power_ups, other_team, other_protected)| {
+                    // Without friendly fire teammates pass through each other
+                    if *other_entity == snake_entity
+                        || processed_deaths.contains(other_entity)
+                        || has_power_up(*other_power_ups, PowerUp::Ghost)
+                        || *other_protected
+                        || !config.rules.friendly_fire && team.is_some() && team == *other_team
+                    {
+                        return false;
                     }
 
-                    // Then despawn the snake entity itself
-                    commands
-                        .entity(snake_entity)
-                        .insert(DeadSnake {
-                            killer: other_segment.owner,
-                        })
-                        .remove::<Snake>(); // Remove the Snake component to stop movement systems
-
-                    // Spawn death orbs at the death position
-                    spawn_death_orbs(
-                        &mut commands,
-                        &mut meshes,
-                        &mut materials,
-                        snake.color,
-                        &snake
-                            .segments
-                            .iter()
-                            .map(|&segment_entity| {
-                                if let Ok((_, _, segment_transform)) = segment_query.get(segment_entity) {
-                                    segment_transform.translation
-                                } else {
-                                    Vec3::new(0.0, 0.0, 0.0)
-                                }
-                            })
-                            .collect::<Vec<_>>(),
-                    );
-
-                    processed_deaths.insert(snake_entity);
-                }
-            }
+                    let reach = body.radius + other_body.radius;
+                    std::iter::once(other_transform.translation.truncate())
+                        .chain(other_body.points.iter().copied())
+                        .any(|point| head.distance_squared(point) < reach * reach)
+                },
+            )
+            .map(|(other_entity, ..)| other_entity);
+
+        let Some(killer) = killer else {
+            continue;
+        };
+
+        snake_killer.kill(snake_entity, snake, head, &body.points, Some(killer));
+        processed_deaths.insert(snake_entity);
+    }
+}
+
+/// Kills snakes for any reason: the snake stops moving, drops its death orbs and a [`SnakeDied`] event goes out.
+#[derive(SystemParam)]
+pub struct SnakeKiller<'w, 's>
+{
+    commands: Commands<'w, 's>,
+    death_events: EventWriter<'w, SnakeDied>,
+    name_query: Query<'w, 's, &'static Name>,
+    growth: Res<'w, SnakeGrowth>,
+    config: Res<'w, Config>,
+    render_handles: ResMut<'w, RenderHandles>,
+    materials: ResMut<'w, Assets<ColorMaterial>>,
+}
+
+impl SnakeKiller<'_, '_>
+{
+    /// Kills `victim` with death orbs dropped at `orb_points`. `killer` is `None` when the arena itself did it.
+    pub fn kill(&mut self, victim: Entity, snake: &Snake, head: Vec2, orb_points: &[Vec2], killer: Option<Entity>)
+    {
+        // Remove the Snake component to stop movement systems
+        self.commands.entity(victim).insert(DeadSnake { killer }).remove::<Snake>();
+
+        // Death orbs are worth a share of what the snake had eaten
+        spawn_death_orbs(
+            &mut self.commands,
+            &mut self.render_handles,
+            &mut self.materials,
+            snake.color,
+            &orb_points.iter().map(|point| point.extend(Z_ORBS)).collect::<Vec<_>>(),
+            (snake.mass * self.config.orbs.death_mass_share) as u32,
+            self.config.orbs.death_orb_lifetime,
+        );
+
+        self.death_events.send(SnakeDied {
+            victim,
+            victim_name: snake_name(&self.name_query, victim),
+            victim_score: snake.score(),
+            victim_length: self.growth.segment_count(snake.mass),
+            victim_kills: snake.kills,
+            position: head,
+            killer,
+            killer_name: killer.map(|killer| snake_name(&self.name_query, killer)),
+        });
+    }
+}
+
+pub fn snake_name(name_query: &Query<&Name>, snake: Entity) -> String
+{
+    name_query
+        .get(snake)
+        .map_or_else(|_| "Unknown".to_string(), |name| name.to_string())
+}
+
+/// Credits each kill to the snake that did the killing.
+pub fn record_kills(mut death_events: EventReader<SnakeDied>, mut snake_query: Query<&mut Snake>)
+{
+    for death in death_events.read() {
+        if let Some(Ok(mut killer)) = death.killer.map(|killer| snake_query.get_mut(killer)) {
+            killer.kills += 1;
         }
     }
 }
 
+/// Keeps the number of living snakes up to date, which drives how many orbs are spawned. Counted rather than
+/// tracked through events, as rematches and bot count changes remove snakes without them dying.
+pub fn track_snake_count(snake_query: Query<(), With<Snake>>, mut global_game_state: ResMut<GlobalGameState>)
+{
+    let total_snakes = snake_query.iter().count();
+    if global_game_state.total_snakes != total_snakes {
+        global_game_state.total_snakes = total_snakes;
+    }
+}
+
 fn spawn_death_orbs(
     commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
+    render_handles: &mut RenderHandles,
+    materials: &mut Assets<ColorMaterial>,
     color: Color,
     segment_positions: &[Vec3],
+    total_value: u32,
+    lifetime: f32,
 )
 {
-    // Spawn one orb at each segment position
-    for &position in segment_positions {
+    let mut rng = rand::thread_rng();
+    // Never more orbs than the value covers, so the orbs add up to exactly the snake's share
+    let orb_count = (segment_positions.len() as u32).min(total_value);
+
+    // Spawn the orbs spread evenly along the body, each bursting out in a random direction
+    for i in 0..orb_count {
+        let position = segment_positions[i as usize * segment_positions.len() / orb_count as usize];
+        let from = position.truncate();
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let to = from + Vec2::from_angle(angle) * rng.gen_range(0.0..DEATH_BURST_DISTANCE);
+
+        // Split the value evenly, handing the remainder to the first orbs
+        let value = total_value / orb_count + u32::from(i < total_value % orb_count);
+        let radius = orb_radius_for_value(value);
+
         commands.spawn((
-            Orb {
-                radius: ORB_RADIUS,
-                value: ORB_VALUE,
+            Orb { radius, value },
+            OrbLifetime::new(lifetime),
+            OrbBurst {
+                from,
+                to,
+                timer: Timer::from_seconds(DEATH_BURST_DURATION, TimerMode::Once),
             },
             MaterialMesh2dBundle {
-                mesh: meshes.add(Circle::new(ORB_RADIUS)).into(),
-                material: materials.add(ColorMaterial::from(color)),
-                transform: Transform::from_translation(position),
+                mesh: render_handles.circle.clone().into(),
+                material: render_handles.material(materials, color),
+                transform: Transform::from_translation(position).with_scale(Vec3::splat(radius)),
                 ..default()
             },
         ));
     }
 }
 
-fn collide(position1: Vec3, size1: Vec2, position2: Vec3, size2: Vec2) -> Option<()>
-{
-    // Convert Vec3 to Vec2 by dropping the z component
-    let pos1 = position1.truncate();
-    let pos2 = position2.truncate();
-
-    // Calculate the half-sizes
-    let half_size1 = size1 * 0.5;
-    let half_size2 = size2 * 0.5;
-
-    // Calculate the bounds for each rectangle
-    let min1 = pos1 - half_size1;
-    let max1 = pos1 + half_size1;
-    let min2 = pos2 - half_size2;
-    let max2 = pos2 + half_size2;
-
-    // Check for overlap on both axes
-    if max1.x > min2.x && min1.x < max2.x && max1.y > min2.y && min1.y < max2.y {
-        Some(()) // Collision detected
-    } else {
-        None // No collision
-    }
-}
-
 pub fn cleanup_dead_snakes(mut commands: Commands, dead_snakes: Query<Entity, With<DeadSnake>>)
 {
     for dead_snake in dead_snakes.iter() {
@@ -184,92 +361,447 @@ pub fn cleanup_dead_snakes(mut commands: Commands, dead_snakes: Query<Entity, Wi
     }
 }
 
-pub fn orb_collection(
+/// Lets every free orb inside a snake's magnet radius be claimed by the closest such snake. Orbs still flying out
+/// of a burst are left alone until they land.
+pub fn magnetize_orbs(
     mut commands: Commands,
-    mut snake_query: Query<(Entity, &Transform, &mut Snake, Option<&mut Player>, Option<&mut Bot>)>,
-    orb_query: Query<(Entity, &Transform, &Orb)>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+    magnet: Res<OrbMagnet>,
+    mut orb_query: Query<
+        (Entity, &Transform, Option<&mut OrbDroppedBy>),
+        (With<Orb>, Without<OrbMagnetized>, Without<OrbBurst>),
+    >,
+    snake_query: Query<(Entity, &Transform, &SnakeBody, Option<&PowerUps>), With<Snake>>,
 )
 {
-    for (snake_entity, snake_transform, mut snake, mut player, mut bot) in snake_query.iter_mut() {
-        for (orb_entity, orb_transform, orb) in orb_query.iter() {
-            let collision = collide(
-                snake_transform.translation,
-                Vec2::new(SEGMENT_SIZE * 2.0, SEGMENT_SIZE * 2.0),
-                orb_transform.translation,
-                Vec2::new(ORB_RADIUS * 2.0, ORB_RADIUS * 2.0),
-            );
-
-            if collision.is_some() {
-                commands.entity(orb_entity).despawn_recursive();
-
-                // Update score and calculate new radius
-                let new_radius = if let Some(player) = &mut player {
-                    player.score += orb.value;
-                    calculate_radius(player.score)
-                } else if let Some(ref mut bot) = bot {
-                    bot.score += orb.value;
-                    calculate_radius(bot.score)
-                } else {
-                    PLAYER_DEFAULT_RADIUS
-                };
-
-                // Add new segment with the calculated radius
-                let segment_entity = commands
-                    .spawn((
-                        Segment {
-                            index: snake.length,
-                            radius: new_radius,
-                        },
-                        SnakeSegment { owner: snake_entity },
-                        MaterialMesh2dBundle {
-                            mesh: meshes.add(Circle::new(1.0)).into(),
-                            material: materials.add(ColorMaterial::from(snake.color)),
-                            transform: Transform {
-                                translation: snake_transform.translation,
-                                scale: Vec3::new(new_radius, new_radius, Z_PLAYER_SEGMENTS),
-                                ..default()
-                            },
-                            ..default()
-                        },
-                    ))
-                    .id();
+    let heads: Vec<(Entity, Vec2, f32)> = snake_query
+        .iter()
+        .map(|(entity, transform, body, power_ups)| {
+            (
+                entity,
+                transform.translation.truncate(),
+                magnet_radius(&magnet, body, power_ups),
+            )
+        })
+        .collect();
 
-                snake.segments.push_back(segment_entity);
-                snake.length += orb.value;
+    for (orb_entity, orb_transform, dropped_by) in orb_query.iter_mut() {
+        let position = orb_transform.translation.truncate();
+
+        // Snakes cannot pull their own boost orbs straight back in
+        let mut ignored = None;
+        if let Some(mut dropped_by) = dropped_by {
+            if dropped_by.timer.tick(time.delta()).finished() {
+                commands.entity(orb_entity).remove::<OrbDroppedBy>();
+            } else {
+                ignored = Some(dropped_by.snake);
             }
         }
+
+        let closest = heads
+            .iter()
+            .filter(|&&(snake, ..)| Some(snake) != ignored)
+            .map(|&(snake, head, radius)| (snake, head.distance(position), radius))
+            .filter(|&(_, distance, radius)| distance < radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((owner, ..)) = closest {
+            commands.entity(orb_entity).insert(OrbMagnetized { owner });
+        }
     }
 }
 
-// Add this helper function to calculate radius based on score
-fn calculate_radius(score: u32) -> f32
+/// Moves claimed orbs towards their owner's head, letting them go if the owner died or got away.
+pub fn pull_magnetized_orbs(
+    mut commands: Commands,
+    time: Res<Time>,
+    magnet: Res<OrbMagnet>,
+    mut orb_query: Query<(Entity, &OrbMagnetized, &mut Transform), (With<Orb>, Without<OrbBurst>)>,
+    snake_query: Query<(&Transform, &SnakeBody, Option<&PowerUps>), (With<Snake>, Without<Orb>)>,
+)
 {
-    let stages = score / SCORE_PER_RADIUS_STAGE;
-    MIN_PLAYER_RADIUS + (stages as f32 * RADIUS_GROWTH_PER_STAGE)
+    let step = magnet.pull_speed * time.delta_seconds();
+
+    for (orb_entity, magnetized, mut orb_transform) in orb_query.iter_mut() {
+        let position = orb_transform.translation.truncate();
+
+        let head = snake_query
+            .get(magnetized.owner)
+            .ok()
+            .map(|(transform, body, power_ups)| (transform.translation.truncate(), magnet_radius(&magnet, body, power_ups)))
+            .filter(|&(head, radius)| head.distance(position) < radius * ORB_MAGNET_RELEASE_FACTOR);
+
+        let Some((head, _)) = head else {
+            commands.entity(orb_entity).remove::<OrbMagnetized>();
+            continue;
+        };
+
+        let position = position.move_towards(head, step);
+        orb_transform.translation = position.extend(orb_transform.translation.z);
+    }
 }
 
-// Add a system to update existing segment sizes
-pub fn update_segment_sizes(
-    snake_query: Query<(Entity, Option<&Player>, Option<&Bot>)>,
-    mut segment_query: Query<(&mut Transform, &SnakeSegment)>,
+fn magnet_radius(magnet: &OrbMagnet, body: &SnakeBody, power_ups: Option<&PowerUps>) -> f32
+{
+    let radius = magnet.radius_for(body.radius);
+    if has_power_up(power_ups, PowerUp::Magnet) {
+        radius * POWER_UP_MAGNET_MULTIPLIER
+    } else {
+        radius
+    }
+}
+
+/// Eats claimed orbs once they reach their owner's head.
+pub fn orb_collection(
+    mut commands: Commands,
+    mut snake_query: Query<(&Transform, &SnakeBody, &mut Snake, Option<&mut PowerUps>)>,
+    orb_query: Query<(Entity, &Transform, &Orb, &OrbMagnetized, Option<&PowerOrb>)>,
+    growth: Res<SnakeGrowth>,
+    mut collected_events: EventWriter<OrbCollected>,
+    mut grew_events: EventWriter<SnakeGrew>,
 )
 {
-    for (snake_entity, player, bot) in snake_query.iter() {
-        let radius = if let Some(player) = player {
-            calculate_radius(player.score)
-        } else if let Some(bot) = bot {
-            calculate_radius(bot.score)
-        } else {
+    for (orb_entity, orb_transform, orb, magnetized, power_orb) in orb_query.iter() {
+        let Ok((snake_transform, body, mut snake, power_ups)) = snake_query.get_mut(magnetized.owner) else {
             continue;
         };
 
-        // Update all segments belonging to this snake
-        for (mut transform, segment) in segment_query.iter_mut() {
-            if segment.owner == snake_entity {
-                transform.scale = Vec3::new(radius, radius, transform.scale.z);
+        let position = orb_transform.translation.truncate();
+        if snake_transform.translation.truncate().distance(position) > body.radius + orb.radius {
+            continue;
+        }
+
+        start_orb_suck(&mut commands, orb_entity, orb, position, magnetized.owner);
+
+        snake.mass += orb.value as f32;
+
+        if let (Some(power_orb), Some(mut power_ups)) = (power_orb, power_ups) {
+            power_ups.grant(power_orb.power_up);
+        }
+
+        collected_events.send(OrbCollected {
+            snake: magnetized.owner,
+            value: orb.value,
+            position,
+        });
+        grew_events.send(SnakeGrew {
+            snake: magnetized.owner,
+            length: growth.segment_count(snake.mass),
+        });
+    }
+}
+
+/// Counts down active power ups and drops the ones that ran out.
+pub fn tick_power_ups(time: Res<Time>, mut power_ups_query: Query<&mut PowerUps>)
+{
+    for mut power_ups in power_ups_query.iter_mut() {
+        for (_, timer) in power_ups.active.iter_mut() {
+            timer.tick(time.delta());
+        }
+        power_ups.active.retain(|(_, timer)| !timer.finished());
+    }
+}
+
+/// Lifts spawn protection once it runs out.
+pub fn tick_spawn_protection(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut protection_query: Query<(Entity, &mut SpawnProtection)>,
+)
+{
+    for (entity, mut protection) in protection_query.iter_mut() {
+        if protection.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<SpawnProtection>();
+        }
+    }
+}
+
+/// Rebuilds every snake body from its position history and keeps the head scaled to the radius for its mass.
+pub fn update_snake_bodies(
+    growth: Res<SnakeGrowth>,
+    mut snake_query: Query<(&mut Transform, &SegmentPositionHistory, &Snake, &mut SnakeBody)>,
+)
+{
+    for (mut transform, history, snake, mut body) in snake_query.iter_mut() {
+        body.radius = growth.radius(snake.mass);
+        transform.scale.x = body.radius;
+        transform.scale.y = body.radius;
+
+        body.points.clear();
+        for index in 1..=growth.segment_count(snake.mass) as usize {
+            let Some(position) = history.positions.get(index * POSITIONS_PER_SEGMENT as usize) else {
+                break;
+            };
+            body.points.push(position.truncate());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    #[test]
+    fn an_orb_between_two_snakes_is_only_eaten_once()
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+            .init_resource::<OrbMagnet>()
+            .init_resource::<SnakeGrowth>()
+            .add_event::<OrbCollected>()
+            .add_event::<SnakeGrew>()
+            .add_systems(Update, (magnetize_orbs, pull_magnetized_orbs, orb_collection).chain());
+
+        let snakes: Vec<Entity> = [-20.0, 20.0]
+            .into_iter()
+            .map(|x| {
+                app.world_mut()
+                    .spawn((
+                        Snake::new(0),
+                        SnakeBody::default(),
+                        Transform::from_xyz(x, 0.0, 0.0),
+                        GlobalTransform::default(),
+                    ))
+                    .id()
+            })
+            .collect();
+        app.world_mut().spawn((
+            Orb {
+                radius: ORB_RADIUS,
+                value: ORB_VALUE,
+            },
+            Transform::from_xyz(1.0, 0.0, 0.0),
+        ));
+
+        for _ in 0..10 {
+            app.update();
+        }
+
+        let scores: Vec<u32> = snakes
+            .iter()
+            .map(|&snake| app.world().get::<Snake>(snake).unwrap().score())
+            .collect();
+        // Only the closer snake grows
+        assert_eq!(scores, vec![0, ORB_VALUE]);
+    }
+
+    #[test]
+    fn bursting_orbs_are_not_pulled_in_until_they_land()
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+            .init_resource::<OrbMagnet>()
+            .add_systems(Update, (magnetize_orbs, pull_magnetized_orbs).chain());
+
+        app.world_mut().spawn((
+            Snake::new(0),
+            SnakeBody::default(),
+            Transform::default(),
+            GlobalTransform::default(),
+        ));
+        let orb = app
+            .world_mut()
+            .spawn((
+                Orb {
+                    radius: ORB_RADIUS,
+                    value: ORB_VALUE,
+                },
+                OrbBurst {
+                    from: Vec2::new(5.0, 0.0),
+                    to: Vec2::new(10.0, 0.0),
+                    timer: Timer::from_seconds(1.0, TimerMode::Once),
+                },
+                Transform::from_xyz(5.0, 0.0, 0.0),
+            ))
+            .id();
+
+        app.update();
+        app.update();
+        assert!(app.world().get::<OrbMagnetized>(orb).is_none());
+
+        app.world_mut().entity_mut(orb).remove::<OrbBurst>();
+        app.update();
+        assert!(app.world().get::<OrbMagnetized>(orb).is_some());
+    }
+
+    #[test]
+    fn teammates_only_eat_each_other_with_friendly_fire()
+    {
+        for friendly_fire in [false, true] {
+            let mut config = Config::default();
+            config.rules.friendly_fire = friendly_fire;
+
+            let mut app = App::new();
+            app.add_plugins(MinimalPlugins)
+                .insert_resource(config)
+                .init_resource::<Assets<Mesh>>()
+                .init_resource::<Assets<ColorMaterial>>()
+                .init_resource::<RenderHandles>()
+                .init_resource::<SnakeGrowth>()
+                .add_event::<SnakeDied>()
+                .add_systems(Update, check_snake_collisions);
+
+            let rammer = app
+                .world_mut()
+                .spawn((
+                    Snake::new(0),
+                    SnakeBody::default(),
+                    Team(0),
+                    Transform::from_xyz(0.0, 0.0, 0.0),
+                ))
+                .id();
+            app.world_mut().spawn((
+                Snake::new(0),
+                SnakeBody {
+                    points: vec![Vec2::new(5.0, 0.0)],
+                    ..default()
+                },
+                Team(0),
+                Transform::from_xyz(100.0, 0.0, 0.0),
+            ));
+
+            app.update();
+            assert_eq!(app.world().get::<DeadSnake>(rammer).is_some(), friendly_fire);
+        }
+    }
+
+    #[test]
+    fn freshly_spawned_snakes_cannot_be_killed()
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)))
+            .insert_resource(Config::default())
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .init_resource::<RenderHandles>()
+            .init_resource::<SnakeGrowth>()
+            .add_event::<SnakeDied>()
+            .add_systems(Update, (check_snake_collisions, tick_spawn_protection).chain());
+
+        let newcomer = app
+            .world_mut()
+            .spawn((
+                Snake::new(0),
+                SnakeBody::default(),
+                SpawnProtection::default(),
+                Transform::from_xyz(0.0, 0.0, 0.0),
+            ))
+            .id();
+        // The newcomer spawned with its head on another snake's body
+        app.world_mut().spawn((
+            Snake::new(0),
+            SnakeBody {
+                points: vec![Vec2::new(5.0, 0.0)],
+                ..default()
+            },
+            Transform::from_xyz(100.0, 0.0, 0.0),
+        ));
+
+        app.update();
+        assert!(app.world().get::<DeadSnake>(newcomer).is_none());
+
+        for _ in 0..(SPAWN_PROTECTION_DURATION * 10.0) as usize + 2 {
+            app.update();
+        }
+        assert!(app.world().get::<SpawnProtection>(newcomer).is_none());
+        assert!(app.world().get::<DeadSnake>(newcomer).is_some());
+    }
+
+    #[test]
+    fn the_closing_arena_edge_kills_snakes_it_passes()
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+            .insert_resource(Config::default())
+            .insert_resource(ArenaRadius {
+                shrink_elapsed: Some(1000.0),
+                ..default()
+            })
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .init_resource::<RenderHandles>()
+            .init_resource::<SnakeGrowth>()
+            .init_resource::<ArenaFeatures>()
+            .add_event::<SnakeDied>()
+            .add_systems(Update, (update_arena_radius, kill_snakes_outside_arena).chain());
+
+        let mut spawn_snake = |x: f32| {
+            app.world_mut()
+                .spawn((Snake::new(0), SnakeBody::default(), Transform::from_xyz(x, 0.0, 0.0)))
+                .id()
+        };
+        let inside = spawn_snake(100.0);
+        let outside = spawn_snake(600.0);
+
+        app.update();
+
+        let min_radius = app.world().resource::<Config>().arena.min_radius;
+        assert_eq!(app.world().resource::<ArenaRadius>().radius, min_radius);
+        assert!(app.world().get::<Snake>(inside).is_some());
+        assert!(app
+            .world()
+            .get::<DeadSnake>(outside)
+            .is_some_and(|dead| dead.killer.is_none()));
+    }
+
+    #[test]
+    fn death_orbs_add_up_to_the_dead_snakes_share()
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(Config::default())
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .init_resource::<RenderHandles>()
+            .init_resource::<SnakeGrowth>()
+            .add_event::<SnakeDied>();
+        let share = app.world().resource::<Config>().orbs.death_mass_share;
+
+        // Less value than segments as well as more
+        for mass in [6.0, 100.0] {
+            let victim = app.world_mut().spawn_empty().id();
+            let points: Vec<Vec2> = (0..20).map(|i| Vec2::new(i as f32 * 5.0, 0.0)).collect();
+            app.world_mut().run_system_once(move |mut killer: SnakeKiller| {
+                let mut snake = Snake::new(0);
+                snake.mass = mass;
+                killer.kill(victim, &snake, Vec2::ZERO, &points, None);
+            });
+
+            let mut orb_query = app.world_mut().query::<(Entity, &Orb)>();
+            let orbs: Vec<(Entity, u32)> = orb_query.iter(app.world()).map(|(entity, orb)| (entity, orb.value)).collect();
+            assert_eq!(orbs.iter().map(|(_, value)| value).sum::<u32>(), (mass * share) as u32);
+            assert!(orbs.iter().all(|&(_, value)| value > 0));
+            for (orb, _) in orbs {
+                app.world_mut().despawn(orb);
             }
         }
     }
+
+    #[test]
+    fn snakes_removed_without_dying_leave_the_count()
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<GlobalGameState>()
+            .add_systems(Update, track_snake_count);
+
+        let snakes: Vec<Entity> = (0..3).map(|_| app.world_mut().spawn(Snake::new(0)).id()).collect();
+        app.update();
+        assert_eq!(app.world().resource::<GlobalGameState>().total_snakes, 3);
+
+        app.world_mut().despawn(snakes[0]);
+        app.world_mut().entity_mut(snakes[1]).remove::<Snake>();
+        app.update();
+        assert_eq!(app.world().resource::<GlobalGameState>().total_snakes, 1);
+    }
 }
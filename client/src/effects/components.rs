@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+/// Added to a snake while it boosts, dropping glowing particles behind its head.
+#[derive(Component)]
+pub struct BoostTrail
+{
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct BoostParticle
+{
+    pub velocity: Vec2,
+    pub size: f32,
+    pub lifetime: Timer,
+}
+
+/// A segment lost while boosting, swelling briefly before it shrinks away.
+#[derive(Component)]
+pub struct SegmentPop
+{
+    pub radius: f32,
+    pub timer: Timer,
+}
@@ -0,0 +1,25 @@
+pub mod components;
+pub mod systems;
+
+use bevy::prelude::*;
+use systems::*;
+
+/// Short-lived visual effects that do not affect the simulation, like boost trails and pickup sparks.
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_systems(
+            Update,
+            (
+                (start_boost_trails, stop_boost_trails, spawn_boost_particles).chain(),
+                spawn_pickup_sparks,
+                update_boost_particles,
+                spawn_segment_pops,
+                update_segment_pops,
+            ),
+        );
+    }
+}
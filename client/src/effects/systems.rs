@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+use rand::Rng;
+
+use super::components::*;
+use crate::constants::*;
+use crate::core::components::{SegmentPositionHistory, Snake, SnakeBody};
+use crate::core::events::{BoostEnded, BoostStarted, OrbCollected, SegmentsRemoved};
+use crate::core::resources::RenderHandles;
+use crate::utils::{ease_in_cubic, ease_out_cubic};
+
+pub fn start_boost_trails(mut commands: Commands, mut boost_events: EventReader<BoostStarted>)
+{
+    for boost in boost_events.read() {
+        // The snake may die before the commands apply, so the insert must not assume it is still there
+        if let Some(mut snake) = commands.get_entity(boost.snake) {
+            snake.try_insert(BoostTrail {
+                timer: Timer::from_seconds(BOOST_PARTICLE_INTERVAL, TimerMode::Repeating),
+            });
+        }
+    }
+}
+
+pub fn stop_boost_trails(mut commands: Commands, mut boost_events: EventReader<BoostEnded>)
+{
+    for boost in boost_events.read() {
+        if let Some(mut snake) = commands.get_entity(boost.snake) {
+            snake.remove::<BoostTrail>();
+        }
+    }
+}
+
+pub fn spawn_boost_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut snake_query: Query<(&Transform, &Snake, &SnakeBody, &SegmentPositionHistory, &mut BoostTrail)>,
+    mut render_handles: ResMut<RenderHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+)
+{
+    let mut rng = rand::thread_rng();
+
+    for (transform, snake, body, history, mut trail) in snake_query.iter_mut() {
+        trail.timer.tick(time.delta());
+        if trail.timer.times_finished_this_tick() == 0 {
+            continue;
+        }
+
+        let head = transform.translation.truncate();
+        let heading = history
+            .positions
+            .get(POSITIONS_PER_SEGMENT as usize)
+            .and_then(|previous| (head - previous.truncate()).try_normalize())
+            .unwrap_or(snake.steering);
+
+        // Brighter than the snake itself so the bloom turns the trail into sparks
+        let material = render_handles.material(
+            &mut materials,
+            Color::LinearRgba(snake.color.to_linear() * BOOST_PARTICLE_GLOW),
+        );
+
+        for _ in 0..trail.timer.times_finished_this_tick() {
+            let spread = heading.perp() * rng.gen_range(-0.5..0.5);
+            let size = body.radius * BOOST_PARTICLE_SIZE * rng.gen_range(0.6..1.0);
+
+            commands.spawn((
+                BoostParticle {
+                    velocity: (-heading + spread) * BOOST_PARTICLE_SPEED,
+                    size,
+                    lifetime: Timer::from_seconds(BOOST_PARTICLE_LIFETIME, TimerMode::Once),
+                },
+                MaterialMesh2dBundle {
+                    mesh: render_handles.circle.clone().into(),
+                    material: material.clone(),
+                    transform: Transform::from_translation((head - heading * body.radius).extend(Z_EFFECTS))
+                        .with_scale(Vec3::splat(size)),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+/// Eaten orbs throw off a few sparks in the colour of the snake that ate them, more for bigger orbs.
+pub fn spawn_pickup_sparks(
+    mut commands: Commands,
+    mut collected_events: EventReader<OrbCollected>,
+    snake_query: Query<&Snake>,
+    mut render_handles: ResMut<RenderHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+)
+{
+    let mut rng = rand::thread_rng();
+
+    for collected in collected_events.read() {
+        let Ok(snake) = snake_query.get(collected.snake) else {
+            continue;
+        };
+        let material = render_handles.material(
+            &mut materials,
+            Color::LinearRgba(snake.color.to_linear() * BOOST_PARTICLE_GLOW),
+        );
+
+        for _ in 0..(collected.value * PICKUP_SPARKS_PER_VALUE).min(PICKUP_SPARKS_MAX) {
+            let direction = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU));
+            let size = PICKUP_SPARK_SIZE * rng.gen_range(0.6..1.0);
+
+            commands.spawn((
+                BoostParticle {
+                    velocity: direction * BOOST_PARTICLE_SPEED,
+                    size,
+                    lifetime: Timer::from_seconds(BOOST_PARTICLE_LIFETIME, TimerMode::Once),
+                },
+                MaterialMesh2dBundle {
+                    mesh: render_handles.circle.clone().into(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(collected.position.extend(Z_EFFECTS))
+                        .with_scale(Vec3::splat(size)),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+/// Particles drift away from the snake and shrink until they disappear.
+pub fn update_boost_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particle_query: Query<(Entity, &mut BoostParticle, &mut Transform)>,
+)
+{
+    for (entity, mut particle, mut transform) in particle_query.iter_mut() {
+        if particle.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.0);
+        let size = particle.size * (1.0 - particle.lifetime.fraction());
+        transform.scale = Vec3::new(size, size, 1.0);
+    }
+}
+
+pub fn spawn_segment_pops(
+    mut commands: Commands,
+    mut removed_events: EventReader<SegmentsRemoved>,
+    mut render_handles: ResMut<RenderHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+)
+{
+    for removed in removed_events.read() {
+        let material = render_handles.material(&mut materials, removed.color);
+
+        for &position in &removed.positions {
+            commands.spawn((
+                SegmentPop {
+                    radius: removed.radius,
+                    timer: Timer::from_seconds(SEGMENT_POP_DURATION, TimerMode::Once),
+                },
+                MaterialMesh2dBundle {
+                    mesh: render_handles.circle.clone().into(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(position.extend(Z_EFFECTS))
+                        .with_scale(Vec3::splat(removed.radius)),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+pub fn update_segment_pops(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pop_query: Query<(Entity, &mut SegmentPop, &mut Transform)>,
+)
+{
+    for (entity, mut pop, mut transform) in pop_query.iter_mut() {
+        if pop.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let size = pop.radius * pop_scale(pop.timer.fraction());
+        transform.scale = Vec3::new(size, size, 1.0);
+    }
+}
+
+/// Swells quickly during the first third of the pop, then shrinks to nothing.
+fn pop_scale(t: f32) -> f32
+{
+    const SWELL: f32 = 0.3;
+
+    if t < SWELL {
+        1.0 + SEGMENT_POP_GROWTH * ease_out_cubic(t / SWELL)
+    } else {
+        (1.0 + SEGMENT_POP_GROWTH) * (1.0 - ease_in_cubic((t - SWELL) / (1.0 - SWELL)))
+    }
+}
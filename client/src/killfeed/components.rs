@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct KillFeed;
+
+#[derive(Component)]
+pub struct KillFeedEntry
+{
+    pub timer: Timer,
+}
+
+/// The "you killed X" message shown in the middle of the screen.
+#[derive(Component)]
+pub struct KillNotification
+{
+    pub timer: Timer,
+}
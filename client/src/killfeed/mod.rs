@@ -0,0 +1,23 @@
+pub mod components;
+pub mod systems;
+
+use bevy::prelude::*;
+use systems::*;
+
+pub struct KillFeedPlugin;
+
+impl Plugin for KillFeedPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_systems(Startup, spawn_kill_feed).add_systems(
+            Update,
+            (
+                push_kill_feed_entries,
+                expire_kill_feed_entries,
+                show_kill_notification,
+                hide_kill_notification,
+            ),
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+
+use super::components::*;
+use crate::constants::*;
+use crate::core::events::SnakeDied;
+use crate::player::components::Player;
+
+pub fn spawn_kill_feed(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    // Kill feed along the top of the screen
+    commands.spawn((
+        KillFeed,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+    ));
+
+    // Personal kill notification in the middle of the screen
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(30.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                KillNotification {
+                    timer: Timer::from_seconds(KILL_NOTIFICATION_DURATION, TimerMode::Once),
+                },
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 36.0,
+                        color: LEADERBOARD_COLOR,
+                    },
+                ),
+            ));
+        });
+}
+
+pub fn push_kill_feed_entries(
+    mut commands: Commands,
+    mut death_events: EventReader<SnakeDied>,
+    feed_query: Query<(Entity, Option<&Children>), With<KillFeed>>,
+    player_query: Query<(), With<Player>>,
+    asset_server: Res<AssetServer>,
+)
+{
+    let Ok((feed, entries)) = feed_query.get_single() else {
+        return;
+    };
+
+    let mut entry_count = entries.map_or(0, |entries| entries.len());
+
+    for death in death_events.read() {
+        // Drop the oldest entry to make room
+        if entry_count >= KILL_FEED_MAX_ENTRIES {
+            if let Some(&oldest) = entries.and_then(|entries| entries.first()) {
+                commands.entity(oldest).despawn_recursive();
+            }
+        } else {
+            entry_count += 1;
+        }
+
+        let involves_player =
+            player_query.contains(death.victim) || death.killer.is_some_and(|killer| player_query.contains(killer));
+        let message = match &death.killer_name {
+            Some(killer_name) => format!("{} was eaten by {}", death.victim_name, killer_name),
+            None => format!("{} died", death.victim_name),
+        };
+
+        commands.entity(feed).with_children(|parent| {
+            parent.spawn((
+                KillFeedEntry {
+                    timer: Timer::from_seconds(KILL_FEED_ENTRY_DURATION, TimerMode::Once),
+                },
+                TextBundle::from_section(
+                    message,
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 18.0,
+                        color: if involves_player { LEADERBOARD_COLOR } else { TEXT_COLOR },
+                    },
+                ),
+            ));
+        });
+    }
+}
+
+/// Fades out kill feed entries during their last second and removes them once they expire.
+pub fn expire_kill_feed_entries(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entry_query: Query<(Entity, &mut KillFeedEntry, &mut Text)>,
+)
+{
+    for (entity, mut entry, mut text) in entry_query.iter_mut() {
+        entry.timer.tick(time.delta());
+
+        if entry.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let alpha = entry.timer.remaining_secs().min(1.0);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_alpha(alpha);
+        }
+    }
+}
+
+pub fn show_kill_notification(
+    mut death_events: EventReader<SnakeDied>,
+    player_query: Query<(), With<Player>>,
+    mut notification_query: Query<(&mut KillNotification, &mut Text)>,
+)
+{
+    let Ok((mut notification, mut text)) = notification_query.get_single_mut() else {
+        return;
+    };
+
+    for death in death_events.read() {
+        if death.killer.is_some_and(|killer| player_query.contains(killer)) {
+            text.sections[0].value = format!("You killed {}!", death.victim_name);
+            notification.timer.reset();
+        }
+    }
+}
+
+pub fn hide_kill_notification(time: Res<Time>, mut notification_query: Query<(&mut KillNotification, &mut Text)>)
+{
+    let Ok((mut notification, mut text)) = notification_query.get_single_mut() else {
+        return;
+    };
+
+    if notification.timer.tick(time.delta()).just_finished() {
+        text.sections[0].value.clear();
+    }
+}
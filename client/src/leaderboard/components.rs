@@ -3,6 +3,10 @@ use bevy::prelude::*;
 #[derive(Component)]
 pub struct Leaderboard;
 
+/// The top lists shown on the death screen.
+#[derive(Component)]
+pub struct RankedListsText;
+
 #[derive(Component)]
 pub struct LeaderboardEntry
 {
@@ -1,7 +1,9 @@
 pub mod components;
+pub mod resources;
 pub mod systems;
 
 use bevy::prelude::*;
+use resources::RankedLeaderboards;
 use systems::*;
 
 pub struct LeaderboardPlugin;
@@ -10,7 +12,14 @@ impl Plugin for LeaderboardPlugin
 {
     fn build(&self, app: &mut App)
     {
-        app.add_systems(Startup, spawn_leaderboard)
-            .add_systems(Update, update_leaderboard);
+        app.init_resource::<RankedLeaderboards>()
+            .add_systems(Startup, spawn_leaderboard)
+            .add_systems(
+                Update,
+                (
+                    update_leaderboard,
+                    (add_ranked_lists_to_death_screen, update_death_screen_ranked_lists).chain(),
+                ),
+            );
     }
 }
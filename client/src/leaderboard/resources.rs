@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+use slither_common::ranking::Leaderboards;
+
+/// The daily, weekly and all-time top lists the server sent, shown on the death screen and between rounds. `None`
+/// when playing offline.
+#[derive(Resource, Default)]
+pub struct RankedLeaderboards(pub Option<Leaderboards>);
@@ -1,9 +1,15 @@
 use bevy::prelude::*;
+use slither_common::ranking::{LeaderboardEntry, Leaderboards};
 
 use super::components::*;
+use super::resources::RankedLeaderboards;
 use crate::bot::components::Bot;
+use crate::config::resources::Config;
 use crate::constants::*;
-use crate::player::components::Player;
+use crate::core::components::{Snake, Team};
+use crate::mode::resources::MatchState;
+use crate::mode::systems::team_scores;
+use crate::player::components::{DeathScreen, Player};
 
 pub fn spawn_leaderboard(mut commands: Commands, asset_server: Res<AssetServer>)
 {
@@ -47,26 +53,42 @@ pub fn spawn_leaderboard(mut commands: Commands, asset_server: Res<AssetServer>)
 pub fn update_leaderboard(
     mut commands: Commands,
     leaderboard_query: Query<Entity, With<Leaderboard>>,
-    player_query: Query<(&Player, &Name)>,
-    bot_query: Query<&Bot>,
+    player_query: Query<(&Snake, &Name), With<Player>>,
+    bot_query: Query<(&Snake, &Name), With<Bot>>,
+    team_query: Query<(&Snake, &Team)>,
+    config: Res<Config>,
+    match_state: Res<MatchState>,
+    ranked: Res<RankedLeaderboards>,
     asset_server: Res<AssetServer>,
 )
 {
-    // Get all scores and names
-    let mut scores: Vec<(String, u32)> = Vec::new();
+    // In team matches the teams are ranked by their combined score above the snakes
+    let mut teams: Vec<(Team, u32)> = if team_query.is_empty() {
+        Vec::new()
+    } else {
+        team_scores(team_query.iter(), config.rules.team_count)
+            .into_iter()
+            .enumerate()
+            .map(|(team, score)| (Team(team as u8), score))
+            .collect()
+    };
+    teams.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+
+    // Get all scores, kills and names
+    let mut scores: Vec<(String, u32, u32)> = Vec::new();
 
     // Add player scores
-    for (player, name) in player_query.iter() {
-        scores.push((name.to_string(), player.score));
+    for (snake, name) in player_query.iter() {
+        scores.push((name.to_string(), snake.score(), snake.kills));
     }
 
     // Add bot scores
-    for (i, bot) in bot_query.iter().enumerate() {
-        scores.push((format!("Bot {}", i + 1), bot.score));
+    for (snake, name) in bot_query.iter() {
+        scores.push((name.to_string(), snake.score(), snake.kills));
     }
 
     // Sort scores in descending order
-    scores.sort_by(|a, b| b.1.cmp(&a.1));
+    scores.sort_by_key(|(_, score, _)| std::cmp::Reverse(*score));
 
     // Update the leaderboard UI
     if let Ok(leaderboard_entity) = leaderboard_query.get_single() {
@@ -89,11 +111,28 @@ pub fn update_leaderboard(
                 }),
             );
 
+            for (team, score) in &teams {
+                parent.spawn(
+                    TextBundle::from_section(
+                        format!("{} team - {}", team.name(), score),
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 18.0,
+                            color: team.color(),
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(5.0)),
+                        ..default()
+                    }),
+                );
+            }
+
             // Spawn entries
-            for (i, (name, score)) in scores.iter().take(10).enumerate() {
+            for (i, (name, score, kills)) in scores.iter().take(10).enumerate() {
                 parent.spawn(
                     TextBundle::from_section(
-                        format!("{}. {} - {}", i + 1, name, score),
+                        format!("{}. {} - {} ({} kills)", i + 1, name, score, kills),
                         TextStyle {
                             font: asset_server.load("fonts/FiraSans-Bold.ttf"),
                             font_size: 16.0,
@@ -106,6 +145,96 @@ pub fn update_leaderboard(
                     }),
                 );
             }
+
+            // Between rounds the top lists follow the match standings
+            let Some(boards) = ranked.0.as_ref().filter(|_| match_state.is_over()) else {
+                return;
+            };
+            parent.spawn(TextBundle::from_sections(ranked_list_sections(boards, &asset_server)));
         });
     }
 }
+
+/// The daily, weekly and all-time lists as text, each under its own title.
+fn ranked_list_sections(boards: &Leaderboards, asset_server: &AssetServer) -> Vec<TextSection>
+{
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let title_style = TextStyle {
+        font: font.clone(),
+        font_size: 18.0,
+        color: Color::WHITE,
+    };
+    let entry_style = TextStyle {
+        font,
+        font_size: 14.0,
+        color: LEADERBOARD_ENTRY_COLOR,
+    };
+
+    let lists = [
+        ("Today", &boards.daily),
+        ("This week", &boards.weekly),
+        ("All time", &boards.all_time),
+    ];
+    let mut sections = Vec::new();
+    for (title, entries) in lists {
+        let gap = if sections.is_empty() { "" } else { "\n" };
+        sections.push(TextSection::new(format!("{gap}{title}\n"), title_style.clone()));
+        for (i, LeaderboardEntry { name, score }) in entries.iter().take(RANKED_LIST_SHOWN).enumerate() {
+            sections.push(TextSection::new(
+                format!("{}. {} - {}\n", i + 1, name, score),
+                entry_style.clone(),
+            ));
+        }
+    }
+    sections
+}
+
+/// Puts the top lists next to the stats on the death screen.
+pub fn add_ranked_lists_to_death_screen(
+    mut commands: Commands,
+    death_screen_query: Query<Entity, Added<DeathScreen>>,
+    ranked: Res<RankedLeaderboards>,
+    asset_server: Res<AssetServer>,
+)
+{
+    for death_screen in death_screen_query.iter() {
+        let sections = ranked
+            .0
+            .as_ref()
+            .map_or_else(Vec::new, |boards| ranked_list_sections(boards, &asset_server));
+        commands.entity(death_screen).with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::left(Val::Px(20.0)),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(BLACK_COLOR.with_alpha(0.8)),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((RankedListsText, TextBundle::from_sections(sections)));
+                });
+        });
+    }
+}
+
+/// The lists usually arrive from the server after the death screen opened.
+pub fn update_death_screen_ranked_lists(
+    ranked: Res<RankedLeaderboards>,
+    mut text_query: Query<&mut Text, With<RankedListsText>>,
+    asset_server: Res<AssetServer>,
+)
+{
+    if !ranked.is_changed() {
+        return;
+    }
+    let Some(boards) = ranked.0.as_ref() else {
+        return;
+    };
+
+    for mut text in text_query.iter_mut() {
+        text.sections = ranked_list_sections(boards, &asset_server);
+    }
+}
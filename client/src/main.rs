@@ -1,16 +1,31 @@
 #![allow(unused_mut)]
 #![allow(dead_code)]
 #![allow(unused_variables)]
+#![allow(clippy::type_complexity)]
+#![allow(clippy::too_many_arguments)]
 
+mod config;
 mod constants;
 mod core;
 mod utils;
 
 mod bot;
+mod effects;
+mod killfeed;
 mod leaderboard;
+mod map;
+mod menu;
+mod minimap;
+mod mode;
+mod network;
 mod orb;
 mod player;
+mod profile;
+mod replay;
+mod snake_render;
+mod spectator;
 
+use std::path::PathBuf;
 use std::time::Duration;
 
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
@@ -24,33 +39,94 @@ use crate::core::CorePlugin;
 
 fn main()
 {
-    App::new()
-        .insert_resource(WinitSettings {
-            focused_mode: bevy::winit::UpdateMode::Continuous,
-            unfocused_mode: bevy::winit::UpdateMode::reactive_low_power(Duration::from_secs(10)),
-        })
-        .add_plugins((
-            DefaultPlugins.set(WindowPlugin {
-                primary_window: Some(Window {
-                    title: WINDOW_TITLE.into(),
-                    name: Some(WINDOW_NAME.into()),
-                    resolution: (SCREEN_WIDTH, SCREEN_HEIGHT).into(),
-                    present_mode: PresentMode::AutoVsync,
-                    fit_canvas_to_parent: true,
-                    prevent_default_event_handling: false,
-                    visible: false,
-                    ..default()
-                }),
+    let mut app = App::new();
+
+    app.insert_resource(WinitSettings {
+        focused_mode: bevy::winit::UpdateMode::Continuous,
+        unfocused_mode: bevy::winit::UpdateMode::reactive_low_power(Duration::from_secs(10)),
+    })
+    .add_plugins((
+        DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(Window {
+                title: WINDOW_TITLE.into(),
+                name: Some(WINDOW_NAME.into()),
+                resolution: (SCREEN_WIDTH, SCREEN_HEIGHT).into(),
+                present_mode: PresentMode::AutoVsync,
+                fit_canvas_to_parent: true,
+                prevent_default_event_handling: false,
+                visible: false,
                 ..default()
             }),
-            LogDiagnosticsPlugin::default(),
-            FrameTimeDiagnosticsPlugin,
-            FpsOverlayPlugin::default(),
-            CorePlugin,
-            player::PlayerPlugin,
-            bot::BotPlugin,
-            orb::OrbPlugin,
-            leaderboard::LeaderboardPlugin,
-        ))
-        .run();
+            ..default()
+        }),
+        LogDiagnosticsPlugin::default(),
+        FrameTimeDiagnosticsPlugin,
+        FpsOverlayPlugin::default(),
+        config::ConfigPlugin {
+            path: PathBuf::from(arg_value("--config").unwrap_or_else(|| CONFIG_PATH.to_string())),
+        },
+        CorePlugin,
+        map::MapPlugin,
+        snake_render::SnakeRenderPlugin,
+    ));
+
+    match arg_value("--replay") {
+        Some(path) => {
+            app.add_plugins(replay::ReplayPlaybackPlugin {
+                path: PathBuf::from(path),
+            });
+        }
+        None => {
+            app.add_plugins((
+                bot::BotPlugin,
+                effects::EffectsPlugin,
+                orb::OrbPlugin,
+                leaderboard::LeaderboardPlugin,
+                minimap::MinimapPlugin,
+                mode::ModePlugin,
+                killfeed::KillFeedPlugin,
+                replay::ReplayRecorderPlugin,
+                spectator::SpectatorPlugin,
+            ));
+
+            // Spectators join the match without a snake of their own
+            if has_flag("--spectate") {
+                app.add_systems(Startup, spectator::systems::skip_main_menu);
+            } else {
+                app.add_plugins((
+                    menu::MenuPlugin,
+                    player::PlayerPlugin,
+                    profile::ProfilePlugin {
+                        path: PathBuf::from(arg_value("--profile").unwrap_or_else(|| PROFILE_PATH.to_string())),
+                    },
+                ));
+            }
+
+            if let Some(address) = arg_value("--server") {
+                app.add_plugins(network::NetworkPlugin {
+                    address,
+                    name: arg_value("--name"),
+                });
+            }
+        }
+    }
+
+    app.run();
+}
+
+/// Returns the value after a command line option, e.g. the file in `--replay <path>`.
+fn arg_value(name: &str) -> Option<String>
+{
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+    }
+    None
+}
+
+fn has_flag(name: &str) -> bool
+{
+    std::env::args().skip(1).any(|arg| arg == name)
 }
@@ -0,0 +1,5 @@
+use bevy::prelude::*;
+
+/// An obstacle or zone drawn in the arena, replaced whenever a new map loads.
+#[derive(Component)]
+pub struct MapFeature;
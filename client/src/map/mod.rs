@@ -0,0 +1,35 @@
+pub mod components;
+pub mod resources;
+pub mod systems;
+
+use bevy::prelude::*;
+use resources::*;
+use systems::*;
+
+use crate::config::resources::Config;
+use crate::core::systems::{check_snake_collisions, kill_snakes_outside_arena};
+
+/// Loads the map named in the config and builds the arena from it: its shape, background, obstacles and zones.
+pub struct MapPlugin;
+
+impl Plugin for MapPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<ArenaFeatures>()
+            .add_systems(
+                PreUpdate,
+                (
+                    load_map.run_if(resource_changed::<Config>),
+                    (spawn_game_world, spawn_map_features).run_if(resource_changed::<ArenaFeatures>),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                kill_snakes_hitting_obstacles
+                    .after(kill_snakes_outside_arena)
+                    .before(check_snake_collisions),
+            );
+    }
+}
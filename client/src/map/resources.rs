@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+use slither_common::config::ArenaConfig;
+use slither_common::map::ArenaMap;
+
+/// The loaded map. An empty circle when the config names no map or it failed to load.
+#[derive(Resource, Clone, PartialEq, Deref, DerefMut)]
+pub struct ArenaFeatures(pub ArenaMap);
+
+impl Default for ArenaFeatures
+{
+    fn default() -> Self
+    {
+        Self(ArenaMap::circle(ArenaConfig::default().map_radius))
+    }
+}
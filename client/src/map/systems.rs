@@ -0,0 +1,422 @@
+use std::path::Path;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::sprite::MaterialMesh2dBundle;
+use rand::seq::SliceRandom;
+use slither_common::map::{ArenaMap, ArenaShape, Background};
+
+use super::components::*;
+use super::resources::*;
+use crate::config::resources::Config;
+use crate::constants::*;
+use crate::core::components::{GameWorld, Snake, SnakeBody};
+use crate::core::resources::{ArenaRadius, RenderHandles};
+use crate::core::systems::SnakeKiller;
+use crate::utils::{generate_random_position_within_radius, rgb_to_color};
+
+/// Loads the map the config names. A map that fails to load leaves an empty circular arena rather than a half
+/// built one.
+pub fn load_map(config: Res<Config>, mut features: ResMut<ArenaFeatures>)
+{
+    let empty_arena = || ArenaMap::circle(config.arena.map_radius);
+    let map = match &config.arena.map {
+        Some(name) => {
+            let path = Path::new(MAPS_DIR).join(name);
+            ArenaMap::load(&path).unwrap_or_else(|err| {
+                error!("Failed to load map {}: {err}", path.display());
+                empty_arena()
+            })
+        }
+        None => empty_arena(),
+    };
+
+    features.set_if_neq(ArenaFeatures(map));
+}
+
+/// Builds the arena floor from the map's shape and background.
+pub fn spawn_game_world(
+    mut commands: Commands,
+    features: Res<ArenaFeatures>,
+    world_query: Query<Entity, With<GameWorld>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>,
+)
+{
+    for entity in world_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let material = match &features.background {
+        Background::Color(color) => ColorMaterial::from(rgb_to_color(*color)),
+        Background::Texture(path) => ColorMaterial {
+            texture: Some(asset_server.load(path.clone())),
+            ..default()
+        },
+    };
+
+    commands.spawn((
+        GameWorld,
+        Name::new("Map Boundary"),
+        MaterialMesh2dBundle {
+            mesh: meshes.add(arena_mesh(&features.shape)).into(),
+            material: materials.add(material),
+            transform: Transform::from_xyz(0.0, 0.0, Z_BACKGROUND),
+            ..default()
+        },
+    ));
+}
+
+/// A mesh of the arena at full size, with UVs that stretch a texture over its bounding square.
+fn arena_mesh(shape: &ArenaShape) -> Mesh
+{
+    let corners = match shape {
+        ArenaShape::Circle(radius) => return Circle::new(*radius).mesh().resolution(ARENA_CIRCLE_RESOLUTION).build(),
+        ArenaShape::Polygon(corners) => corners,
+    };
+
+    let radius = shape.radius();
+    let positions: Vec<[f32; 3]> = corners.iter().map(|&[x, y]| [x, y, 0.0]).collect();
+    let uvs: Vec<[f32; 2]> = corners
+        .iter()
+        .map(|&[x, y]| [0.5 + x / (2.0 * radius), 0.5 - y / (2.0 * radius)])
+        .collect();
+    // Maps only allow convex polygons, so a fan from the first corner covers them
+    let indices: Vec<u32> = (1..corners.len() as u32 - 1)
+        .flat_map(|index| [0, index, index + 1])
+        .collect();
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; corners.len()])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Picks where new snakes enter, keeping them away from other snakes and the arena edge.
+#[derive(SystemParam)]
+pub struct SpawnSelector<'w, 's>
+{
+    features: Res<'w, ArenaFeatures>,
+    arena: Res<'w, ArenaRadius>,
+    snake_query: Query<'w, 's, (&'static Transform, &'static SnakeBody), With<Snake>>,
+}
+
+impl SpawnSelector<'_, '_>
+{
+    /// The safest spawn spot right now. `also_avoid` holds spots handed out earlier in the same frame, whose
+    /// snakes are not in the world yet.
+    pub fn pick(&self, also_avoid: &[Vec2]) -> Vec2
+    {
+        let occupied: Vec<Vec2> = self
+            .snake_query
+            .iter()
+            .flat_map(|(transform, body)| {
+                std::iter::once(transform.translation.truncate()).chain(body.points.iter().copied())
+            })
+            .chain(also_avoid.iter().copied())
+            .collect();
+
+        safest_spawn(&spawn_candidates(&self.features, &self.arena), &occupied, &self.arena).unwrap_or_else(|| {
+            warn!("No free spawn spot left in the arena, spawning in the centre");
+            Vec2::ZERO
+        })
+    }
+}
+
+/// The map's spawn points, or random spots inside the arena if it has none. Only spots clear of the edge and of
+/// obstacles are candidates, and the centre is the last resort when no other spot is.
+fn spawn_candidates(features: &ArenaFeatures, arena: &ArenaRadius) -> Vec<Vec2>
+{
+    let is_clear = |position: &Vec2| {
+        arena.contains(*position, PLAYER_DEFAULT_RADIUS)
+            && !features.hits_obstacle(position.to_array(), PLAYER_DEFAULT_RADIUS)
+    };
+
+    // Spawn points move in with the edge as the arena shrinks, which can take them into an obstacle. Shuffled so
+    // equally safe points take turns.
+    let mut points: Vec<Vec2> = features
+        .spawn_points
+        .iter()
+        .map(|&point| Vec2::from_array(point) * arena.scale())
+        .filter(is_clear)
+        .collect();
+    if !points.is_empty() {
+        points.shuffle(&mut rand::thread_rng());
+        return points;
+    }
+
+    let mut candidates: Vec<Vec2> = (0..SPAWN_CANDIDATES)
+        .map(|_| generate_random_position_within_radius(arena.radius))
+        .filter(is_clear)
+        .collect();
+    if candidates.is_empty() && is_clear(&Vec2::ZERO) {
+        candidates.push(Vec2::ZERO);
+    }
+    candidates
+}
+
+/// The candidate furthest from both the nearest snake and the edge. Spots at least
+/// [`SPAWN_SAFE_EDGE_DISTANCE`] from both count as equally safe, so an empty arena does not stack every snake
+/// in the centre. `None` without candidates.
+pub fn safest_spawn(candidates: &[Vec2], occupied: &[Vec2], arena: &ArenaRadius) -> Option<Vec2>
+{
+    let safety = |candidate: Vec2| {
+        let nearest_snake = occupied
+            .iter()
+            .map(|point| point.distance(candidate))
+            .fold(f32::INFINITY, f32::min);
+        nearest_snake
+            .min(arena.edge_distance(candidate))
+            .min(SPAWN_SAFE_EDGE_DISTANCE)
+    };
+
+    candidates.iter().copied().max_by(|a, b| safety(*a).total_cmp(&safety(*b)))
+}
+
+pub fn spawn_map_features(
+    mut commands: Commands,
+    features: Res<ArenaFeatures>,
+    feature_query: Query<Entity, With<MapFeature>>,
+    mut render_handles: ResMut<RenderHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+)
+{
+    for entity in feature_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for ground in &features.feeding_grounds {
+        spawn_circle(
+            &mut commands,
+            &mut render_handles,
+            &mut materials,
+            ground.center,
+            ground.radius,
+            FEEDING_GROUND_COLOR,
+            Z_MAP_ZONES,
+        );
+    }
+    for zone in &features.slow_zones {
+        spawn_circle(
+            &mut commands,
+            &mut render_handles,
+            &mut materials,
+            zone.center,
+            zone.radius,
+            SLOW_ZONE_COLOR,
+            Z_MAP_ZONES,
+        );
+    }
+    for obstacle in &features.obstacles {
+        spawn_circle(
+            &mut commands,
+            &mut render_handles,
+            &mut materials,
+            obstacle.center,
+            obstacle.radius,
+            OBSTACLE_COLOR,
+            Z_OBSTACLES,
+        );
+    }
+
+    if features.speed_lanes.is_empty() {
+        return;
+    }
+
+    // Lanes are a unit square stretched between their ends, with round caps so they match how `contains` works
+    let square = meshes.add(Rectangle::new(1.0, 1.0));
+    for lane in &features.speed_lanes {
+        let from = Vec2::from_array(lane.from);
+        let to = Vec2::from_array(lane.to);
+        let along = to - from;
+
+        commands.spawn((
+            MapFeature,
+            MaterialMesh2dBundle {
+                mesh: square.clone().into(),
+                material: render_handles.material(&mut materials, SPEED_LANE_COLOR),
+                transform: Transform {
+                    translation: ((from + to) * 0.5).extend(Z_MAP_ZONES),
+                    rotation: Quat::from_rotation_z(along.y.atan2(along.x)),
+                    scale: Vec3::new(along.length(), lane.width, 1.0),
+                },
+                ..default()
+            },
+        ));
+        for end in [lane.from, lane.to] {
+            spawn_circle(
+                &mut commands,
+                &mut render_handles,
+                &mut materials,
+                end,
+                lane.width * 0.5,
+                SPEED_LANE_COLOR,
+                Z_MAP_ZONES,
+            );
+        }
+    }
+}
+
+fn spawn_circle(
+    commands: &mut Commands,
+    render_handles: &mut RenderHandles,
+    materials: &mut Assets<ColorMaterial>,
+    center: [f32; 2],
+    radius: f32,
+    color: Color,
+    z: f32,
+)
+{
+    commands.spawn((
+        MapFeature,
+        MaterialMesh2dBundle {
+            mesh: render_handles.circle.clone().into(),
+            material: render_handles.material(materials, color),
+            transform: Transform::from_translation(Vec2::from_array(center).extend(z))
+                .with_scale(Vec3::new(radius, radius, 1.0)),
+            ..default()
+        },
+    ));
+}
+
+/// Obstacles are solid: a snake whose head touches one dies, ghost or not.
+pub fn kill_snakes_hitting_obstacles(
+    mut killer: SnakeKiller,
+    features: Res<ArenaFeatures>,
+    snake_query: Query<(Entity, &Snake, &Transform, &SnakeBody)>,
+)
+{
+    if features.obstacles.is_empty() {
+        return;
+    }
+
+    for (snake_entity, snake, transform, body) in snake_query.iter() {
+        let head = transform.translation.truncate();
+        if !features.hits_obstacle(head.to_array(), body.radius) {
+            continue;
+        }
+
+        // Orbs cannot be eaten from inside an obstacle
+        let outside: Vec<Vec2> = body
+            .points
+            .iter()
+            .copied()
+            .filter(|point| !features.hits_obstacle(point.to_array(), 0.0))
+            .collect();
+        killer.kill(snake_entity, snake, head, &outside, None);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use slither_common::map::{ArenaMap, Obstacle};
+
+    use super::*;
+    use crate::core::components::DeadSnake;
+    use crate::core::events::SnakeDied;
+    use crate::core::resources::SnakeGrowth;
+
+    #[test]
+    fn shipped_maps_are_valid()
+    {
+        let maps_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(MAPS_DIR);
+        for entry in std::fs::read_dir(maps_dir).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(err) = ArenaMap::load(&path) {
+                panic!("{}: {err}", path.display());
+            }
+        }
+    }
+
+    #[test]
+    fn snakes_spawn_away_from_other_snakes_and_the_edge()
+    {
+        let arena = ArenaRadius::default();
+        let crowded = Vec2::new(100.0, 0.0);
+        let at_the_edge = Vec2::new(0.0, arena.radius - 20.0);
+        let open = Vec2::new(-400.0, 0.0);
+        let occupied = [Vec2::new(120.0, 0.0)];
+
+        assert_eq!(safest_spawn(&[crowded, at_the_edge, open], &occupied, &arena), Some(open));
+        assert_eq!(safest_spawn(&[], &occupied, &arena), None);
+    }
+
+    #[test]
+    fn spawn_points_pulled_into_an_obstacle_are_skipped()
+    {
+        let mut map = ArenaMap::circle(1000.0);
+        map.spawn_points = vec![[600.0, 0.0], [0.0, 600.0]];
+        map.obstacles = vec![Obstacle {
+            center: [300.0, 0.0],
+            radius: 40.0,
+        }];
+        let features = ArenaFeatures(map);
+        let half_size = ArenaRadius {
+            shape: features.shape.clone(),
+            radius: 500.0,
+            previous: 500.0,
+            shrink_elapsed: Some(0.0),
+        };
+
+        assert_eq!(spawn_candidates(&features, &half_size), [Vec2::new(0.0, 300.0)]);
+    }
+
+    #[test]
+    fn a_blocked_centre_is_never_the_fallback()
+    {
+        let mut map = ArenaMap::circle(1000.0);
+        map.obstacles = vec![Obstacle {
+            center: [0.0, 0.0],
+            radius: 100.0,
+        }];
+        let features = ArenaFeatures(map);
+        let tiny = ArenaRadius {
+            shape: features.shape.clone(),
+            radius: 50.0,
+            previous: 50.0,
+            shrink_elapsed: Some(0.0),
+        };
+
+        assert!(spawn_candidates(&features, &tiny).is_empty());
+    }
+
+    #[test]
+    fn obstacles_kill_snakes_that_run_into_them()
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(Config::default())
+            .insert_resource(ArenaFeatures(ArenaMap {
+                obstacles: vec![Obstacle {
+                    center: [100.0, 0.0],
+                    radius: 50.0,
+                }],
+                ..ArenaMap::circle(1000.0)
+            }))
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .init_resource::<RenderHandles>()
+            .init_resource::<SnakeGrowth>()
+            .add_event::<SnakeDied>()
+            .add_systems(Update, kill_snakes_hitting_obstacles);
+
+        let mut spawn_snake = |x: f32| {
+            app.world_mut()
+                .spawn((Snake::new(0), SnakeBody::default(), Transform::from_xyz(x, 0.0, 0.0)))
+                .id()
+        };
+        let crashed = spawn_snake(60.0);
+        let clear = spawn_snake(-100.0);
+
+        app.update();
+
+        assert!(app.world().get::<DeadSnake>(crashed).is_some());
+        assert!(app.world().get::<Snake>(clear).is_some());
+    }
+}
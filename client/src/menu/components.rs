@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct MainMenu;
+
+#[derive(Component)]
+pub struct SkinNameText;
+
+#[derive(Component)]
+pub struct ModeNameText;
+
+/// One square of the skin preview, showing the colour of the segment at `index`.
+#[derive(Component)]
+pub struct SkinSwatch
+{
+    pub index: usize,
+}
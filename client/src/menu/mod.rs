@@ -0,0 +1,26 @@
+pub mod components;
+pub mod systems;
+
+use bevy::prelude::*;
+use systems::*;
+
+use crate::core::states::GameState;
+use crate::utils::despawn_screen;
+
+/// The screen shown before joining the match, where the player picks a skin.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+            .add_systems(
+                Update,
+                (main_menu_controls, update_mode_preview, update_skin_preview)
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(OnExit(GameState::MainMenu), despawn_screen::<components::MainMenu>);
+    }
+}
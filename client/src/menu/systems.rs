@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use slither_common::config::GameMode;
+use slither_common::skins::{skin, SkinId, SKINS};
+
+use super::components::*;
+use crate::config::resources::Config;
+use crate::constants::*;
+use crate::core::resources::SelectedSkin;
+use crate::core::states::GameState;
+use crate::utils::rgb_to_color;
+
+pub fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 24.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn((
+            MainMenu,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(24.0)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(MENU_BACKGROUND_COLOR),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Slither Wars",
+                        TextStyle {
+                            font_size: 48.0,
+                            color: LEADERBOARD_COLOR,
+                            ..text_style.clone()
+                        },
+                    ));
+                    parent.spawn((ModeNameText, TextBundle::from_section("", text_style.clone())));
+                    parent.spawn((SkinNameText, TextBundle::from_section("", text_style.clone())));
+
+                    // A strip of segments previewing the skin pattern
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                column_gap: Val::Px(2.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for index in 0..MENU_SKIN_SWATCHES {
+                                parent.spawn((
+                                    SkinSwatch { index },
+                                    NodeBundle {
+                                        style: Style {
+                                            width: Val::Px(MENU_SWATCH_SIZE),
+                                            height: Val::Px(MENU_SWATCH_SIZE),
+                                            ..default()
+                                        },
+                                        border_radius: BorderRadius::MAX,
+                                        ..default()
+                                    },
+                                ));
+                            }
+                        });
+
+                    parent.spawn(TextBundle::from_section(
+                        "[Up/Down] change mode  [Left/Right] change skin  [Tab] stats  [Enter] play",
+                        TextStyle {
+                            font_size: 16.0,
+                            ..text_style.clone()
+                        },
+                    ));
+                });
+        });
+}
+
+/// Up/down cycle through the game modes, left/right through the skins and enter joins the match.
+pub fn main_menu_controls(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut selected_skin: ResMut<SelectedSkin>,
+    mut config: ResMut<Config>,
+    mut next_state: ResMut<NextState<GameState>>,
+)
+{
+    let skin_count = SKINS.len() as SkinId;
+    let mode_count = GameMode::ALL.len();
+    let mode_index = GameMode::ALL.iter().position(|&mode| mode == config.rules.mode).unwrap_or(0);
+
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        config.rules.mode = GameMode::ALL[(mode_index + 1) % mode_count];
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        config.rules.mode = GameMode::ALL[(mode_index + mode_count - 1) % mode_count];
+    }
+
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        selected_skin.0 = (selected_skin.0 + 1) % skin_count;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        selected_skin.0 = (selected_skin.0 + skin_count - 1) % skin_count;
+    }
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        next_state.set(GameState::Playing);
+    }
+}
+
+pub fn update_mode_preview(config: Res<Config>, mut name_query: Query<&mut Text, With<ModeNameText>>)
+{
+    if let Ok(mut text) = name_query.get_single_mut() {
+        text.sections[0].value = format!("Mode: {}", config.rules.mode.label());
+    }
+}
+
+pub fn update_skin_preview(
+    selected_skin: Res<SelectedSkin>,
+    mut name_query: Query<&mut Text, With<SkinNameText>>,
+    mut swatch_query: Query<(&SkinSwatch, &mut BackgroundColor)>,
+)
+{
+    let skin = skin(selected_skin.0);
+
+    if let Ok(mut text) = name_query.get_single_mut() {
+        let glow = if skin.glow > 0.0 { " (glows)" } else { "" };
+        text.sections[0].value = format!("< {}{glow} >", skin.name);
+    }
+
+    for (swatch, mut background) in swatch_query.iter_mut() {
+        background.0 = rgb_to_color(skin.color_at(swatch.index));
+    }
+}
@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct Minimap;
+
+/// Dot for one of the largest snakes, `rank` 0 being the biggest.
+#[derive(Component)]
+pub struct MinimapSnakeDot
+{
+    pub rank: usize,
+}
+
+#[derive(Component)]
+pub struct MinimapPlayerDot;
+
+/// One of the dots tracing the arena's current edge, `index` counting along the outline.
+#[derive(Component)]
+pub struct MinimapBoundaryDot
+{
+    pub index: usize,
+}
+
+/// Small dot drawn in front of the player dot to show which way they are heading.
+#[derive(Component)]
+pub struct MinimapHeadingDot;
@@ -0,0 +1,29 @@
+pub mod components;
+pub mod resources;
+pub mod systems;
+
+use bevy::prelude::*;
+use systems::*;
+
+use crate::network::resources::ServerConnection;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<resources::MinimapData>()
+            .add_systems(Startup, spawn_minimap)
+            .add_systems(
+                Update,
+                (
+                    // Networked play draws the summaries the server sends instead
+                    summarize_local_world.run_if(not(resource_exists::<ServerConnection>)),
+                    track_local_player,
+                    (update_minimap_dots, update_minimap_boundary),
+                )
+                    .chain(),
+            );
+    }
+}
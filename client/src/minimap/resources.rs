@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+use slither_common::config::ArenaConfig;
+use slither_protocol::messages::MinimapSummary;
+
+use crate::constants::*;
+
+/// Everything the minimap draws.
+///
+/// In single player the summary is rebuilt from the local world, in networked play it is replaced by the
+/// `MinimapSummary` message from the server. The local player is always tracked every frame.
+#[derive(Resource)]
+pub struct MinimapData
+{
+    pub summary: MinimapSummary,
+    pub refresh_timer: Timer,
+    pub player_position: Option<Vec2>,
+    pub player_heading: Vec2,
+}
+
+impl Default for MinimapData
+{
+    fn default() -> Self
+    {
+        Self {
+            summary: MinimapSummary {
+                map_radius: ArenaConfig::default().map_radius,
+                snakes: Vec::new(),
+            },
+            refresh_timer: Timer::from_seconds(MINIMAP_REFRESH_INTERVAL, TimerMode::Repeating),
+            player_position: None,
+            player_heading: Vec2::Y,
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+use slither_common::skins::skin;
+use slither_protocol::messages::MinimapSnake;
+
+use super::components::*;
+use super::resources::*;
+use crate::constants::*;
+use crate::core::components::{SegmentPositionHistory, Snake, SnakeBody};
+use crate::core::resources::ArenaRadius;
+use crate::network::resources::RemoteSnakes;
+use crate::player::components::Player;
+use crate::utils::rgb_to_color;
+
+pub fn spawn_minimap(mut commands: Commands)
+{
+    commands
+        .spawn((
+            Minimap,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    width: Val::Px(MINIMAP_SIZE),
+                    height: Val::Px(MINIMAP_SIZE),
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                background_color: BackgroundColor(MINIMAP_BACKGROUND_COLOR),
+                border_color: BorderColor(MINIMAP_BORDER_COLOR),
+                border_radius: BorderRadius::MAX,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for index in 0..MINIMAP_BOUNDARY_DOTS {
+                parent.spawn((MinimapBoundaryDot { index }, minimap_dot(MINIMAP_BOUNDARY_COLOR)));
+            }
+            for rank in 0..MINIMAP_TOP_SNAKES {
+                parent.spawn((MinimapSnakeDot { rank }, minimap_dot(MINIMAP_SNAKE_COLOR)));
+            }
+            parent.spawn((MinimapHeadingDot, minimap_dot(MINIMAP_PLAYER_COLOR)));
+            parent.spawn((MinimapPlayerDot, minimap_dot(MINIMAP_PLAYER_COLOR)));
+        });
+}
+
+fn minimap_dot(color: Color) -> NodeBundle
+{
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        background_color: BackgroundColor(color),
+        border_radius: BorderRadius::MAX,
+        visibility: Visibility::Hidden,
+        ..default()
+    }
+}
+
+/// Rebuilds the summary from the local simulation at the same low rate a server would send it.
+pub fn summarize_local_world(
+    time: Res<Time>,
+    mut minimap: ResMut<MinimapData>,
+    arena: Res<ArenaRadius>,
+    snake_query: Query<(Entity, &Transform, &Snake, &SnakeBody)>,
+)
+{
+    if !minimap.refresh_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let mut snakes: Vec<MinimapSnake> = snake_query
+        .iter()
+        .map(|(entity, transform, snake, body)| MinimapSnake {
+            id: entity.to_bits(),
+            position: transform.translation.truncate().to_array(),
+            radius: body.radius,
+            score: snake.score(),
+        })
+        .collect();
+
+    snakes.sort_by_key(|snake| std::cmp::Reverse(snake.score));
+    snakes.truncate(MINIMAP_TOP_SNAKES);
+
+    minimap.summary.map_radius = arena.shape.radius();
+    minimap.summary.snakes = snakes;
+}
+
+/// The local player is tracked every frame so their own dot never lags behind.
+pub fn track_local_player(
+    mut minimap: ResMut<MinimapData>,
+    player_query: Query<(&Transform, &SegmentPositionHistory), (With<Player>, With<Snake>)>,
+)
+{
+    let Ok((transform, history)) = player_query.get_single() else {
+        minimap.player_position = None;
+        return;
+    };
+
+    let position = transform.translation.truncate();
+    if let Some(previous) = history.positions.get(1) {
+        let heading = position - previous.truncate();
+        if heading != Vec2::ZERO {
+            minimap.player_heading = heading.normalize();
+        }
+    }
+    minimap.player_position = Some(position);
+}
+
+pub fn update_minimap_dots(
+    minimap: Res<MinimapData>,
+    remote_snakes: Option<Res<RemoteSnakes>>,
+    mut snake_dots: Query<(&MinimapSnakeDot, &mut Style, &mut Visibility, &mut BackgroundColor)>,
+    mut player_dot: Query<
+        (&mut Style, &mut Visibility),
+        (With<MinimapPlayerDot>, Without<MinimapSnakeDot>, Without<MinimapHeadingDot>),
+    >,
+    mut heading_dot: Query<
+        (&mut Style, &mut Visibility),
+        (With<MinimapHeadingDot>, Without<MinimapSnakeDot>, Without<MinimapPlayerDot>),
+    >,
+)
+{
+    let map_radius = minimap.summary.map_radius;
+
+    for (dot, mut style, mut visibility, mut color) in snake_dots.iter_mut() {
+        match minimap.summary.snakes.get(dot.rank) {
+            Some(snake) => {
+                let size = (snake.radius / PLAYER_DEFAULT_RADIUS * MINIMAP_DOT_SIZE)
+                    .clamp(MINIMAP_DOT_SIZE, MINIMAP_DOT_SIZE * 2.5);
+                place_dot(&mut style, Vec2::from_array(snake.position), map_radius, size);
+                *visibility = Visibility::Inherited;
+
+                // Other players show in the skin they joined with
+                let skin_color = remote_snakes
+                    .as_ref()
+                    .and_then(|remote_snakes| remote_snakes.0.get(&snake.id))
+                    .map_or(MINIMAP_SNAKE_COLOR, |remote| rgb_to_color(skin(remote.skin).primary_color()));
+                color.set_if_neq(BackgroundColor(skin_color));
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    let player_position = minimap.player_position;
+
+    if let Ok((mut style, mut visibility)) = player_dot.get_single_mut() {
+        match player_position {
+            Some(position) => {
+                place_dot(&mut style, position, map_radius, MINIMAP_DOT_SIZE * 1.5);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    if let Ok((mut style, mut visibility)) = heading_dot.get_single_mut() {
+        match player_position {
+            Some(position) => {
+                // Offset the heading dot by a fixed amount of minimap pixels so it is visible at any map size
+                let offset = minimap.player_heading * MINIMAP_DOT_SIZE * 2.0 / MINIMAP_SIZE * 2.0 * map_radius;
+                place_dot(&mut style, position + offset, map_radius, MINIMAP_DOT_SIZE * 0.75);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+/// Traces the arena's current edge, so a closing arena and polygon maps show as they are rather than as the full
+/// circle the minimap itself is.
+pub fn update_minimap_boundary(
+    arena: Res<ArenaRadius>,
+    minimap: Res<MinimapData>,
+    mut drawn_for: Local<Option<f32>>,
+    mut boundary_dots: Query<(&MinimapBoundaryDot, &mut Style, &mut Visibility)>,
+)
+{
+    let map_radius = minimap.summary.map_radius;
+    if !arena.is_changed() && *drawn_for == Some(map_radius) {
+        return;
+    }
+    *drawn_for = Some(map_radius);
+
+    let outline = arena.shape.outline(MINIMAP_BOUNDARY_DOTS);
+    let scale = arena.scale();
+    for (dot, mut style, mut visibility) in boundary_dots.iter_mut() {
+        match outline.get(dot.index) {
+            Some(&point) => {
+                place_dot(
+                    &mut style,
+                    Vec2::from_array(point) * scale,
+                    map_radius,
+                    MINIMAP_BOUNDARY_DOT_SIZE,
+                );
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+/// Converts a world position into minimap pixels, with the arena circle filling the whole minimap.
+fn place_dot(style: &mut Style, position: Vec2, map_radius: f32, size: f32)
+{
+    let normalized = position / map_radius * 0.5;
+    let inner_size = MINIMAP_SIZE - 4.0; // Minus the border
+
+    style.width = Val::Px(size);
+    style.height = Val::Px(size);
+    style.left = Val::Px((0.5 + normalized.x) * inner_size - size * 0.5);
+    style.top = Val::Px((0.5 - normalized.y) * inner_size - size * 0.5);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::network::resources::RemoteSnake;
+
+    #[test]
+    fn the_boundary_follows_the_closing_arena()
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ArenaRadius>()
+            .init_resource::<MinimapData>()
+            .add_systems(Update, update_minimap_boundary);
+        let first_dot = app
+            .world_mut()
+            .spawn((MinimapBoundaryDot { index: 0 }, Style::default(), Visibility::Hidden))
+            .id();
+        let dot_centre = |app: &App| match app.world().get::<Style>(first_dot).unwrap().left {
+            Val::Px(left) => left + MINIMAP_BOUNDARY_DOT_SIZE * 0.5,
+            other => panic!("dots are placed in pixels, not {other:?}"),
+        };
+
+        app.update();
+        let full_size = dot_centre(&app);
+
+        let mut arena = app.world_mut().resource_mut::<ArenaRadius>();
+        arena.radius *= 0.5;
+        app.update();
+
+        // The first dot sits on the right edge, which moves halfway towards the centre of the minimap
+        let centre = (MINIMAP_SIZE - 4.0) * 0.5;
+        assert!((full_size - centre) > 0.0);
+        assert!(((dot_centre(&app) - centre) - (full_size - centre) * 0.5).abs() < 0.01);
+        assert_eq!(app.world().get::<Visibility>(first_dot), Some(&Visibility::Inherited));
+    }
+
+    #[test]
+    fn other_players_show_in_their_skin()
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<MinimapData>()
+            .init_resource::<RemoteSnakes>()
+            .add_systems(Update, update_minimap_dots);
+        let dots: Vec<Entity> = (0..2)
+            .map(|rank| {
+                app.world_mut()
+                    .spawn((
+                        MinimapSnakeDot { rank },
+                        Style::default(),
+                        Visibility::Hidden,
+                        BackgroundColor(MINIMAP_SNAKE_COLOR),
+                    ))
+                    .id()
+            })
+            .collect();
+
+        app.world_mut().resource_mut::<RemoteSnakes>().0.insert(
+            7,
+            RemoteSnake {
+                name: "Viper".into(),
+                skin: 2,
+            },
+        );
+        let snake = |id| MinimapSnake {
+            id,
+            position: [0.0, 0.0],
+            radius: 10.0,
+            score: 100,
+        };
+        app.world_mut().resource_mut::<MinimapData>().summary.snakes = vec![snake(7), snake(8)];
+        app.update();
+
+        let color = |dot: Entity| app.world().get::<BackgroundColor>(dot).unwrap().0;
+        assert_eq!(color(dots[0]), rgb_to_color(skin(2).primary_color()));
+        // Snakes the server never announced keep the plain minimap colour
+        assert_eq!(color(dots[1]), MINIMAP_SNAKE_COLOR);
+    }
+}
@@ -0,0 +1,9 @@
+use bevy::prelude::*;
+
+/// Mode name and progress, e.g. the time left or snakes still alive.
+#[derive(Component)]
+pub struct MatchHudText;
+
+/// The final standings shown once a match ends.
+#[derive(Component)]
+pub struct Podium;
@@ -0,0 +1,37 @@
+pub mod components;
+pub mod resources;
+pub mod systems;
+
+use bevy::prelude::*;
+use resources::*;
+use systems::*;
+
+use crate::core::states::GameState;
+
+/// Runs the selected game mode: team assignment, the match clock, win conditions and the final podium.
+pub struct ModePlugin;
+
+impl Plugin for ModePlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<MatchState>()
+            .add_systems(Startup, spawn_match_hud)
+            .add_systems(OnEnter(GameState::Playing), start_match)
+            .add_systems(
+                Update,
+                (
+                    start_match.run_if(match_restart_requested),
+                    assign_teams.run_if(mode_uses_teams),
+                    clear_teams.run_if(not(mode_uses_teams)),
+                    record_eliminations,
+                    tick_match_clock,
+                    check_last_snake_standing,
+                    show_podium,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Update, update_match_hud);
+    }
+}
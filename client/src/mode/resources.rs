@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+use slither_common::config::{GameMode, RulesConfig};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PodiumEntry
+{
+    pub name: String,
+    pub score: u32,
+}
+
+/// Progress of the current match.
+///
+/// The mode is copied from the config when the match starts, so switching modes takes effect from the next match.
+#[derive(Resource, Default)]
+pub struct MatchState
+{
+    pub mode: GameMode,
+    /// Counts down in modes with a time limit.
+    pub clock: Timer,
+    /// Snakes knocked out so far, in the order they died.
+    pub eliminated: Vec<PodiumEntry>,
+    /// Final standings, winner first, once the match is over.
+    pub podium: Option<Vec<PodiumEntry>>,
+}
+
+impl MatchState
+{
+    pub fn new(rules: &RulesConfig) -> Self
+    {
+        Self {
+            mode: rules.mode,
+            clock: Timer::from_seconds(rules.match_duration, TimerMode::Once),
+            eliminated: Vec::new(),
+            podium: None,
+        }
+    }
+
+    pub fn is_over(&self) -> bool
+    {
+        self.podium.is_some()
+    }
+}
@@ -0,0 +1,467 @@
+use bevy::prelude::*;
+use slither_common::skins::skin;
+
+use super::components::*;
+use super::resources::*;
+use crate::bot::components::Bot;
+use crate::config::resources::Config;
+use crate::constants::*;
+use crate::core::components::{DeadSnake, Snake, Team};
+use crate::core::events::{MatchStarted, SnakeDied};
+use crate::core::resources::{ArenaRadius, SelectedSkin};
+use crate::orb::components::Orb;
+use crate::utils::rgb_to_color;
+
+pub fn spawn_match_hud(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                MatchHudText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 20.0,
+                        color: TEXT_COLOR,
+                    },
+                ),
+            ));
+        });
+}
+
+/// Resets the match under the mode currently in the config. Runs when the player joins and again when they
+/// start the next match from the podium.
+///
+/// Every match starts from an empty arena: the snakes of the last one, dead or alive, and its orbs are cleared
+/// out, and the player and the bots spawn again on [`MatchStarted`].
+pub fn start_match(
+    mut commands: Commands,
+    mut match_state: ResMut<MatchState>,
+    config: Res<Config>,
+    mut arena: ResMut<ArenaRadius>,
+    mut time: ResMut<Time<Virtual>>,
+    mut started_events: EventWriter<MatchStarted>,
+    podium_query: Query<Entity, With<Podium>>,
+    snake_query: Query<Entity, Or<(With<Snake>, With<DeadSnake>)>>,
+    orb_query: Query<Entity, With<Orb>>,
+)
+{
+    *match_state = MatchState::new(&config.rules);
+    arena.shrink_elapsed = match_state.mode.shrinks_arena().then_some(0.0);
+    time.unpause();
+
+    for entity in podium_query.iter().chain(snake_query.iter()).chain(orb_query.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    started_events.send(MatchStarted);
+}
+
+pub fn match_restart_requested(keyboard_input: Res<ButtonInput<KeyCode>>, match_state: Res<MatchState>) -> bool
+{
+    match_state.is_over() && keyboard_input.just_pressed(KeyCode::Enter)
+}
+
+pub fn mode_uses_teams(match_state: Res<MatchState>) -> bool
+{
+    match_state.mode.uses_teams()
+}
+
+/// Puts every snake without a team into the smallest team and paints it in the team colour.
+pub fn assign_teams(
+    mut commands: Commands,
+    config: Res<Config>,
+    mut unassigned_query: Query<(Entity, &mut Snake), Without<Team>>,
+    team_query: Query<&Team, With<Snake>>,
+)
+{
+    let mut team_sizes = vec![0; config.rules.team_count as usize];
+    for team in team_query.iter() {
+        if let Some(size) = team_sizes.get_mut(team.0 as usize) {
+            *size += 1;
+        }
+    }
+
+    for (entity, mut snake) in unassigned_query.iter_mut() {
+        let Some((smallest, _)) = team_sizes.iter().enumerate().min_by_key(|(_, size)| **size) else {
+            return;
+        };
+        let team = Team(smallest as u8);
+        team_sizes[smallest] += 1;
+
+        snake.skin = team.skin();
+        snake.color = team.color();
+        commands.entity(entity).insert(team);
+    }
+}
+
+/// Takes snakes out of their teams once a match without teams starts and gives them their own skin back.
+pub fn clear_teams(
+    mut commands: Commands,
+    selected_skin: Res<SelectedSkin>,
+    mut snake_query: Query<(Entity, &mut Snake, Option<&Bot>), With<Team>>,
+)
+{
+    for (entity, mut snake, bot) in snake_query.iter_mut() {
+        snake.skin = bot.map_or(selected_skin.0, |bot| bot.skin);
+        snake.color = rgb_to_color(skin(snake.skin).primary_color());
+        commands.entity(entity).remove::<Team>();
+    }
+}
+
+pub fn record_eliminations(mut death_events: EventReader<SnakeDied>, mut match_state: ResMut<MatchState>)
+{
+    for death in death_events.read() {
+        if match_state.is_over() {
+            continue;
+        }
+
+        match_state.eliminated.push(PodiumEntry {
+            name: death.victim_name.clone(),
+            score: death.victim_score,
+        });
+    }
+}
+
+/// Ends a timed match once the clock runs out, ranking the snakes still alive by score.
+pub fn tick_match_clock(time: Res<Time>, mut match_state: ResMut<MatchState>, snake_query: Query<(&Snake, &Name)>)
+{
+    if !match_state.mode.has_time_limit() || match_state.is_over() {
+        return;
+    }
+
+    if match_state.clock.tick(time.delta()).finished() {
+        let mut standings: Vec<PodiumEntry> = snake_query
+            .iter()
+            .map(|(snake, name)| PodiumEntry {
+                name: name.to_string(),
+                score: snake.score(),
+            })
+            .collect();
+        standings.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        standings.truncate(PODIUM_SIZE);
+
+        match_state.podium = Some(standings);
+    }
+}
+
+/// Ends a battle royale once a single snake is left. The survivor wins and the rest are ranked by how long
+/// they lasted.
+pub fn check_last_snake_standing(mut match_state: ResMut<MatchState>, snake_query: Query<(&Snake, &Name)>)
+{
+    // Nothing to decide until someone has been knocked out, e.g. while the arena is still filling up
+    if !match_state.mode.last_snake_standing() || match_state.is_over() || match_state.eliminated.is_empty() {
+        return;
+    }
+
+    if snake_query.iter().count() > 1 {
+        return;
+    }
+
+    let survivor = snake_query.iter().map(|(snake, name)| PodiumEntry {
+        name: name.to_string(),
+        score: snake.score(),
+    });
+    let mut standings: Vec<PodiumEntry> = survivor.chain(match_state.eliminated.iter().rev().cloned()).collect();
+    standings.truncate(PODIUM_SIZE);
+
+    match_state.podium = Some(standings);
+}
+
+/// Freezes the arena and shows the final standings once the match is over.
+pub fn show_podium(
+    mut commands: Commands,
+    match_state: Res<MatchState>,
+    mut time: ResMut<Time<Virtual>>,
+    podium_query: Query<(), With<Podium>>,
+    asset_server: Res<AssetServer>,
+)
+{
+    let Some(standings) = &match_state.podium else {
+        return;
+    };
+    if !podium_query.is_empty() {
+        return;
+    }
+
+    time.pause();
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 24.0,
+        color: TEXT_COLOR,
+    };
+    let lines: Vec<String> = standings
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| format!("{}. {} - {}", index + 1, entry.name, entry.score))
+        .collect();
+
+    commands
+        .spawn((
+            Podium,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(BLACK_COLOR.with_alpha(0.8)),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        format!("{} over", match_state.mode.label()),
+                        TextStyle {
+                            font_size: 36.0,
+                            color: LEADERBOARD_COLOR,
+                            ..text_style.clone()
+                        },
+                    ));
+                    parent.spawn(TextBundle::from_section(lines.join("\n"), text_style.clone()));
+                    parent.spawn(TextBundle::from_section(
+                        "Press Enter to start the next match",
+                        TextStyle {
+                            font_size: 16.0,
+                            ..text_style.clone()
+                        },
+                    ));
+                });
+        });
+}
+
+/// Total score of each team, indexed by team number.
+pub fn team_scores<'a>(snakes: impl Iterator<Item = (&'a Snake, &'a Team)>, team_count: u8) -> Vec<u32>
+{
+    let mut scores = vec![0; team_count as usize];
+    for (snake, team) in snakes {
+        if let Some(score) = scores.get_mut(team.0 as usize) {
+            *score += snake.score();
+        }
+    }
+    scores
+}
+
+pub fn update_match_hud(
+    match_state: Res<MatchState>,
+    config: Res<Config>,
+    snake_query: Query<(&Snake, Option<&Team>)>,
+    mut text_query: Query<&mut Text, With<MatchHudText>>,
+)
+{
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    let mode = match_state.mode;
+    let progress = if match_state.is_over() {
+        "Match over".to_string()
+    } else if mode.has_time_limit() {
+        let remaining = match_state.clock.remaining_secs().ceil() as u32;
+        format!("{}:{:02} left", remaining / 60, remaining % 60)
+    } else if mode.uses_teams() {
+        let members = snake_query.iter().filter_map(|(snake, team)| team.map(|team| (snake, team)));
+        team_scores(members, config.rules.team_count)
+            .iter()
+            .enumerate()
+            .map(|(team, score)| format!("{} {score}", Team(team as u8).name()))
+            .collect::<Vec<_>>()
+            .join("  ")
+    } else if mode.last_snake_standing() {
+        format!("{} snakes left", snake_query.iter().count())
+    } else {
+        String::new()
+    };
+
+    text.sections[0].value = if progress.is_empty() {
+        mode.label().to_string()
+    } else {
+        format!("{}  |  {progress}", mode.label())
+    };
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::time::TimeUpdateStrategy;
+    use slither_common::config::{GameMode, RulesConfig};
+
+    use super::*;
+    use crate::bot::systems::sync_bot_count;
+    use crate::core::events::SnakeSpawned;
+    use crate::core::resources::{CameraTarget, RenderHandles};
+    use crate::map::resources::ArenaFeatures;
+    use crate::player::components::Player;
+    use crate::player::systems::spawn_player;
+
+    fn headless_app(mode: GameMode) -> App
+    {
+        let rules = RulesConfig {
+            mode,
+            match_duration: 0.2,
+            ..default()
+        };
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+            .insert_resource(MatchState::new(&rules))
+            .add_event::<SnakeDied>()
+            .add_systems(
+                Update,
+                (record_eliminations, tick_match_clock, check_last_snake_standing).chain(),
+            );
+        app
+    }
+
+    fn spawn_snake(app: &mut App, name: &str, mass: f32) -> Entity
+    {
+        let mut snake = Snake::new(0);
+        snake.mass = mass;
+        app.world_mut().spawn((snake, Name::new(name.to_string()))).id()
+    }
+
+    fn podium_names(app: &App) -> Option<Vec<String>>
+    {
+        let podium = app.world().resource::<MatchState>().podium.as_ref()?;
+        Some(podium.iter().map(|entry| entry.name.clone()).collect())
+    }
+
+    #[test]
+    fn timed_match_ends_with_the_biggest_snakes_on_the_podium()
+    {
+        let mut app = headless_app(GameMode::TimedMatch);
+        for (name, mass) in [("Small", 5.0), ("Big", 50.0), ("Tiny", 1.0), ("Medium", 20.0)] {
+            spawn_snake(&mut app, name, mass);
+        }
+
+        app.update();
+        app.update();
+        assert_eq!(podium_names(&app), None);
+
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(podium_names(&app).unwrap(), ["Big", "Medium", "Small"]);
+    }
+
+    #[test]
+    fn battle_royale_ends_when_one_snake_is_left()
+    {
+        let mut app = headless_app(GameMode::BattleRoyale);
+        let first = spawn_snake(&mut app, "First out", 10.0);
+        let second = spawn_snake(&mut app, "Runner up", 10.0);
+        spawn_snake(&mut app, "Winner", 10.0);
+
+        for (victim, name) in [(first, "First out"), (second, "Runner up")] {
+            app.world_mut().despawn(victim);
+            app.world_mut().send_event(SnakeDied {
+                victim,
+                victim_name: name.to_string(),
+                victim_score: 10,
+                victim_length: 1,
+                victim_kills: 0,
+                position: Vec2::ZERO,
+                killer: None,
+                killer_name: None,
+            });
+            app.update();
+        }
+
+        assert_eq!(podium_names(&app).unwrap(), ["Winner", "Runner up", "First out"]);
+    }
+
+    #[test]
+    fn a_rematch_after_the_player_died_starts_everyone_afresh()
+    {
+        let mut config = Config::default();
+        config.rules.mode = GameMode::BattleRoyale;
+        config.bots.enabled = true;
+        config.bots.spawn_amount = 2;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(config)
+            .init_resource::<MatchState>()
+            .init_resource::<ArenaRadius>()
+            .init_resource::<ArenaFeatures>()
+            .init_resource::<CameraTarget>()
+            .init_resource::<SelectedSkin>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .init_resource::<RenderHandles>()
+            .add_event::<MatchStarted>()
+            .add_event::<SnakeSpawned>()
+            .add_event::<SnakeDied>()
+            .add_systems(
+                Update,
+                (
+                    spawn_player.run_if(on_event::<MatchStarted>()),
+                    sync_bot_count.run_if(on_event::<MatchStarted>()),
+                ),
+            );
+
+        app.world_mut().run_system_once(start_match);
+        app.update();
+
+        // The player grows, dies and leaves some orbs behind
+        let mut players = app.world_mut().query_filtered::<(Entity, &mut Snake), With<Player>>();
+        let (player, mut snake) = players.single_mut(app.world_mut());
+        snake.mass = 500.0;
+        app.world_mut()
+            .entity_mut(player)
+            .insert(DeadSnake { killer: None })
+            .remove::<Snake>();
+        app.world_mut().spawn(Orb {
+            radius: ORB_RADIUS,
+            value: ORB_VALUE,
+        });
+
+        app.world_mut().run_system_once(start_match);
+        app.update();
+
+        let world = app.world_mut();
+        let snakes: Vec<(bool, f32)> = world
+            .query::<(&Snake, Has<Player>, Has<Bot>)>()
+            .iter(world)
+            .map(|(snake, player, bot)| {
+                assert!(player != bot);
+                (player, snake.mass)
+            })
+            .collect();
+        assert_eq!(snakes.len(), 3);
+        assert_eq!(snakes.iter().filter(|(player, _)| *player).count(), 1);
+        assert!(snakes.iter().all(|&(_, mass)| mass == Snake::new(0).mass));
+        assert!(world.get_entity(player).is_none());
+        assert_eq!(world.query::<&Orb>().iter(world).count(), 0);
+    }
+}
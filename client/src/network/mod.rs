@@ -0,0 +1,46 @@
+pub mod resources;
+pub mod systems;
+
+use bevy::prelude::*;
+use resources::{RemoteSnakes, ServerConnection};
+use systems::*;
+
+use crate::core::resources::SelectedSkin;
+use crate::profile::systems::{record_match_end, record_player_death};
+
+/// Talks to a game server: plays by its rules, signs in to the player's account, shows the player's snake and skin to
+/// the other players and theirs on the minimap, reports finished games and fetches the top lists.
+pub struct NetworkPlugin
+{
+    pub address: String,
+    /// Signs up under this name when the profile has no account yet.
+    pub name: Option<String>,
+}
+
+impl Plugin for NetworkPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        match ServerConnection::connect(&self.address, self.name.clone()) {
+            Ok(connection) => {
+                info!("Connected to {}", self.address);
+                app.insert_resource(connection);
+            }
+            Err(err) => {
+                error!("Failed to connect to {}, playing offline: {err}", self.address);
+                return;
+            }
+        }
+
+        app.init_resource::<RemoteSnakes>().add_systems(Startup, log_in).add_systems(
+            Update,
+            (
+                join_with_selected_skin.run_if(resource_changed::<SelectedSkin>),
+                (receive_server_messages, handle_server_messages).chain(),
+                report_player_snake,
+                // After the profile so a finished game is submitted before the lists that should include it
+                submit_finished_games.after(record_player_death).after(record_match_end),
+            ),
+        );
+    }
+}
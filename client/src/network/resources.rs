@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::io::{self, BufReader};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, PoisonError};
+
+use bevy::prelude::*;
+use slither_common::skins::SkinId;
+use slither_protocol::messages::{ClientMessage, ServerMessage};
+
+/// The link to the game server. The socket is read and written on background threads, so systems never wait
+/// on it.
+#[derive(Resource)]
+pub struct ServerConnection
+{
+    outgoing: Sender<ClientMessage>,
+    incoming: Mutex<Receiver<ServerMessage>>,
+    /// Name to sign up with when the profile has no account yet.
+    pub sign_up_name: Option<String>,
+    /// The account name once the server accepted the login.
+    pub signed_in_as: Option<String>,
+}
+
+impl ServerConnection
+{
+    pub fn connect(address: &str, sign_up_name: Option<String>) -> io::Result<Self>
+    {
+        let stream = TcpStream::connect(address)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        let (outgoing, to_server) = mpsc::channel::<ClientMessage>();
+        let (from_server, incoming) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for message in to_server {
+                if let Err(err) = message.write_to(&mut writer) {
+                    warn!("Lost the connection to the server: {err}");
+                    return;
+                }
+            }
+        });
+        std::thread::spawn(move || loop {
+            match ServerMessage::read_from(&mut reader) {
+                Ok(message) => {
+                    if from_server.send(message).is_err() {
+                        return;
+                    }
+                }
+                Err(err) => {
+                    warn!("Lost the connection to the server: {err}");
+                    return;
+                }
+            }
+        });
+
+        Ok(Self::from_channels(outgoing, incoming, sign_up_name))
+    }
+
+    /// A connection that talks over the given channels instead of a socket.
+    pub fn from_channels(
+        outgoing: Sender<ClientMessage>,
+        incoming: Receiver<ServerMessage>,
+        sign_up_name: Option<String>,
+    ) -> Self
+    {
+        Self {
+            outgoing,
+            incoming: Mutex::new(incoming),
+            sign_up_name,
+            signed_in_as: None,
+        }
+    }
+
+    /// Queues a message for the server. Once the connection is lost messages are dropped, which the receiving
+    /// side has already reported.
+    pub fn send(&self, message: ClientMessage)
+    {
+        let _ = self.outgoing.send(message);
+    }
+
+    /// The next message that arrived from the server, if any.
+    pub fn try_receive(&self) -> Option<ServerMessage>
+    {
+        self.incoming.lock().unwrap_or_else(PoisonError::into_inner).try_recv().ok()
+    }
+}
+
+/// The other players the server announced, by the id their snakes have in minimap summaries.
+#[derive(Resource, Default)]
+pub struct RemoteSnakes(pub HashMap<u64, RemoteSnake>);
+
+pub struct RemoteSnake
+{
+    pub name: String,
+    pub skin: SkinId,
+}
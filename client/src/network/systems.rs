@@ -0,0 +1,423 @@
+use bevy::prelude::*;
+use slither_common::map::MAP_VERSION;
+use slither_common::profile::SavedAccount;
+use slither_protocol::messages::{ClientMessage, GameResult, JoinRequest, LoginRequest, ServerMessage, SnakeReport};
+
+use super::resources::{RemoteSnake, RemoteSnakes, ServerConnection};
+use crate::config::resources::{Config, ConfigReloader};
+use crate::constants::MINIMAP_REFRESH_INTERVAL;
+use crate::core::components::{Snake, SnakeBody};
+use crate::core::events::{GameFinished, ServerMessageReceived};
+use crate::core::resources::SelectedSkin;
+use crate::leaderboard::resources::RankedLeaderboards;
+use crate::minimap::resources::MinimapData;
+use crate::mode::resources::MatchState;
+use crate::player::components::Player;
+use crate::profile::resources::Profile;
+use crate::profile::systems::save_profile;
+
+/// Signs back in to the account saved in the profile, or signs up when a name was given, and asks for the top
+/// lists to have them ready.
+pub fn log_in(connection: Res<ServerConnection>, profile: Option<Res<Profile>>)
+{
+    let account = profile.and_then(|profile| profile.account.clone());
+    match (account, &connection.sign_up_name) {
+        (Some(account), _) => connection.send(ClientMessage::Login(LoginRequest {
+            name: account.name,
+            token: Some(account.token),
+        })),
+        (None, Some(name)) => connection.send(ClientMessage::Login(LoginRequest {
+            name: name.clone(),
+            token: None,
+        })),
+        (None, None) => info!("Playing without an account, start with --name <name> to sign up"),
+    }
+    connection.send(ClientMessage::FetchLeaderboards);
+}
+
+/// Tells the server the name and skin the other players should see, on connecting and whenever another skin is
+/// picked.
+pub fn join_with_selected_skin(
+    connection: Res<ServerConnection>,
+    selected_skin: Res<SelectedSkin>,
+    profile: Option<Res<Profile>>,
+)
+{
+    let name = connection
+        .signed_in_as
+        .clone()
+        .or_else(|| profile.and_then(|profile| profile.account.as_ref().map(|account| account.name.clone())))
+        .or_else(|| connection.sign_up_name.clone())
+        .unwrap_or_else(|| "Guest".to_string());
+    connection.send(ClientMessage::Join(JoinRequest {
+        name,
+        skin: selected_skin.0,
+    }));
+}
+
+pub fn receive_server_messages(connection: Res<ServerConnection>, mut received_events: EventWriter<ServerMessageReceived>)
+{
+    while let Some(message) = connection.try_receive() {
+        received_events.send(ServerMessageReceived(message));
+    }
+}
+
+pub fn handle_server_messages(
+    mut received_events: EventReader<ServerMessageReceived>,
+    mut connection: ResMut<ServerConnection>,
+    mut ranked: ResMut<RankedLeaderboards>,
+    mut profile: Option<ResMut<Profile>>,
+    mut config: ResMut<Config>,
+    mut reloader: ResMut<ConfigReloader>,
+    mut minimap: Option<ResMut<MinimapData>>,
+    mut remote_snakes: ResMut<RemoteSnakes>,
+)
+{
+    for ServerMessageReceived(message) in received_events.read() {
+        match message {
+            ServerMessage::Rules(rules) => {
+                if !reloader.overridden {
+                    info!("Playing by the server's rules, edits to the local config are ignored");
+                    reloader.overridden = true;
+                    reloader.error = None;
+                }
+                // The same rules sent again should not rebuild everything derived from them
+                if config.0 != **rules {
+                    config.0 = (**rules).clone();
+                }
+            }
+            // The rules already name the map, this makes sure it is one the client can read
+            ServerMessage::LoadMap(choice) if choice.version != MAP_VERSION => warn!(
+                "The server plays on {} in map version {}, this client reads version {MAP_VERSION}",
+                choice.name, choice.version
+            ),
+            ServerMessage::LoadMap(choice) => {
+                if config.arena.map.as_ref() != Some(&choice.name) {
+                    config.arena.map = Some(choice.name.clone());
+                }
+            }
+            ServerMessage::LoggedIn { name, token } => {
+                info!("Signed in as {name}");
+                connection.signed_in_as = Some(name.clone());
+
+                let account = SavedAccount {
+                    name: name.clone(),
+                    token: token.clone(),
+                };
+                if let Some(profile) = profile.as_mut().filter(|profile| profile.account.as_ref() != Some(&account)) {
+                    profile.account = Some(account);
+                    save_profile(profile);
+                }
+            }
+            ServerMessage::LoginRejected(reason) => warn!("The server refused the login: {reason}"),
+            ServerMessage::RequestFailed(reason) => warn!("The server could not answer: {reason}"),
+            ServerMessage::Leaderboards(boards) => ranked.0 = Some(boards.clone()),
+            ServerMessage::SnakeJoined { id, name, skin } => {
+                remote_snakes.0.insert(
+                    *id,
+                    RemoteSnake {
+                        name: name.clone(),
+                        skin: *skin,
+                    },
+                );
+            }
+            ServerMessage::MinimapSummary(summary) => {
+                if let Some(minimap) = minimap.as_mut() {
+                    minimap.summary = summary.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Tells the server where the player's snake is at the minimap's rate, so it shows up on the other players'
+/// minimaps.
+pub fn report_player_snake(
+    time: Res<Time>,
+    connection: Res<ServerConnection>,
+    mut since_report: Local<f32>,
+    player_query: Query<(&Transform, &Snake, &SnakeBody), With<Player>>,
+)
+{
+    *since_report += time.delta_seconds();
+    if *since_report < MINIMAP_REFRESH_INTERVAL {
+        return;
+    }
+
+    let Ok((transform, snake, body)) = player_query.get_single() else {
+        return;
+    };
+    *since_report = 0.0;
+    connection.send(ClientMessage::ReportSnake(SnakeReport {
+        position: transform.translation.truncate().to_array(),
+        radius: body.radius,
+        score: snake.score(),
+    }));
+}
+
+/// Reports every finished game of the signed in player and refreshes the top lists, as well as when a match
+/// ends, so they are up to date on the death screen and between rounds.
+pub fn submit_finished_games(
+    mut finished_events: EventReader<GameFinished>,
+    connection: Res<ServerConnection>,
+    match_state: Option<Res<MatchState>>,
+    mut was_over: Local<bool>,
+)
+{
+    let mut refresh = false;
+    for GameFinished(game) in finished_events.read() {
+        if connection.signed_in_as.is_some() {
+            connection.send(ClientMessage::SubmitResult(GameResult {
+                score: game.score,
+                kills: game.kills,
+                duration: game.duration,
+            }));
+        }
+        refresh = true;
+    }
+
+    let over = match_state.is_some_and(|match_state| match_state.is_over());
+    refresh |= over && !*was_over;
+    *was_over = over;
+
+    if refresh {
+        connection.send(ClientMessage::FetchLeaderboards);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+    use slither_common::config::{GameConfig, GameMode};
+    use slither_common::profile::{GameRecord, PlayerProfile};
+    use slither_common::ranking::{LeaderboardEntry, Leaderboards};
+    use slither_protocol::messages::{MapChoice, MinimapSnake, MinimapSummary};
+
+    use super::*;
+
+    #[test]
+    fn finished_games_are_submitted_and_the_lists_come_back()
+    {
+        let (outgoing, to_server) = mpsc::channel();
+        let (from_server, incoming) = mpsc::channel();
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(ServerConnection::from_channels(outgoing, incoming, Some("Viper".into())))
+            .insert_resource(Profile {
+                stats: PlayerProfile::default(),
+                path: std::env::temp_dir().join("unused-profile.ron"),
+                read_only: true,
+            })
+            .init_resource::<RankedLeaderboards>()
+            .init_resource::<Config>()
+            .init_resource::<RemoteSnakes>()
+            .insert_resource(ConfigReloader::new(std::env::temp_dir().join("unused-config.ron")))
+            .insert_resource(SelectedSkin(5))
+            .add_event::<GameFinished>()
+            .add_event::<ServerMessageReceived>()
+            .add_systems(Startup, log_in)
+            .add_systems(
+                Update,
+                (
+                    join_with_selected_skin.run_if(resource_changed::<SelectedSkin>),
+                    receive_server_messages,
+                    handle_server_messages,
+                    submit_finished_games,
+                )
+                    .chain(),
+            );
+
+        app.update();
+        assert_eq!(
+            to_server.try_iter().collect::<Vec<_>>(),
+            [
+                ClientMessage::Login(LoginRequest {
+                    name: "Viper".into(),
+                    token: None,
+                }),
+                ClientMessage::FetchLeaderboards,
+                ClientMessage::Join(JoinRequest {
+                    name: "Viper".into(),
+                    skin: 5,
+                }),
+            ]
+        );
+
+        from_server
+            .send(ServerMessage::LoggedIn {
+                name: "Viper".into(),
+                token: "0123abcd".into(),
+            })
+            .unwrap();
+        app.update();
+        let account = app.world().resource::<Profile>().account.clone().unwrap();
+        assert_eq!(account.token, "0123abcd");
+
+        // Picking another skin tells the other players
+        app.world_mut().resource_mut::<SelectedSkin>().0 = 8;
+        app.update();
+        assert_eq!(
+            to_server.try_iter().collect::<Vec<_>>(),
+            [ClientMessage::Join(JoinRequest {
+                name: "Viper".into(),
+                skin: 8,
+            })]
+        );
+
+        app.world_mut().send_event(GameFinished(GameRecord {
+            mode: GameMode::Classic,
+            score: 120,
+            length: 30,
+            kills: 2,
+            duration: 60.0,
+            ended_at: 0,
+        }));
+        app.update();
+        assert_eq!(
+            to_server.try_iter().collect::<Vec<_>>(),
+            [
+                ClientMessage::SubmitResult(GameResult {
+                    score: 120,
+                    kills: 2,
+                    duration: 60.0,
+                }),
+                ClientMessage::FetchLeaderboards,
+            ]
+        );
+
+        let boards = Leaderboards {
+            daily: vec![LeaderboardEntry {
+                name: "Viper".into(),
+                score: 120,
+            }],
+            ..default()
+        };
+        from_server.send(ServerMessage::Leaderboards(boards.clone())).unwrap();
+        app.update();
+        assert_eq!(app.world().resource::<RankedLeaderboards>().0, Some(boards));
+    }
+
+    #[test]
+    fn the_server_rules_and_map_replace_the_local_config()
+    {
+        let (outgoing, _to_server) = mpsc::channel();
+        let (from_server, incoming) = mpsc::channel();
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(ServerConnection::from_channels(outgoing, incoming, None))
+            .init_resource::<RankedLeaderboards>()
+            .init_resource::<Config>()
+            .init_resource::<RemoteSnakes>()
+            .insert_resource(ConfigReloader::new(std::env::temp_dir().join("unused-config.ron")))
+            .add_event::<ServerMessageReceived>()
+            .add_systems(Update, (receive_server_messages, handle_server_messages).chain());
+
+        let mut rules = GameConfig::default();
+        rules.arena.map_radius = 1500.0;
+        rules.bots.enabled = false;
+        from_server.send(ServerMessage::Rules(Box::new(rules.clone()))).unwrap();
+        app.update();
+
+        assert_eq!(app.world().resource::<Config>().0, rules);
+        assert!(app.world().resource::<ConfigReloader>().overridden);
+
+        from_server
+            .send(ServerMessage::LoadMap(MapChoice {
+                name: "future.ron".into(),
+                version: MAP_VERSION + 1,
+            }))
+            .unwrap();
+        app.update();
+        assert_eq!(app.world().resource::<Config>().arena.map, None);
+
+        from_server
+            .send(ServerMessage::LoadMap(MapChoice {
+                name: "crossroads.ron".into(),
+                version: MAP_VERSION,
+            }))
+            .unwrap();
+        app.update();
+        assert_eq!(app.world().resource::<Config>().arena.map.as_deref(), Some("crossroads.ron"));
+    }
+
+    #[test]
+    fn the_player_is_reported_and_the_server_summary_fills_the_minimap()
+    {
+        let (outgoing, to_server) = mpsc::channel();
+        let (from_server, incoming) = mpsc::channel();
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(300)))
+            .insert_resource(ServerConnection::from_channels(outgoing, incoming, None))
+            .init_resource::<RankedLeaderboards>()
+            .init_resource::<Config>()
+            .init_resource::<RemoteSnakes>()
+            .insert_resource(ConfigReloader::new(std::env::temp_dir().join("unused-config.ron")))
+            .init_resource::<MinimapData>()
+            .add_event::<ServerMessageReceived>()
+            .add_systems(
+                Update,
+                (receive_server_messages, handle_server_messages, report_player_snake).chain(),
+            );
+
+        let mut snake = Snake::new(0);
+        snake.mass = 40.0;
+        let score = snake.score();
+        app.world_mut().spawn((
+            Player::new(Color::WHITE),
+            snake,
+            SnakeBody {
+                radius: 12.0,
+                ..default()
+            },
+            Transform::from_xyz(50.0, -20.0, 0.0),
+        ));
+
+        let reports = |to_server: &mpsc::Receiver<ClientMessage>| {
+            to_server
+                .try_iter()
+                .filter(|message| matches!(message, ClientMessage::ReportSnake(_)))
+                .collect::<Vec<_>>()
+        };
+        for _ in 0..4 {
+            app.update();
+        }
+        assert_eq!(
+            reports(&to_server),
+            [ClientMessage::ReportSnake(SnakeReport {
+                position: [50.0, -20.0],
+                radius: 12.0,
+                score,
+            })]
+        );
+
+        let summary = MinimapSummary {
+            map_radius: 1600.0,
+            snakes: vec![MinimapSnake {
+                id: 7,
+                position: [300.0, 0.0],
+                radius: 20.0,
+                score: 900,
+            }],
+        };
+        from_server.send(ServerMessage::MinimapSummary(summary.clone())).unwrap();
+        from_server
+            .send(ServerMessage::SnakeJoined {
+                id: 7,
+                name: "Cobra".into(),
+                skin: 3,
+            })
+            .unwrap();
+        app.update();
+        assert_eq!(app.world().resource::<MinimapData>().summary, summary);
+        let remote = &app.world().resource::<RemoteSnakes>().0[&7];
+        assert_eq!((remote.name.as_str(), remote.skin), ("Cobra", 3));
+    }
+}
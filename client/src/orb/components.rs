@@ -1,8 +1,69 @@
 use bevy::prelude::*;
 
+use crate::core::components::PowerUp;
+
 #[derive(Component, Clone, Debug)]
 pub struct Orb
 {
     pub value: u32,
     pub radius: f32,
 }
+
+/// Orbs dropped by snakes only last a while. Natural orbs have no lifetime and are the only ones the
+/// spawner counts towards its target.
+#[derive(Component, Clone, Debug)]
+pub struct OrbLifetime
+{
+    pub timer: Timer,
+}
+
+impl OrbLifetime
+{
+    pub fn new(seconds: f32) -> Self
+    {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
+
+/// A rare orb that grants a temporary power up to the snake that eats it.
+#[derive(Component, Clone, Debug)]
+pub struct PowerOrb
+{
+    pub power_up: PowerUp,
+}
+
+/// Claims an orb for the snake whose magnet caught it, so no other snake can eat it.
+#[derive(Component, Clone, Debug)]
+pub struct OrbMagnetized
+{
+    pub owner: Entity,
+}
+
+/// An orb a snake dropped while boosting, which its own magnet ignores for a moment.
+#[derive(Component, Clone, Debug)]
+pub struct OrbDroppedBy
+{
+    pub snake: Entity,
+    pub timer: Timer,
+}
+
+/// A death orb flying out of the body segment it came from.
+#[derive(Component, Clone, Debug)]
+pub struct OrbBurst
+{
+    pub from: Vec2,
+    pub to: Vec2,
+    pub timer: Timer,
+}
+
+/// An eaten orb being pulled into the head of `target`. It is no longer an [`Orb`] at this point.
+#[derive(Component, Clone, Debug)]
+pub struct OrbSuck
+{
+    pub target: Entity,
+    pub from: Vec2,
+    pub radius: f32,
+    pub timer: Timer,
+}
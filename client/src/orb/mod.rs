@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use systems::*;
 
 pub mod components;
+pub mod resources;
 pub mod systems;
 
 pub struct OrbPlugin;
@@ -10,6 +11,16 @@ impl Plugin for OrbPlugin
 {
     fn build(&self, app: &mut App)
     {
-        app.add_systems(Update, spawn_orbs);
+        app.init_resource::<resources::OrbTable>().add_systems(
+            Update,
+            (
+                spawn_orbs,
+                expire_orbs,
+                despawn_orbs_outside_arena,
+                animate_orb_bursts,
+                animate_orb_suck,
+                pulse_idle_orbs,
+            ),
+        );
     }
 }
@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+use rand::Rng;
+use slither_common::config::OrbConfig;
+
+use crate::constants::*;
+
+/// One size of natural food. Tiers are picked with a chance proportional to their weight.
+#[derive(Clone, Debug)]
+pub struct OrbTier
+{
+    pub radius: f32,
+    pub value: u32,
+    pub weight: u32,
+}
+
+/// The mix of natural orbs spawned in the arena.
+#[derive(Resource, Clone, Debug)]
+pub struct OrbTable
+{
+    pub tiers: Vec<OrbTier>,
+    /// Chance for each natural orb to be a power orb instead.
+    pub power_orb_chance: f64,
+}
+
+impl OrbTable
+{
+    pub fn pick(&self, rng: &mut impl Rng) -> &OrbTier
+    {
+        let total_weight: u32 = self.tiers.iter().map(|tier| tier.weight).sum();
+        let mut roll = rng.gen_range(0..total_weight.max(1));
+
+        for tier in &self.tiers {
+            if roll < tier.weight {
+                return tier;
+            }
+            roll -= tier.weight;
+        }
+        &self.tiers[0]
+    }
+}
+
+impl Default for OrbTable
+{
+    fn default() -> Self
+    {
+        Self {
+            tiers: vec![
+                OrbTier {
+                    radius: ORB_RADIUS,
+                    value: ORB_VALUE,
+                    weight: 70,
+                },
+                OrbTier {
+                    radius: MEDIUM_ORB_RADIUS,
+                    value: MEDIUM_ORB_VALUE,
+                    weight: 25,
+                },
+                OrbTier {
+                    radius: LARGE_ORB_RADIUS,
+                    value: LARGE_ORB_VALUE,
+                    weight: 5,
+                },
+            ],
+            power_orb_chance: OrbConfig::default().power_orb_chance,
+        }
+    }
+}
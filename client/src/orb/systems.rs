@@ -1,38 +1,78 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
 use bevy::sprite::MaterialMesh2dBundle;
+use rand::Rng;
 
-use super::components::Orb;
+use super::components::{Orb, OrbBurst, OrbLifetime, OrbMagnetized, OrbSuck, PowerOrb};
+use super::resources::OrbTable;
+use crate::config::resources::Config;
 use crate::constants::*;
-use crate::core::resources::GlobalGameState;
+use crate::core::components::PowerUp;
+use crate::core::resources::{ArenaRadius, GlobalGameState, RenderHandles};
+use crate::map::resources::ArenaFeatures;
 use crate::utils::*;
 
+/// Tops natural orbs back up to the target at a steady rate, placing each one in a sparse part of the arena.
 pub fn spawn_orbs(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
+    time: Res<Time>,
+    mut render_handles: ResMut<RenderHandles>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     global_game_state: Res<GlobalGameState>,
-    existing_orbs: Query<Entity, With<Orb>>,
+    config: Res<Config>,
+    arena: Res<ArenaRadius>,
+    features: Res<ArenaFeatures>,
+    orb_table: Res<OrbTable>,
+    natural_orbs: Query<&Transform, (With<Orb>, Without<OrbLifetime>)>,
+    mut spawn_budget: Local<f32>,
 )
 {
-    let desired_orb_count = calculate_desired_orb_count(&global_game_state);
+    let mut rng = rand::thread_rng();
+
+    let desired_orb_count =
+        (calculate_desired_orb_count(&global_game_state, config.orbs.per_snake) as f32 * features.orb_density) as usize;
 
-    let current_orb_count = existing_orbs.iter().count();
+    let current_orb_count = natural_orbs.iter().count();
+
+    if current_orb_count >= desired_orb_count {
+        *spawn_budget = 0.0;
+        return;
+    }
 
-    if current_orb_count < desired_orb_count {
-        let orbs_to_spawn = desired_orb_count - current_orb_count;
+    *spawn_budget += config.orbs.respawn_rate * time.delta_seconds();
+    let orbs_to_spawn = (desired_orb_count - current_orb_count).min(*spawn_budget as usize);
+    *spawn_budget -= orbs_to_spawn as f32;
+
+    if orbs_to_spawn > 0 {
+        let mut density = OrbDensity::from_positions(natural_orbs.iter().map(|transform| transform.translation.truncate()));
 
         for _ in 0..orbs_to_spawn {
-            let position = generate_random_position_within_radius(MAP_RADIUS);
-            let color = generate_random_color();
+            // Feeding grounds get their share of the orbs, the rest go wherever the arena is sparse
+            let position = match features.feeding_ground_for(rng.gen()) {
+                Some(ground) => Vec2::from_array(ground.center) + generate_random_position_within_radius(ground.radius),
+                None => density.sparse_position(&mut rng, arena.radius),
+            };
+            if !arena.contains(position, ORB_RADIUS) || features.hits_obstacle(position.to_array(), ORB_RADIUS) {
+                continue;
+            }
+            density.add(position);
+
+            if rng.gen_bool(orb_table.power_orb_chance) {
+                let power_up = PowerUp::ALL[rng.gen_range(0..PowerUp::ALL.len())];
+                spawn_power_orb(&mut commands, &mut render_handles, &mut materials, power_up, position);
+                continue;
+            }
 
+            let tier = orb_table.pick(&mut rng);
             spawn_singlular_orb(
                 &mut commands,
-                &mut meshes,
+                &mut render_handles,
                 &mut materials,
-                color,
+                generate_random_color(),
                 position,
-                ORB_RADIUS,
-                ORB_VALUE,
+                tier.radius,
+                tier.value,
             );
         }
     }
@@ -40,8 +80,8 @@ pub fn spawn_orbs(
 
 pub fn spawn_singlular_orb(
     commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
+    render_handles: &mut RenderHandles,
+    materials: &mut Assets<ColorMaterial>,
     color: Color,
     position: Vec2,
     radius: f32,
@@ -52,10 +92,11 @@ pub fn spawn_singlular_orb(
         .spawn((
             Orb { radius, value },
             MaterialMesh2dBundle {
-                mesh: meshes.add(Circle::new(ORB_RADIUS)).into(), // Orbs have a radius of 5.0
-                material: materials.add(ColorMaterial::from(color)),
+                mesh: render_handles.circle.clone().into(),
+                material: render_handles.material(materials, color),
                 transform: Transform {
                     translation: position.extend(Z_ORBS),
+                    scale: Vec3::splat(radius),
                     ..default()
                 },
                 ..default()
@@ -64,12 +105,345 @@ pub fn spawn_singlular_orb(
         .id()
 }
 
+/// Spawns a glowing orb that grants `power_up` when eaten.
+pub fn spawn_power_orb(
+    commands: &mut Commands,
+    render_handles: &mut RenderHandles,
+    materials: &mut Assets<ColorMaterial>,
+    power_up: PowerUp,
+    position: Vec2,
+) -> Entity
+{
+    let color = Color::LinearRgba(power_up.color().to_linear() * POWER_ORB_GLOW);
+    let orb = spawn_singlular_orb(
+        commands,
+        render_handles,
+        materials,
+        color,
+        position,
+        POWER_ORB_RADIUS,
+        ORB_VALUE,
+    );
+    commands.entity(orb).insert(PowerOrb { power_up });
+    orb
+}
+
+/// Orbs are sized so their area grows with their value, like the natural orb tiers.
+pub fn orb_radius_for_value(value: u32) -> f32
+{
+    ORB_RADIUS * (value.max(1) as f32 / ORB_VALUE as f32).sqrt()
+}
+
+/// Turns an eaten orb into an animation that pulls it into the head of `target`.
+pub fn start_orb_suck(commands: &mut Commands, orb_entity: Entity, orb: &Orb, position: Vec2, target: Entity)
+{
+    commands
+        .entity(orb_entity)
+        .remove::<(Orb, OrbBurst, OrbMagnetized)>()
+        .insert(OrbSuck {
+            target,
+            from: position,
+            radius: orb.radius,
+            timer: Timer::from_seconds(ORB_SUCK_DURATION, TimerMode::Once),
+        });
+}
+
+/// Moves death orbs out of the body with an ease out, so they burst fast and settle softly.
+pub fn animate_orb_bursts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut orb_query: Query<(Entity, &mut OrbBurst, &mut Transform)>,
+)
+{
+    for (entity, mut burst, mut transform) in orb_query.iter_mut() {
+        burst.timer.tick(time.delta());
+
+        let position = burst.from.lerp(burst.to, ease_out_cubic(burst.timer.fraction()));
+        transform.translation = position.extend(transform.translation.z);
+
+        if burst.timer.finished() {
+            commands.entity(entity).remove::<OrbBurst>();
+        }
+    }
+}
+
+/// Pulls eaten orbs into the head that ate them, shrinking them on the way in.
+pub fn animate_orb_suck(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut orb_query: Query<(Entity, &mut OrbSuck, &mut Transform)>,
+    target_query: Query<&GlobalTransform, Without<OrbSuck>>,
+)
+{
+    for (entity, mut suck, mut transform) in orb_query.iter_mut() {
+        let Ok(target) = target_query.get(suck.target) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        if suck.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let t = ease_in_cubic(suck.timer.fraction());
+        let position = suck.from.lerp(target.translation().truncate(), t);
+        let size = suck.radius * (1.0 - t);
+        transform.translation = position.extend(transform.translation.z);
+        transform.scale = Vec3::new(size, size, 1.0);
+    }
+}
+
+/// Idle orbs softly breathe. The phase comes from the position so neighbouring orbs do not pulse in sync.
+pub fn pulse_idle_orbs(
+    time: Res<Time>,
+    mut orb_query: Query<(&Orb, &mut Transform, Option<&OrbLifetime>), Without<OrbBurst>>,
+)
+{
+    let elapsed = time.elapsed_seconds();
+
+    for (orb, mut transform, lifetime) in orb_query.iter_mut() {
+        let phase = transform.translation.x * 0.37 + transform.translation.y * 0.73;
+        // Orbs near the end of their lifetime shrink away
+        let fade = lifetime.map_or(1.0, |lifetime| (lifetime.timer.remaining_secs() / ORB_FADE_DURATION).min(1.0));
+        let size = orb.radius * fade * (1.0 + ORB_PULSE_AMOUNT * (elapsed * ORB_PULSE_SPEED + phase).sin());
+        transform.scale = Vec3::new(size, size, 1.0);
+    }
+}
+
+/// Removes the orbs the edge passes over as the arena closes in.
+pub fn despawn_orbs_outside_arena(
+    mut commands: Commands,
+    arena: Res<ArenaRadius>,
+    orb_query: Query<(Entity, &Transform), With<Orb>>,
+)
+{
+    if !arena.is_closing() {
+        return;
+    }
+
+    for (entity, transform) in orb_query.iter() {
+        if !arena.contains(transform.translation.truncate(), 0.0) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Removes dropped orbs nobody ate in time.
+pub fn expire_orbs(mut commands: Commands, time: Res<Time>, mut orb_query: Query<(Entity, &mut OrbLifetime), With<Orb>>)
+{
+    for (entity, mut lifetime) in orb_query.iter_mut() {
+        if lifetime.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Counts natural orbs per grid cell so new ones can go where food is scarce.
+pub struct OrbDensity
+{
+    cells: HashMap<IVec2, u32>,
+}
+
+impl OrbDensity
+{
+    pub fn from_positions(positions: impl Iterator<Item = Vec2>) -> Self
+    {
+        let mut density = Self { cells: HashMap::new() };
+        for position in positions {
+            density.add(position);
+        }
+        density
+    }
+
+    pub fn add(&mut self, position: Vec2)
+    {
+        *self.cells.entry(Self::cell(position)).or_default() += 1;
+    }
+
+    pub fn count_at(&self, position: Vec2) -> u32
+    {
+        self.cells.get(&Self::cell(position)).copied().unwrap_or(0)
+    }
+
+    /// Tries a few random spots in the arena and returns the one in the emptiest cell.
+    pub fn sparse_position(&self, rng: &mut impl Rng, map_radius: f32) -> Vec2
+    {
+        (0..ORB_SPAWN_CANDIDATES)
+            .map(|_| {
+                let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                // The square root spreads candidates evenly over the area of the arena
+                let distance = map_radius * rng.gen_range(0.0f32..1.0).sqrt();
+                Vec2::from_angle(angle) * distance
+            })
+            .min_by_key(|&position| self.count_at(position))
+            .unwrap_or(Vec2::ZERO)
+    }
+
+    fn cell(position: Vec2) -> IVec2
+    {
+        (position / ORB_DENSITY_CELL_SIZE).floor().as_ivec2()
+    }
+}
+
 /// Calculates the desired number of orbs to spawn based on the number of snakes and total orbs in the game
-fn calculate_desired_orb_count(global_game_state: &GlobalGameState) -> usize
+fn calculate_desired_orb_count(global_game_state: &GlobalGameState, orbs_per_snake: usize) -> usize
 {
     // Base orbs per player
-    let base_orbs = ORB_SPAWN_PER_PLAYER * global_game_state.total_snakes;
+    let base_orbs = orbs_per_snake * global_game_state.total_snakes;
 
     // Cap or adjust total orbs based on map size or other factors if needed
     base_orbs.min(global_game_state.total_orbs)
 }
+
+#[cfg(test)]
+mod tests
+{
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    fn headless_app() -> App
+    {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)))
+            .add_systems(Update, (animate_orb_bursts, animate_orb_suck, pulse_idle_orbs, expire_orbs));
+        app
+    }
+
+    #[test]
+    fn eaten_orbs_are_pulled_into_the_head()
+    {
+        let mut app = headless_app();
+        let head = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(100.0, 0.0, 0.0),
+                GlobalTransform::from_xyz(100.0, 0.0, 0.0),
+            ))
+            .id();
+        let orb = app
+            .world_mut()
+            .spawn((
+                OrbSuck {
+                    target: head,
+                    from: Vec2::ZERO,
+                    radius: ORB_RADIUS,
+                    timer: Timer::from_seconds(ORB_SUCK_DURATION, TimerMode::Once),
+                },
+                Transform::default(),
+            ))
+            .id();
+
+        app.update();
+        app.update();
+        let transform = app.world().get::<Transform>(orb).unwrap();
+        assert!(transform.translation.x > 0.0 && transform.translation.x < 100.0);
+        assert!(transform.scale.x < ORB_RADIUS);
+
+        for _ in 0..5 {
+            app.update();
+        }
+        assert!(app.world().get_entity(orb).is_none());
+    }
+
+    #[test]
+    fn death_orbs_settle_where_their_burst_ends()
+    {
+        let mut app = headless_app();
+        let orb = app
+            .world_mut()
+            .spawn((
+                Orb {
+                    radius: ORB_RADIUS,
+                    value: ORB_VALUE,
+                },
+                OrbBurst {
+                    from: Vec2::ZERO,
+                    to: Vec2::new(DEATH_BURST_DISTANCE, 0.0),
+                    timer: Timer::from_seconds(DEATH_BURST_DURATION, TimerMode::Once),
+                },
+                Transform::default(),
+            ))
+            .id();
+
+        for _ in 0..20 {
+            app.update();
+        }
+
+        let transform = app.world().get::<Transform>(orb).unwrap();
+        assert_eq!(transform.translation.truncate(), Vec2::new(DEATH_BURST_DISTANCE, 0.0));
+        assert!(app.world().get::<OrbBurst>(orb).is_none());
+    }
+
+    #[test]
+    fn dropped_orbs_fade_and_expire()
+    {
+        let mut app = headless_app();
+        let orb = app
+            .world_mut()
+            .spawn((
+                Orb {
+                    radius: ORB_RADIUS,
+                    value: ORB_VALUE,
+                },
+                OrbLifetime::new(1.0),
+                Transform::default(),
+            ))
+            .id();
+
+        for _ in 0..15 {
+            app.update();
+        }
+        // Well into the fade, so even at the top of its pulse the orb is smaller than normal
+        assert!(app.world().get::<Transform>(orb).unwrap().scale.x < ORB_RADIUS);
+
+        for _ in 0..10 {
+            app.update();
+        }
+        assert!(app.world().get_entity(orb).is_none());
+    }
+
+    #[test]
+    fn new_orbs_avoid_crowded_cells()
+    {
+        let crowded = Vec2::new(10.0, 10.0);
+        let density = OrbDensity::from_positions(std::iter::repeat_n(crowded, 100));
+        let mut rng = rand::thread_rng();
+
+        let in_crowded_cell = (0..100)
+            .filter(|_| density.count_at(density.sparse_position(&mut rng, 1000.0)) > 0)
+            .count();
+        // A candidate only lands in the crowded cell when every candidate does
+        assert!(in_crowded_cell < 5);
+    }
+
+    #[test]
+    fn idle_orbs_pulse_around_their_radius()
+    {
+        let mut app = headless_app();
+        let orb = app
+            .world_mut()
+            .spawn((
+                Orb {
+                    radius: ORB_RADIUS,
+                    value: ORB_VALUE,
+                },
+                Transform::default(),
+            ))
+            .id();
+
+        let mut sizes = Vec::new();
+        for _ in 0..10 {
+            app.update();
+            sizes.push(app.world().get::<Transform>(orb).unwrap().scale.x);
+        }
+
+        let max_size = ORB_RADIUS * (1.0 + ORB_PULSE_AMOUNT);
+        assert!(sizes.iter().all(|&size| size > 0.0 && size <= max_size + f32::EPSILON));
+        assert!(sizes.windows(2).any(|pair| pair[0] != pair[1]));
+    }
+}
@@ -1,17 +1,15 @@
 use bevy::prelude::*;
 
-use crate::constants::*;
-
 #[derive(Component)]
 pub struct ScoreText;
 
+#[derive(Component)]
+pub struct DeathScreen;
+
 #[derive(Component, Clone, Debug)]
 pub struct Player
 {
-    pub score: u32,
-    pub radius: f32,
     pub color: Color,
-    pub boost_timer: f32,
     pub orb_spawn_timer: f32,
 }
 
@@ -20,10 +18,7 @@ impl Player
     pub fn new(color: Color) -> Self
     {
         Player {
-            score: 0,
-            radius: PLAYER_DEFAULT_RADIUS,
             color,
-            boost_timer: 0.0,
             orb_spawn_timer: 0.0,
         }
     }
@@ -4,13 +4,26 @@ pub mod systems;
 use bevy::prelude::*;
 use systems::*;
 
+use crate::core::events::MatchStarted;
+use crate::core::systems::update_arena_radius;
+use crate::mode::systems::start_match;
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin
 {
     fn build(&self, app: &mut App)
     {
-        app.add_systems(Startup, (spawn_score_text, spawn_player).chain())
-            .add_systems(Update, (move_player, update_player_camera, update_score_text));
+        app.add_systems(Startup, spawn_score_text).add_systems(
+            Update,
+            (
+                spawn_player.after(start_match).run_if(on_event::<MatchStarted>()),
+                // Move before the arena checks so collisions and the edge see this frame's positions
+                move_player.before(update_arena_radius),
+                update_score_text,
+                show_death_screen,
+                close_death_screen,
+            ),
+        );
     }
 }
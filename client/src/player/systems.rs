@@ -1,35 +1,41 @@
-use std::collections::VecDeque;
-
 use bevy::prelude::*;
 use bevy::sprite::MaterialMesh2dBundle;
 
 use super::components::*;
+use crate::config::resources::Config;
 use crate::constants::*;
-use crate::core::components::{Segment, SegmentPositionHistory, Snake, SnakeSegment};
-use crate::core::resources::GlobalGameState;
+use crate::core::components::{has_power_up, PowerUp, PowerUps, SegmentPositionHistory, Snake, SnakeBody, SpawnProtection};
+use crate::core::events::{BoostEnded, BoostStarted, SegmentsRemoved, SnakeDied, SnakeSpawned};
+use crate::core::resources::{ArenaRadius, CameraTarget, RenderHandles, SelectedSkin, SnakeGrowth};
+use crate::map::resources::ArenaFeatures;
+use crate::map::systems::SpawnSelector;
+use crate::orb::components::{OrbDroppedBy, OrbLifetime};
 use crate::orb::systems::spawn_singlular_orb;
-use crate::utils::*;
 
 pub fn spawn_player(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
+    mut render_handles: ResMut<RenderHandles>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    mut global_game_state: ResMut<GlobalGameState>,
+    mut camera_target: ResMut<CameraTarget>,
+    mut spawn_events: EventWriter<SnakeSpawned>,
+    selected_skin: Res<SelectedSkin>,
+    spawn_selector: SpawnSelector,
 )
 {
-    let player_spawn_location = generate_random_position_within_radius(MAP_RADIUS);
+    let player_spawn_location = spawn_selector.pick(&[]);
     let player_size = Vec3::new(PLAYER_DEFAULT_RADIUS, PLAYER_DEFAULT_RADIUS, Z_PLAYER_SEGMENTS);
 
-    let player = Player::new(generate_random_color());
+    let snake = Snake::new(selected_skin.0);
+    let player = Player::new(snake.color);
 
     let player_entity = commands
         .spawn((
             player.clone(),
-            Snake::new(player.color),
+            snake,
             Name::new("Player 1"),
             MaterialMesh2dBundle {
-                mesh: meshes.add(Circle::new(1.0)).into(),
-                material: materials.add(ColorMaterial::from(player.color)),
+                mesh: render_handles.circle.clone().into(),
+                material: render_handles.material(&mut materials, player.color),
                 transform: Transform {
                     scale: player_size,
                     translation: player_spawn_location.extend(Z_PLAYER_SEGMENTS),
@@ -38,236 +44,179 @@ pub fn spawn_player(
                 ..default()
             },
             SegmentPositionHistory::default(),
+            SnakeBody::default(),
+            PowerUps::default(),
+            SpawnProtection::default(),
         ))
         .id();
 
-    // Spawn initial segments
-    let mut snake_segments = VecDeque::new();
-    for i in 0..PLAYER_DEFAULT_LENGTH {
-        let segment_entity = commands
-            .spawn((
-                Segment {
-                    index: i,
-                    radius: PLAYER_DEFAULT_RADIUS,
-                },
-                SnakeSegment { owner: player_entity },
-                MaterialMesh2dBundle {
-                    mesh: meshes.add(Circle::new(1.0)).into(),
-                    material: materials.add(player.color),
-                    transform: Transform {
-                        translation: Vec3::new(-(i as f32) * SEGMENT_SPACING, 0.0, Z_PLAYER_SEGMENTS),
-                        scale: player_size,
-                        ..default()
-                    },
-                    ..default()
-                },
-            ))
-            .id();
-        snake_segments.push_back(segment_entity);
-    }
-
-    // Add segments to the Snake component
-    if let Some(mut snake) = commands.get_entity(player_entity) {
-        snake.insert(Snake {
-            length: PLAYER_DEFAULT_LENGTH,
-            segments: snake_segments,
-            color: player.color,
-        });
-    }
-
-    global_game_state.total_snakes += 1;
+    spawn_events.send(SnakeSpawned { snake: player_entity });
+    camera_target.0 = Some(player_entity);
 }
 
 pub fn move_player(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
+    mut render_handles: ResMut<RenderHandles>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     time: Res<Time>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut query_set: ParamSet<(
-        Query<(Entity, &mut Transform, &mut SegmentPositionHistory, &mut Player, &mut Snake)>,
-        Query<(&mut Transform, &Segment, &SnakeSegment)>,
+    growth: Res<SnakeGrowth>,
+    config: Res<Config>,
+    arena: Res<ArenaRadius>,
+    features: Res<ArenaFeatures>,
+    mut boost_started_events: EventWriter<BoostStarted>,
+    mut boost_ended_events: EventWriter<BoostEnded>,
+    mut removed_events: EventWriter<SegmentsRemoved>,
+    mut player_query: Query<(
+        Entity,
+        &mut Transform,
+        &mut SegmentPositionHistory,
+        &mut Player,
+        &mut Snake,
+        &SnakeBody,
+        Option<&PowerUps>,
     )>,
 )
 {
-    let mut player_movements: Vec<(Entity, Vec3, Vec<Vec3>)> = Vec::new();
-
-    // First, update player position and collect movements
+    for (player_entity, mut transform, mut segment_history, mut player, mut snake, body, power_ups) in
+        player_query.iter_mut()
     {
-        let mut player_query = query_set.p0();
-        for (player_entity, mut transform, mut segment_history, mut player, mut snake) in player_query.iter_mut() {
-            let mut direction = Vec3::ZERO;
-            let mut speed = PLAYER_SPEED;
-            let delta_seconds = time.delta_seconds();
-
-            // Movement input handling
-            if keyboard_input.pressed(KeyCode::ArrowUp) {
-                direction.y += 1.0;
-            }
-            if keyboard_input.pressed(KeyCode::ArrowDown) {
-                direction.y -= 1.0;
-            }
-            if keyboard_input.pressed(KeyCode::ArrowLeft) {
-                direction.x -= 1.0;
-            }
-            if keyboard_input.pressed(KeyCode::ArrowRight) {
-                direction.x += 1.0;
-            }
-
-            let mut is_boosting = false;
-
-            if keyboard_input.pressed(KeyCode::Space) && player.score >= SCORE_NEEDED_FOR_BOOSTING {
-                is_boosting = true;
-            }
-
-            if is_boosting {
-                speed *= 2.0;
+        let mut direction = Vec3::ZERO;
+        let mut speed = growth.speed(snake.mass) * features.speed_multiplier_at(transform.translation.truncate().to_array());
+        if has_power_up(power_ups, PowerUp::Speed) {
+            speed *= POWER_UP_SPEED_MULTIPLIER;
+        }
+        let delta_seconds = time.delta_seconds();
 
-                // Accumulate time for score deduction
-                player.boost_timer += delta_seconds;
+        // Movement input handling
+        if keyboard_input.pressed(KeyCode::ArrowUp) {
+            direction.y += 1.0;
+        }
+        if keyboard_input.pressed(KeyCode::ArrowDown) {
+            direction.y -= 1.0;
+        }
+        if keyboard_input.pressed(KeyCode::ArrowLeft) {
+            direction.x -= 1.0;
+        }
+        if keyboard_input.pressed(KeyCode::ArrowRight) {
+            direction.x += 1.0;
+        }
 
-                // Deduct score every half second of boosting
-                if player.boost_timer >= 0.5 {
-                    let score_deduction = player.boost_timer.floor() as u32;
-                    player.score = player.score.saturating_sub(score_deduction);
-                    player.boost_timer -= score_deduction as f32;
+        let mut is_boosting = false;
 
-                    // Remove segments based on the score deduction
-                    remove_segment(&mut commands, &mut snake, score_deduction);
-                }
+        if keyboard_input.pressed(KeyCode::Space) && snake.mass >= config.snake.mass_needed_for_boosting {
+            is_boosting = true;
+        }
 
-                // Handle orb spawning during boost
-                player.orb_spawn_timer += delta_seconds;
-                if player.orb_spawn_timer >= ORB_SPAWN_INTERVAL {
-                    if direction != Vec3::ZERO {
-                        direction = direction.normalize();
-                    } else {
-                        direction = segment_history
-                            .positions
-                            .get(1)
-                            .map_or(Vec3::ZERO, |prev_pos| (transform.translation - *prev_pos).normalize());
-                    }
-
-                    let collection_threshold = player.radius + BOOST_ORB_RADIUS;
-                    let orb_position =
-                        transform.translation - direction * (collection_threshold + ORB_SPAWN_DISTANCE_MARGIN);
-
-                    spawn_singlular_orb(
-                        &mut commands,
-                        &mut meshes,
-                        &mut materials,
-                        player.color,
-                        orb_position.truncate(),
-                        BOOST_ORB_RADIUS,
-                        ORB_VALUE,
-                    );
-
-                    player.orb_spawn_timer -= ORB_SPAWN_INTERVAL;
-                }
+        if is_boosting != snake.is_boosting {
+            snake.is_boosting = is_boosting;
+            if is_boosting {
+                boost_started_events.send(BoostStarted { snake: player_entity });
             } else {
-                player.boost_timer = 0.0;
-                player.orb_spawn_timer = 0.0;
+                boost_ended_events.send(BoostEnded { snake: player_entity });
             }
+        }
 
-            // Movement and boundary checks
-            if direction != Vec3::ZERO {
-                direction = direction.normalize();
-                let new_translation = transform.translation + direction * speed * delta_seconds;
+        if is_boosting {
+            speed *= config.snake.boost_speed_multiplier;
+
+            // Boosting burns mass and the tail shrinks along with it
+            let positions = shed_mass(&mut snake, body, &growth, config.snake.boost_mass_cost * delta_seconds);
+            if !positions.is_empty() {
+                removed_events.send(SegmentsRemoved {
+                    snake: player_entity,
+                    positions,
+                    radius: body.radius,
+                    color: snake.color,
+                });
+            }
 
-                let distance_from_center = new_translation.truncate().length();
-                if distance_from_center + player.radius <= MAP_RADIUS {
-                    transform.translation = new_translation;
+            // Handle orb spawning during boost
+            player.orb_spawn_timer += delta_seconds;
+            if player.orb_spawn_timer >= ORB_SPAWN_INTERVAL {
+                if direction != Vec3::ZERO {
+                    direction = direction.normalize();
                 } else {
-                    let clamped_position = new_translation.truncate().normalize() * (MAP_RADIUS - player.radius);
-                    transform.translation = clamped_position.extend(transform.translation.z);
+                    direction = segment_history
+                        .positions
+                        .get(1)
+                        .map_or(Vec3::ZERO, |prev_pos| (transform.translation - *prev_pos).normalize());
                 }
-            }
-
-            // Update segment history
-            segment_history.positions.push_front(transform.translation);
-            if segment_history.positions.len() > MAX_SEGMENT_HISTORY {
-                segment_history.positions.pop_back();
-            }
 
-            player_movements.push((player_entity, transform.translation, segment_history.positions.clone().into()));
-
-            let new_radius = calculate_player_radius(player.score);
-            if (new_radius - player.radius).abs() > f32::EPSILON {
-                player.radius = new_radius;
-                transform.scale = Vec3::new(player.radius, player.radius, Z_PLAYER_SEGMENTS);
+                drop_boost_orb(
+                    &mut commands,
+                    &mut render_handles,
+                    &mut materials,
+                    &config,
+                    player_entity,
+                    player.color,
+                    transform.translation.truncate(),
+                    direction.truncate(),
+                    body.radius,
+                );
+
+                player.orb_spawn_timer -= ORB_SPAWN_INTERVAL;
             }
+        } else {
+            player.orb_spawn_timer = 0.0;
         }
-    }
 
-    // Then, update segment positions
-    let mut segment_query = query_set.p1();
-    for (player_entity, _player_pos, history) in player_movements {
-        for (mut segment_transform, segment, snake_segment) in segment_query.iter_mut() {
-            if snake_segment.owner == player_entity {
-                let index = (segment.index + 1) * POSITIONS_PER_SEGMENT;
-                if index < history.len() as u32 {
-                    segment_transform.translation = history[index as usize];
-                }
-            }
+        // Movement and boundary checks
+        if direction != Vec3::ZERO {
+            direction = direction.normalize();
+            snake.steering = direction.truncate();
+            let new_translation = transform.translation + direction * speed * delta_seconds;
+            let confined = arena.confine(new_translation.truncate(), body.radius);
+            transform.translation = confined.extend(transform.translation.z);
         }
-    }
-}
 
-pub fn remove_segment(commands: &mut Commands, snake: &mut Snake, segments_to_remove: u32)
-{
-    let segments_to_remove = segments_to_remove.min(snake.length);
-
-    for _ in 0..segments_to_remove {
-        if let Some(entity) = snake.segments.pop_back() {
-            commands.entity(entity).despawn();
-            snake.length = snake.length.saturating_sub(1);
-        }
+        // Update segment history
+        segment_history.record(transform.translation, growth.segment_count(snake.mass));
     }
 }
 
-/// Updates the player's camera to follow the player in the world
-/// todo - make the player camera zoom start small and scale with the player's radius in the future
-pub fn update_player_camera(
-    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), (With<Camera2d>, Without<Player>)>,
-    player_query: Query<(&Transform, &Player), (With<Player>, Without<Camera2d>)>,
-    time: Res<Time>,
+/// Drops a boost orb just behind a head moving along `heading`. The snake that dropped it can't pull it straight
+/// back in.
+pub fn drop_boost_orb(
+    commands: &mut Commands,
+    render_handles: &mut RenderHandles,
+    materials: &mut Assets<ColorMaterial>,
+    config: &Config,
+    snake: Entity,
+    color: Color,
+    head: Vec2,
+    heading: Vec2,
+    body_radius: f32,
 )
 {
-    let Ok((mut camera_transform, mut projection)) = camera_query.get_single_mut() else {
-        return;
-    };
-
-    let Ok((player_transform, player)) = player_query.get_single() else {
-        return;
-    };
-
-    // Update camera position with lerp
-    let target_pos = Vec3::new(
-        player_transform.translation.x,
-        player_transform.translation.y,
-        camera_transform.translation.z,
+    let collection_threshold = body_radius + BOOST_ORB_RADIUS;
+    let orb_position = head - heading * (collection_threshold + ORB_SPAWN_DISTANCE_MARGIN);
+
+    let orb = spawn_singlular_orb(
+        commands,
+        render_handles,
+        materials,
+        color,
+        orb_position,
+        BOOST_ORB_RADIUS,
+        ORB_VALUE,
     );
-    camera_transform.translation = camera_transform
-        .translation
-        .lerp(target_pos, time.delta_seconds() * CAM_LERP_FACTOR);
-
-    // Calculate desired zoom based on player radius
-    let base_scale = 1.0;
-    let radius_factor = player.radius / PLAYER_DEFAULT_RADIUS;
-    let target_scale = base_scale + (radius_factor - 1.0) * CAMERA_ZOOM_FACTOR;
-
-    // Clamp the zoom scale between min and max values
-    let target_scale = target_scale.clamp(MIN_CAMERA_ZOOM, MAX_CAMERA_ZOOM);
-
-    // Smoothly interpolate to the target scale
-    let current_scale = projection.scale;
-    projection.scale = lerp(current_scale, target_scale, time.delta_seconds() * CAMERA_ZOOM_LERP_FACTOR);
+    commands.entity(orb).insert((
+        OrbDroppedBy {
+            snake,
+            timer: Timer::from_seconds(ORB_DROP_MAGNET_IMMUNITY, TimerMode::Once),
+        },
+        OrbLifetime::new(config.orbs.boost_orb_lifetime),
+    ));
 }
 
-// Helper function for linear interpolation
-fn lerp(start: f32, end: f32, t: f32) -> f32
+/// Takes mass away from the snake and returns where the tail segments it no longer has were, so they can be
+/// animated away.
+pub fn shed_mass(snake: &mut Snake, body: &SnakeBody, growth: &SnakeGrowth, mass: f32) -> Vec<Vec2>
 {
-    start + (end - start) * t
+    snake.mass = (snake.mass - mass).max(0.0);
+    let segment_count = growth.segment_count(snake.mass) as usize;
+    body.points.iter().skip(segment_count).copied().collect()
 }
 
 pub fn spawn_score_text(mut commands: Commands, asset_server: Res<AssetServer>)
@@ -280,7 +229,6 @@ pub fn spawn_score_text(mut commands: Commands, asset_server: Res<AssetServer>)
                 font: asset_server.load("fonts/FiraSans-Bold.ttf"),
                 font_size: 30.0,
                 color: TEXT_COLOR,
-                ..default()
             },
         )
         .with_text_justify(JustifyText::Center)
@@ -293,17 +241,160 @@ pub fn spawn_score_text(mut commands: Commands, asset_server: Res<AssetServer>)
     ));
 }
 
-pub fn update_score_text(mut player_query: Query<&Player>, mut text_query: Query<&mut Text, With<ScoreText>>)
+pub fn update_score_text(
+    mut player_query: Query<(&Snake, Option<&PowerUps>), With<Player>>,
+    mut text_query: Query<&mut Text, With<ScoreText>>,
+)
 {
-    if let Ok(player) = player_query.get_single_mut() {
+    if let Ok((snake, power_ups)) = player_query.get_single_mut() {
         if let Ok(mut text) = text_query.get_single_mut() {
-            text.sections[0].value = format!("Score: {}", player.score);
+            // Active power ups are listed above the score with the seconds they have left
+            let power_ups: String = power_ups
+                .iter()
+                .flat_map(|power_ups| power_ups.active.iter())
+                .map(|(power_up, timer)| format!("{} {:.0}s\n", power_up.label(), timer.remaining_secs().ceil()))
+                .collect();
+            text.sections[0].value = format!("{power_ups}Score: {}", snake.score());
+        }
+    }
+}
+
+/// Shows the final stats of the local player once they get eaten.
+pub fn show_death_screen(
+    mut commands: Commands,
+    mut death_events: EventReader<SnakeDied>,
+    player_query: Query<(), With<Player>>,
+    asset_server: Res<AssetServer>,
+)
+{
+    for death in death_events.read() {
+        if !player_query.contains(death.victim) {
+            continue;
         }
+
+        let headline = match &death.killer_name {
+            Some(killer_name) => format!("You were eaten by {killer_name}"),
+            None => "You died".to_string(),
+        };
+
+        let text_style = TextStyle {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 24.0,
+            color: TEXT_COLOR,
+        };
+
+        commands
+            .spawn((
+                DeathScreen,
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            padding: UiRect::all(Val::Px(20.0)),
+                            ..default()
+                        },
+                        background_color: BackgroundColor(BLACK_COLOR.with_alpha(0.8)),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            headline,
+                            TextStyle {
+                                font_size: 36.0,
+                                color: LEADERBOARD_COLOR,
+                                ..text_style.clone()
+                            },
+                        ));
+                        parent.spawn(TextBundle::from_section(
+                            format!(
+                                "Score: {}\nLength: {}\nKills: {}",
+                                death.victim_score, death.victim_length, death.victim_kills
+                            ),
+                            text_style.clone(),
+                        ));
+                        parent.spawn(TextBundle::from_section(
+                            "Press Enter to keep spectating",
+                            TextStyle {
+                                font_size: 16.0,
+                                ..text_style.clone()
+                            },
+                        ));
+                    });
+            });
+    }
+}
+
+pub fn close_death_screen(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    death_screen_query: Query<Entity, With<DeathScreen>>,
+)
+{
+    if !keyboard_input.just_pressed(KeyCode::Enter) {
+        return;
+    }
+
+    for entity in death_screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
-pub fn calculate_player_radius(score: u32) -> f32
+#[cfg(test)]
+mod tests
 {
-    let stages = score / SCORE_PER_RADIUS_STAGE;
-    MIN_PLAYER_RADIUS + stages as f32 * RADIUS_GROWTH_PER_STAGE
+    use super::*;
+
+    fn snake_with_mass(growth: &SnakeGrowth, mass: f32) -> (Snake, SnakeBody)
+    {
+        let mut snake = Snake::new(0);
+        snake.mass = mass;
+        let body = SnakeBody {
+            points: (0..growth.segment_count(mass))
+                .map(|index| Vec2::new(index as f32, 0.0))
+                .collect(),
+            radius: growth.radius(mass),
+        };
+        (snake, body)
+    }
+
+    #[test]
+    fn shedding_mass_drops_the_segments_it_paid_for()
+    {
+        let growth = SnakeGrowth::default();
+        let (mut snake, body) = snake_with_mass(&growth, 20.0);
+
+        let removed = shed_mass(&mut snake, &body, &growth, 3.0);
+
+        assert_eq!(snake.score(), 17);
+        assert_eq!(removed.len(), 3);
+        assert_eq!(body.points.len() - removed.len(), growth.segment_count(snake.mass) as usize);
+        // The tail goes first
+        assert_eq!(removed.first(), body.points.get(growth.segment_count(snake.mass) as usize));
+    }
+
+    #[test]
+    fn shedding_never_goes_below_an_empty_snake()
+    {
+        let growth = SnakeGrowth::default();
+        let (mut snake, body) = snake_with_mass(&growth, 2.0);
+
+        shed_mass(&mut snake, &body, &growth, 10.0);
+
+        assert_eq!(snake.mass, 0.0);
+        assert_eq!(growth.segment_count(snake.mass), PLAYER_DEFAULT_LENGTH);
+    }
 }
@@ -0,0 +1,5 @@
+use bevy::prelude::*;
+
+/// The stats panel opened from the main menu.
+#[derive(Component)]
+pub struct StatsScreen;
@@ -0,0 +1,87 @@
+pub mod components;
+pub mod resources;
+pub mod systems;
+
+use std::io;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use resources::{CurrentGame, Profile};
+use slither_common::profile::{PlayerProfile, ProfileError};
+use systems::*;
+
+use crate::core::states::GameState;
+use crate::utils::despawn_screen;
+
+/// Keeps the local player's best results and totals in a profile file, updated whenever one of their games ends,
+/// and shows them on a stats screen in the main menu.
+pub struct ProfilePlugin
+{
+    pub path: PathBuf,
+}
+
+impl Plugin for ProfilePlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        let mut read_only = false;
+        let stats = match PlayerProfile::load(&self.path) {
+            Ok(stats) => stats,
+            Err(ProfileError::Io(err)) if err.kind() == io::ErrorKind::NotFound => PlayerProfile::default(),
+            Err(err @ ProfileError::Parse(_)) => {
+                // Keep the broken file around instead of overwriting it with the next game
+                let backup = self.path.with_extension("corrupt");
+                error!("Failed to load profile {}, starting a new one: {err}", self.path.display());
+                if let Err(err) = std::fs::rename(&self.path, &backup) {
+                    error!("Failed to move the broken profile to {}: {err}", backup.display());
+                }
+                PlayerProfile::default()
+            }
+            Err(err) => {
+                // The file may be fine for another version of the game, or readable again later, so leave it alone
+                error!(
+                    "Failed to load profile {}, this session won't be saved: {err}",
+                    self.path.display()
+                );
+                read_only = true;
+                PlayerProfile::default()
+            }
+        };
+
+        app.insert_resource(Profile {
+            stats,
+            path: self.path.clone(),
+            read_only,
+        })
+        .init_resource::<CurrentGame>()
+        .add_systems(
+            Update,
+            (start_game_record, track_player_growth, record_player_death, record_match_end).chain(),
+        )
+        .add_systems(Update, toggle_stats_screen.run_if(in_state(GameState::MainMenu)))
+        .add_systems(OnExit(GameState::MainMenu), despawn_screen::<components::StatsScreen>);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn profiles_from_newer_versions_are_left_alone()
+    {
+        let path = std::env::temp_dir().join(format!("slither-newer-profile-{}.ron", std::process::id()));
+        let text = "(version: 99, best_score: 7)";
+        std::fs::write(&path, text).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins(ProfilePlugin { path: path.clone() });
+
+        let kept = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(kept.unwrap(), text);
+        assert!(!path.with_extension("corrupt").exists());
+        assert!(app.world().resource::<Profile>().read_only);
+    }
+}
@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use slither_common::profile::PlayerProfile;
+
+/// The local player's saved stats and the file they are kept in.
+#[derive(Resource, Clone, Debug, Deref, DerefMut)]
+pub struct Profile
+{
+    #[deref]
+    pub stats: PlayerProfile,
+    pub path: PathBuf,
+    /// Set when the file belongs to a newer version of the game, which would lose data if this one overwrote it.
+    pub read_only: bool,
+}
+
+/// When the local player's current game started, `None` once it has been recorded.
+#[derive(Resource, Default)]
+pub struct CurrentGame
+{
+    pub started_at: Option<f32>,
+    /// The most segments the snake had during the game, as boosting may have shrunk it again by the end.
+    pub longest_length: u32,
+}
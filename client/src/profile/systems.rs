@@ -0,0 +1,320 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use slither_common::profile::GameRecord;
+
+use super::components::*;
+use super::resources::*;
+use crate::constants::*;
+use crate::core::components::Snake;
+use crate::core::events::{GameFinished, SnakeDied, SnakeGrew, SnakeSpawned};
+use crate::core::resources::SnakeGrowth;
+use crate::mode::resources::MatchState;
+use crate::player::components::Player;
+
+/// Starts timing a game when the local player's snake enters the arena.
+pub fn start_game_record(
+    time: Res<Time>,
+    mut spawn_events: EventReader<SnakeSpawned>,
+    mut current_game: ResMut<CurrentGame>,
+    player_query: Query<(), With<Player>>,
+)
+{
+    for spawn in spawn_events.read() {
+        if player_query.contains(spawn.snake) {
+            current_game.started_at = Some(time.elapsed_seconds());
+            current_game.longest_length = 0;
+        }
+    }
+}
+
+/// Keeps track of the local player's peak length.
+pub fn track_player_growth(
+    mut grew_events: EventReader<SnakeGrew>,
+    mut current_game: ResMut<CurrentGame>,
+    player_query: Query<(), With<Player>>,
+)
+{
+    for grew in grew_events.read() {
+        if player_query.contains(grew.snake) {
+            current_game.longest_length = current_game.longest_length.max(grew.length);
+        }
+    }
+}
+
+/// The game ends when the local player gets eaten.
+pub fn record_player_death(
+    time: Res<Time>,
+    match_state: Res<MatchState>,
+    mut death_events: EventReader<SnakeDied>,
+    mut finished_events: EventWriter<GameFinished>,
+    mut current_game: ResMut<CurrentGame>,
+    mut profile: ResMut<Profile>,
+    player_query: Query<(), With<Player>>,
+)
+{
+    for death in death_events.read() {
+        if !player_query.contains(death.victim) {
+            continue;
+        }
+        let Some(started_at) = current_game.started_at.take() else {
+            continue;
+        };
+
+        finish_game(
+            &mut profile,
+            &mut finished_events,
+            GameRecord {
+                mode: match_state.mode,
+                score: death.victim_score,
+                length: death.victim_length.max(current_game.longest_length),
+                kills: death.victim_kills,
+                duration: time.elapsed_seconds() - started_at,
+                ended_at: unix_time(),
+            },
+        );
+    }
+}
+
+/// Or when the match is over with the local player still alive.
+pub fn record_match_end(
+    time: Res<Time>,
+    match_state: Res<MatchState>,
+    growth: Res<SnakeGrowth>,
+    mut finished_events: EventWriter<GameFinished>,
+    mut current_game: ResMut<CurrentGame>,
+    mut profile: ResMut<Profile>,
+    player_query: Query<&Snake, With<Player>>,
+)
+{
+    if !match_state.is_over() {
+        return;
+    }
+    let Ok(snake) = player_query.get_single() else {
+        return;
+    };
+    let Some(started_at) = current_game.started_at.take() else {
+        return;
+    };
+
+    finish_game(
+        &mut profile,
+        &mut finished_events,
+        GameRecord {
+            mode: match_state.mode,
+            score: snake.score(),
+            length: growth.segment_count(snake.mass).max(current_game.longest_length),
+            kills: snake.kills,
+            duration: time.elapsed_seconds() - started_at,
+            ended_at: unix_time(),
+        },
+    );
+}
+
+fn finish_game(profile: &mut Profile, finished_events: &mut EventWriter<GameFinished>, game: GameRecord)
+{
+    finished_events.send(GameFinished(game.clone()));
+    profile.record(game);
+    save_profile(profile);
+}
+
+/// Writes the profile to its file, unless it belongs to a newer version of the game.
+pub fn save_profile(profile: &Profile)
+{
+    if profile.read_only {
+        return;
+    }
+    match profile.stats.save(&profile.path) {
+        Ok(()) => info!("Saved profile to {}", profile.path.display()),
+        Err(err) => error!("Failed to save profile: {err}"),
+    }
+}
+
+fn unix_time() -> u64
+{
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Tab opens and closes the stats screen over the main menu.
+pub fn toggle_stats_screen(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    profile: Res<Profile>,
+    stats_query: Query<Entity, With<StatsScreen>>,
+    asset_server: Res<AssetServer>,
+)
+{
+    if !keyboard_input.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    if let Ok(entity) = stats_query.get_single() {
+        commands.entity(entity).despawn_recursive();
+        return;
+    }
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 20.0,
+        color: TEXT_COLOR,
+    };
+    let totals = format!(
+        "Best score: {}\nLongest length: {}\nTotal kills: {}\nGames played: {}\nPlaytime: {}",
+        profile.best_score,
+        profile.longest_length,
+        profile.total_kills,
+        profile.games_played,
+        format_duration(profile.playtime),
+    );
+    let recent = if profile.recent_games.is_empty() {
+        "No games played yet".to_string()
+    } else {
+        profile.recent_games.iter().map(describe_game).collect::<Vec<_>>().join("\n")
+    };
+
+    commands
+        .spawn((
+            StatsScreen,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                // Drawn over the main menu
+                z_index: ZIndex::Global(STATS_SCREEN_Z_INDEX),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(24.0)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(BLACK_COLOR.with_alpha(0.9)),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Your Stats",
+                        TextStyle {
+                            font_size: 36.0,
+                            color: LEADERBOARD_COLOR,
+                            ..text_style.clone()
+                        },
+                    ));
+                    parent.spawn(TextBundle::from_section(totals, text_style.clone()));
+                    parent.spawn(TextBundle::from_section(
+                        "Recent games",
+                        TextStyle {
+                            color: LEADERBOARD_COLOR,
+                            ..text_style.clone()
+                        },
+                    ));
+                    parent.spawn(TextBundle::from_section(
+                        recent,
+                        TextStyle {
+                            font_size: 16.0,
+                            ..text_style.clone()
+                        },
+                    ));
+                    parent.spawn(TextBundle::from_section(
+                        "[Tab] back",
+                        TextStyle {
+                            font_size: 16.0,
+                            ..text_style.clone()
+                        },
+                    ));
+                });
+        });
+}
+
+fn describe_game(game: &GameRecord) -> String
+{
+    format!(
+        "{} - score {}, length {}, {} kills, {}",
+        game.mode.label(),
+        game.score,
+        game.length,
+        game.kills,
+        format_duration(game.duration)
+    )
+}
+
+/// Whole minutes and seconds, or hours and minutes once it gets long.
+fn format_duration(seconds: f32) -> String
+{
+    let seconds = seconds.max(0.0) as u64;
+    if seconds >= 3600 {
+        format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+    } else {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use slither_common::profile::PlayerProfile;
+
+    use super::*;
+
+    #[test]
+    fn a_player_death_is_saved_to_the_profile()
+    {
+        let path = std::env::temp_dir().join(format!("slither-profile-test-{}.ron", std::process::id()));
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(Profile {
+                stats: PlayerProfile::default(),
+                path: path.clone(),
+                read_only: false,
+            })
+            .init_resource::<CurrentGame>()
+            .init_resource::<MatchState>()
+            .add_event::<SnakeSpawned>()
+            .add_event::<SnakeDied>()
+            .add_event::<SnakeGrew>()
+            .add_event::<GameFinished>()
+            .add_systems(Update, (start_game_record, track_player_growth, record_player_death).chain());
+
+        let player = app.world_mut().spawn(Player::new(Color::WHITE)).id();
+        app.world_mut().send_event(SnakeSpawned { snake: player });
+        app.update();
+        // Boosting shrank the snake again before it died
+        app.world_mut().send_event(SnakeGrew {
+            snake: player,
+            length: 20,
+        });
+        app.update();
+        app.world_mut().send_event(SnakeDied {
+            victim: player,
+            victim_name: "Player 1".into(),
+            victim_score: 42,
+            victim_length: 12,
+            victim_kills: 2,
+            position: Vec2::ZERO,
+            killer: None,
+            killer_name: None,
+        });
+        app.update();
+
+        let saved = PlayerProfile::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved, app.world().resource::<Profile>().stats);
+        assert_eq!(saved.best_score, 42);
+        assert_eq!(saved.total_kills, 2);
+        assert_eq!(saved.longest_length, 20);
+        assert_eq!(saved.recent_games.len(), 1);
+        assert_eq!(format_duration(3725.0), "1h 02m");
+    }
+}
@@ -0,0 +1,8 @@
+use bevy::prelude::*;
+
+/// A pooled circle used to draw orbs during replay playback.
+#[derive(Component)]
+pub struct ReplayVisual;
+
+#[derive(Component)]
+pub struct ReplayHudText;
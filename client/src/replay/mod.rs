@@ -0,0 +1,51 @@
+pub mod components;
+pub mod resources;
+pub mod systems;
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use resources::*;
+use slither_common::replay::Replay;
+use systems::*;
+
+/// Records the running match so it can be watched again later.
+pub struct ReplayRecorderPlugin;
+
+impl Plugin for ReplayRecorderPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<ReplayRecorder>()
+            .add_systems(Update, (record_replay_events, record_replay_frame).chain())
+            .add_systems(Last, save_replay_on_exit);
+    }
+}
+
+/// Plays a replay file back instead of running a live match.
+pub struct ReplayPlaybackPlugin
+{
+    pub path: PathBuf,
+}
+
+impl Plugin for ReplayPlaybackPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        match Replay::load(&self.path) {
+            Ok(replay) => {
+                app.insert_resource(ReplayPlayback::new(replay));
+            }
+            Err(err) => error!("Failed to load replay {}: {err}", self.path.display()),
+        }
+
+        app.init_resource::<ReplayVisuals>()
+            .add_systems(Startup, spawn_replay_hud)
+            .add_systems(
+                Update,
+                (replay_controls, advance_replay_clock, draw_replay_frame, update_replay_hud)
+                    .chain()
+                    .run_if(resource_exists::<ReplayPlayback>),
+            );
+    }
+}
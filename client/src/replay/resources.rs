@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+
+use bevy::prelude::*;
+use slither_common::config::ArenaConfig;
+use slither_common::replay::{Replay, ReplayEvent, ReplayHeader, ReplayWriter};
+
+use crate::constants::*;
+
+/// Samples the running match into a replay file, appending to it as the match goes on.
+#[derive(Resource)]
+pub struct ReplayRecorder
+{
+    pub header: ReplayHeader,
+    /// Opened with the first frame, `None` before that or after the file could not be written.
+    pub writer: Option<ReplayWriter<BufWriter<File>>>,
+    /// Set once writing failed so the recorder stops trying.
+    pub failed: bool,
+    pub snapshot_timer: Timer,
+    pub elapsed: f32,
+    /// Events seen since the last frame, attached to the next one.
+    pub pending_events: Vec<ReplayEvent>,
+}
+
+impl Default for ReplayRecorder
+{
+    fn default() -> Self
+    {
+        Self {
+            header: ReplayHeader {
+                recorded_by: "client".into(),
+                map_radius: ArenaConfig::default().map_radius,
+                snapshot_interval: REPLAY_SNAPSHOT_INTERVAL,
+            },
+            writer: None,
+            failed: false,
+            snapshot_timer: Timer::from_seconds(REPLAY_SNAPSHOT_INTERVAL, TimerMode::Repeating),
+            elapsed: 0.0,
+            pending_events: Vec::new(),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayback
+{
+    pub replay: Replay,
+    /// Current playback position in match seconds.
+    pub time: f32,
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl ReplayPlayback
+{
+    pub fn new(replay: Replay) -> Self
+    {
+        Self {
+            replay,
+            time: 0.0,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn seek(&mut self, time: f32)
+    {
+        self.time = time.clamp(0.0, self.replay.duration());
+    }
+}
+
+/// Entities reused every frame to draw the replay.
+#[derive(Resource, Default)]
+pub struct ReplayVisuals
+{
+    /// Orb circles.
+    pub pool: Vec<Entity>,
+    /// Body mesh entity and mesh for each recorded snake id.
+    pub bodies: HashMap<u64, (Entity, Handle<Mesh>)>,
+}
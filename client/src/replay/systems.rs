@@ -0,0 +1,358 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy::render::view::NoFrustumCulling;
+use bevy::sprite::MaterialMesh2dBundle;
+use slither_common::replay::{OrbSnapshot, ReplayEvent, ReplayFrame, ReplayWriter, SnakeSnapshot, REPLAY_EXTENSION};
+use slither_common::skins::skin;
+
+use super::components::*;
+use super::resources::*;
+use crate::constants::*;
+use crate::core::components::{Snake, SnakeBody};
+use crate::core::events::SnakeDied;
+use crate::core::resources::RenderHandles;
+use crate::map::resources::ArenaFeatures;
+use crate::orb::components::Orb;
+use crate::snake_render::mesh::{empty_snake_body_mesh, fill_snake_body_mesh};
+use crate::snake_render::systems::skin_vertex_color;
+
+pub fn record_replay_frame(
+    time: Res<Time>,
+    mut recorder: ResMut<ReplayRecorder>,
+    features: Res<ArenaFeatures>,
+    snake_query: Query<(Entity, &Snake, &SnakeBody, &Transform, Option<&Name>)>,
+    orb_query: Query<(&Transform, &Orb, &Handle<ColorMaterial>)>,
+    materials: Res<Assets<ColorMaterial>>,
+)
+{
+    recorder.elapsed += time.delta_seconds();
+    if recorder.failed || !recorder.snapshot_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let snakes = snake_query
+        .iter()
+        .map(|(entity, snake, snake_body, transform, name)| {
+            let mut body = vec![transform.translation.truncate().to_array()];
+            body.extend(snake_body.points.iter().map(|point| point.to_array()));
+
+            SnakeSnapshot {
+                id: entity.to_bits(),
+                name: name.map_or_else(|| "Bot".to_string(), |name| name.to_string()),
+                color: color_to_rgb(snake.color),
+                skin: snake.skin,
+                radius: snake_body.radius,
+                score: snake.score(),
+                body,
+            }
+        })
+        .collect();
+
+    let orbs = orb_query
+        .iter()
+        .map(|(transform, orb, material)| OrbSnapshot {
+            position: transform.translation.truncate().to_array(),
+            radius: orb.radius,
+            color: materials
+                .get(material)
+                .map_or([1.0, 1.0, 1.0], |material| color_to_rgb(material.color)),
+        })
+        .collect();
+
+    let frame = ReplayFrame {
+        time: recorder.elapsed,
+        snakes,
+        orbs,
+        events: std::mem::take(&mut recorder.pending_events),
+    };
+
+    if recorder.writer.is_none() {
+        recorder.header.map_radius = features.shape.radius();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let path = PathBuf::from(REPLAY_DIRECTORY).join(format!("{timestamp}.{REPLAY_EXTENSION}"));
+
+        match ReplayWriter::create(&path, &recorder.header) {
+            Ok(writer) => {
+                info!("Recording replay to {}", path.display());
+                recorder.writer = Some(writer);
+            }
+            Err(err) => {
+                error!("Failed to start replay {}: {err}", path.display());
+                recorder.failed = true;
+                return;
+            }
+        }
+    }
+
+    let Some(writer) = recorder.writer.as_mut() else {
+        return;
+    };
+    let mut result = writer.write_frame(&frame);
+    if result.is_ok() && writer.frames_written().is_multiple_of(REPLAY_FLUSH_FRAMES) {
+        result = writer.flush();
+    }
+    if let Err(err) = result {
+        error!("Failed to write replay, recording stopped: {err}");
+        recorder.writer = None;
+        recorder.failed = true;
+    }
+}
+
+pub fn record_replay_events(mut death_events: EventReader<SnakeDied>, mut recorder: ResMut<ReplayRecorder>)
+{
+    for death in death_events.read() {
+        recorder.pending_events.push(ReplayEvent::SnakeDied {
+            victim: death.victim.to_bits(),
+            victim_name: death.victim_name.clone(),
+            killer_name: death.killer_name.clone(),
+        });
+    }
+}
+
+/// Writes out the frames still buffered once the app is closing.
+pub fn save_replay_on_exit(mut exit_events: EventReader<AppExit>, mut recorder: ResMut<ReplayRecorder>)
+{
+    if exit_events.read().next().is_none() {
+        return;
+    }
+    let Some(writer) = recorder.writer.as_mut() else {
+        return;
+    };
+
+    match writer.flush() {
+        Ok(()) => info!("Saved replay with {} frames", writer.frames_written()),
+        Err(err) => error!("Failed to save replay: {err}"),
+    }
+}
+
+pub fn spawn_replay_hud(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    commands.spawn((
+        ReplayHudText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 20.0,
+                color: TEXT_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+/// Space pauses, left/right seek, up/down change the playback speed and home restarts.
+pub fn replay_controls(keyboard_input: Res<ButtonInput<KeyCode>>, mut playback: ResMut<ReplayPlayback>)
+{
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        let time = playback.time + REPLAY_SEEK_STEP;
+        playback.seek(time);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        let time = playback.time - REPLAY_SEEK_STEP;
+        playback.seek(time);
+    }
+    if keyboard_input.just_pressed(KeyCode::Home) {
+        playback.seek(0.0);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        playback.speed = (playback.speed * 2.0).min(REPLAY_MAX_SPEED);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        playback.speed = (playback.speed * 0.5).max(REPLAY_MIN_SPEED);
+    }
+}
+
+pub fn advance_replay_clock(time: Res<Time>, mut playback: ResMut<ReplayPlayback>)
+{
+    if playback.paused {
+        return;
+    }
+
+    let time = playback.time + time.delta_seconds() * playback.speed;
+    playback.seek(time);
+}
+
+/// Draws the world at the current playback time, interpolating between the two closest frames.
+pub fn draw_replay_frame(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    mut visuals: ResMut<ReplayVisuals>,
+    mut render_handles: ResMut<RenderHandles>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut visual_query: Query<(&mut Transform, &mut Handle<ColorMaterial>, &mut Visibility), With<ReplayVisual>>,
+)
+{
+    let frames = &playback.replay.frames;
+    if frames.is_empty() {
+        return;
+    }
+
+    let index = playback.replay.frame_index_at(playback.time);
+    let frame = &frames[index];
+    let next = frames.get(index + 1).unwrap_or(frame);
+    let t = if next.time > frame.time {
+        ((playback.time - frame.time) / (next.time - frame.time)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let visuals = visuals.as_mut();
+    draw_replay_orbs(
+        &mut commands,
+        &mut visuals.pool,
+        &mut render_handles,
+        &mut materials,
+        &mut visual_query,
+        frame,
+    );
+
+    // Snakes that are gone by this frame lose their body mesh
+    visuals.bodies.retain(|id, (entity, _)| {
+        let alive = frame.snakes.iter().any(|snake| snake.id == *id);
+        if !alive {
+            commands.entity(*entity).despawn_recursive();
+        }
+        alive
+    });
+
+    for snake in &frame.snakes {
+        let (_, mesh_handle) = visuals.bodies.entry(snake.id).or_insert_with(|| {
+            let mesh = meshes.add(empty_snake_body_mesh());
+            let entity = commands
+                .spawn((
+                    MaterialMesh2dBundle {
+                        mesh: mesh.clone().into(),
+                        material: render_handles.material(&mut materials, Color::WHITE),
+                        transform: Transform::from_xyz(0.0, 0.0, Z_BOT_SEGMENTS),
+                        ..default()
+                    },
+                    NoFrustumCulling,
+                ))
+                .id();
+            (entity, mesh)
+        });
+        let Some(mesh) = meshes.get_mut(mesh_handle) else {
+            continue;
+        };
+
+        let next_snake = next.snakes.iter().find(|other| other.id == snake.id);
+        let points: Vec<Vec2> = snake
+            .body
+            .iter()
+            .enumerate()
+            .map(|(i, point)| {
+                let from = Vec2::from_array(*point);
+                next_snake
+                    .and_then(|next_snake| next_snake.body.get(i))
+                    .map_or(from, |to| from.lerp(Vec2::from_array(*to), t))
+            })
+            .collect();
+
+        let skin = skin(snake.skin);
+        fill_snake_body_mesh(mesh, &points, snake.radius, |index| skin_vertex_color(skin, index));
+    }
+}
+
+/// Places one pooled circle on every orb in the frame.
+fn draw_replay_orbs(
+    commands: &mut Commands,
+    pool: &mut Vec<Entity>,
+    render_handles: &mut RenderHandles,
+    materials: &mut Assets<ColorMaterial>,
+    visual_query: &mut Query<(&mut Transform, &mut Handle<ColorMaterial>, &mut Visibility), With<ReplayVisual>>,
+    frame: &ReplayFrame,
+)
+{
+    // Grow the pool when this frame needs more circles than we have. New entities show up next frame.
+    while pool.len() < frame.orbs.len() {
+        let entity = commands
+            .spawn((
+                ReplayVisual,
+                MaterialMesh2dBundle::<ColorMaterial> {
+                    mesh: render_handles.circle.clone().into(),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+            ))
+            .id();
+        pool.push(entity);
+    }
+
+    for (i, &entity) in pool.iter().enumerate() {
+        let Ok((mut transform, mut material, mut visibility)) = visual_query.get_mut(entity) else {
+            continue;
+        };
+
+        let Some(orb) = frame.orbs.get(i) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        transform.translation = Vec2::from_array(orb.position).extend(Z_ORBS);
+        transform.scale = Vec3::new(orb.radius, orb.radius, 1.0);
+        *visibility = Visibility::Visible;
+
+        let handle = render_handles.material(materials, Color::srgb(orb.color[0], orb.color[1], orb.color[2]));
+        if *material != handle {
+            *material = handle;
+        }
+    }
+}
+
+pub fn update_replay_hud(playback: Res<ReplayPlayback>, mut text_query: Query<&mut Text, With<ReplayHudText>>)
+{
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    // Show the deaths recorded over the last few seconds of playback
+    let first_frame = playback.replay.frame_index_at(playback.time - KILL_FEED_ENTRY_DURATION);
+    let last_frame = playback.replay.frame_index_at(playback.time);
+    let recent_deaths: Vec<String> = playback
+        .replay
+        .frames
+        .get(first_frame..=last_frame)
+        .unwrap_or_default()
+        .iter()
+        .filter(|frame| frame.time <= playback.time)
+        .flat_map(|frame| frame.events.iter())
+        .map(|event| match event {
+            ReplayEvent::SnakeDied {
+                victim_name,
+                killer_name: Some(killer_name),
+                ..
+            } => format!("{victim_name} was eaten by {killer_name}\n"),
+            ReplayEvent::SnakeDied { victim_name, .. } => format!("{victim_name} died\n"),
+        })
+        .collect();
+
+    text.sections[0].value = format!(
+        "{}Replay by {} {:.1}s / {:.1}s  x{}{}\n[Space] pause  [Left/Right] seek  [Up/Down] speed  [WASD/Wheel] camera",
+        recent_deaths.concat(),
+        playback.replay.header.recorded_by,
+        playback.time,
+        playback.replay.duration(),
+        playback.speed,
+        if playback.paused { "  PAUSED" } else { "" },
+    );
+}
+
+fn color_to_rgb(color: Color) -> [f32; 3]
+{
+    let color = color.to_srgba();
+    [color.red, color.green, color.blue]
+}
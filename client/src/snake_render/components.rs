@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+use slither_common::skins::SkinId;
+
+/// The mesh entity that draws the body of `owner`.
+#[derive(Component)]
+pub struct SnakeBodyRenderer
+{
+    pub owner: Entity,
+    /// The skin the owner's head is currently coloured with.
+    pub skin: SkinId,
+    /// Fades between 0 and 1 as the owner starts and stops boosting.
+    pub boost_glow: f32,
+}
+
+/// One of the two eyes on a snake head. `side` is 1 for the left eye and -1 for the right one.
+#[derive(Component)]
+pub struct SnakeEye
+{
+    pub side: f32,
+}
+
+#[derive(Component)]
+pub struct SnakePupil;
@@ -0,0 +1,119 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::render::render_asset::RenderAssetUsages;
+
+/// Number of triangles used for the rounded cap at each end of a body.
+const CAP_SEGMENTS: u32 = 8;
+
+/// A mesh with vertex colours that `fill_snake_body_mesh` can rebuild every frame.
+pub fn empty_snake_body_mesh() -> Mesh
+{
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::new())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, Vec::<[f32; 4]>::new())
+        .with_inserted_indices(Indices::U32(Vec::new()))
+}
+
+/// Rebuilds `mesh` as a tube of width `radius * 2` through `points`, with rounded ends.
+///
+/// `color` returns the linear vertex colour for the point at an index, so patterns can vary along the body.
+/// The existing vertex buffers are reused to avoid allocating every frame.
+pub fn fill_snake_body_mesh(mesh: &mut Mesh, points: &[Vec2], radius: f32, color: impl Fn(usize) -> [f32; 4])
+{
+    let mut positions = match mesh.remove_attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => positions,
+        _ => Vec::new(),
+    };
+    let mut colors = match mesh.remove_attribute(Mesh::ATTRIBUTE_COLOR) {
+        Some(VertexAttributeValues::Float32x4(colors)) => colors,
+        _ => Vec::new(),
+    };
+    let mut indices = match mesh.remove_indices() {
+        Some(Indices::U32(indices)) => indices,
+        _ => Vec::new(),
+    };
+    positions.clear();
+    colors.clear();
+    indices.clear();
+
+    let mut tangent = Vec2::X;
+    let mut first_tangent = tangent;
+
+    // Two vertices per point, one on each side of the body
+    for (i, &point) in points.iter().enumerate() {
+        let before = points[i.saturating_sub(1)];
+        let after = points[(i + 1).min(points.len() - 1)];
+        // Keep the previous direction where points overlap, e.g. while the snake is standing still
+        tangent = (after - before).try_normalize().unwrap_or(tangent);
+        if i == 0 {
+            first_tangent = tangent;
+        }
+
+        let side = tangent.perp() * radius;
+        positions.push((point + side).extend(0.0).to_array());
+        positions.push((point - side).extend(0.0).to_array());
+        colors.push(color(i));
+        colors.push(color(i));
+
+        if i > 0 {
+            let base = (i as u32 - 1) * 2;
+            indices.extend_from_slice(&[base, base + 1, base + 2, base + 1, base + 3, base + 2]);
+        }
+    }
+
+    if let (Some(&first), Some(&last)) = (points.first(), points.last()) {
+        add_cap(
+            &mut positions,
+            &mut colors,
+            &mut indices,
+            first,
+            -first_tangent,
+            radius,
+            color(0),
+        );
+        add_cap(
+            &mut positions,
+            &mut colors,
+            &mut indices,
+            last,
+            tangent,
+            radius,
+            color(points.len() - 1),
+        );
+    }
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.insert_indices(Indices::U32(indices));
+}
+
+/// Adds a half circle fan around `center` that bulges towards `direction`.
+fn add_cap(
+    positions: &mut Vec<[f32; 3]>,
+    colors: &mut Vec<[f32; 4]>,
+    indices: &mut Vec<u32>,
+    center: Vec2,
+    direction: Vec2,
+    radius: f32,
+    color: [f32; 4],
+)
+{
+    let center_index = positions.len() as u32;
+    positions.push(center.extend(0.0).to_array());
+    colors.push(color);
+
+    let side = -direction.perp();
+    for step in 0..=CAP_SEGMENTS {
+        let angle = step as f32 / CAP_SEGMENTS as f32 * PI;
+        let offset = (side * angle.cos() + direction * angle.sin()) * radius;
+        positions.push((center + offset).extend(0.0).to_array());
+        colors.push(color);
+
+        if step > 0 {
+            let index = center_index + step + 1;
+            indices.extend_from_slice(&[center_index, index - 1, index]);
+        }
+    }
+}
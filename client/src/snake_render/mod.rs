@@ -0,0 +1,31 @@
+pub mod components;
+pub mod mesh;
+pub mod systems;
+
+use bevy::prelude::*;
+use systems::*;
+
+use crate::core::systems::update_snake_bodies;
+
+/// Draws every snake body as one mesh instead of one circle entity per segment, patterned by its skin.
+pub struct SnakeRenderPlugin;
+
+impl Plugin for SnakeRenderPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_systems(
+            PostUpdate,
+            (
+                spawn_snake_body_renderers,
+                update_head_materials,
+                update_boost_glow,
+                update_snake_body_meshes,
+                update_snake_eyes,
+            )
+                .chain()
+                .after(update_snake_bodies)
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+use bevy::render::view::NoFrustumCulling;
+use bevy::sprite::MaterialMesh2dBundle;
+use slither_common::skins::{skin, Skin, SkinId};
+
+use super::components::*;
+use super::mesh::*;
+use crate::constants::*;
+use crate::core::components::{has_power_up, PowerUp, PowerUps, SegmentPositionHistory, Snake, SnakeBody, SpawnProtection};
+use crate::core::resources::RenderHandles;
+
+/// Gives every new snake its own body mesh, drawn just below its head, and a pair of eyes.
+pub fn spawn_snake_body_renderers(
+    mut commands: Commands,
+    mut snake_query: Query<(Entity, &Transform, &Snake, &mut Handle<ColorMaterial>), Added<SnakeBody>>,
+    mut render_handles: ResMut<RenderHandles>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+)
+{
+    for (snake_entity, transform, snake, mut head_material) in snake_query.iter_mut() {
+        commands.spawn((
+            SnakeBodyRenderer {
+                owner: snake_entity,
+                skin: snake.skin,
+                boost_glow: 0.0,
+            },
+            MaterialMesh2dBundle {
+                mesh: meshes.add(empty_snake_body_mesh()).into(),
+                // The colour comes from the vertices, so every body shares the white material
+                material: render_handles.material(&mut materials, Color::WHITE),
+                transform: Transform::from_xyz(0.0, 0.0, transform.translation.z - 0.5),
+                ..default()
+            },
+            // The mesh is rebuilt in world space every frame, so its bounds are never up to date
+            NoFrustumCulling,
+        ));
+
+        *head_material = head_material_for(&mut render_handles, &mut materials, snake.skin);
+
+        let eye_material = render_handles.material(&mut materials, EYE_COLOR);
+        let pupil_material = render_handles.material(&mut materials, PUPIL_COLOR);
+        commands.entity(snake_entity).with_children(|head| {
+            for side in [1.0, -1.0] {
+                head.spawn((
+                    SnakeEye { side },
+                    MaterialMesh2dBundle {
+                        mesh: render_handles.circle.clone().into(),
+                        material: eye_material.clone(),
+                        transform: Transform::from_xyz(EYE_FORWARD_OFFSET, side * EYE_SIDE_OFFSET, 0.1)
+                            .with_scale(Vec3::splat(EYE_SIZE)),
+                        ..default()
+                    },
+                ))
+                .with_children(|eye| {
+                    eye.spawn((
+                        SnakePupil,
+                        MaterialMesh2dBundle {
+                            mesh: render_handles.circle.clone().into(),
+                            material: pupil_material.clone(),
+                            transform: Transform::from_xyz(0.0, 0.0, 0.1).with_scale(Vec3::splat(PUPIL_SIZE)),
+                            ..default()
+                        },
+                    ));
+                });
+            }
+        });
+    }
+}
+
+/// Recolours the head of a snake whose skin changed after it spawned, e.g. when it joins a team.
+pub fn update_head_materials(
+    mut renderer_query: Query<&mut SnakeBodyRenderer>,
+    mut snake_query: Query<(&Snake, &mut Handle<ColorMaterial>)>,
+    mut render_handles: ResMut<RenderHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+)
+{
+    for mut renderer in renderer_query.iter_mut() {
+        let Ok((snake, mut head_material)) = snake_query.get_mut(renderer.owner) else {
+            continue;
+        };
+        if snake.skin != renderer.skin {
+            renderer.skin = snake.skin;
+            *head_material = head_material_for(&mut render_handles, &mut materials, snake.skin);
+        }
+    }
+}
+
+/// Glowing skins need a head that glows just as much as the body.
+fn head_material_for(
+    render_handles: &mut RenderHandles,
+    materials: &mut Assets<ColorMaterial>,
+    skin_id: SkinId,
+) -> Handle<ColorMaterial>
+{
+    render_handles.material(
+        materials,
+        Color::LinearRgba(LinearRgba::from_f32_array(skin_vertex_color(skin(skin_id), 0))),
+    )
+}
+
+/// Rebuilds each body mesh from the snake's current body and removes the meshes of dead snakes.
+pub fn update_snake_body_meshes(
+    mut commands: Commands,
+    time: Res<Time>,
+    renderer_query: Query<(Entity, &SnakeBodyRenderer, &Handle<Mesh>)>,
+    snake_query: Query<(&Snake, &SnakeBody, &Transform, Option<&PowerUps>, Has<SpawnProtection>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut points: Local<Vec<Vec2>>,
+)
+{
+    let pulse = 0.75 + 0.25 * (time.elapsed_seconds() * BOOST_GLOW_PULSE_SPEED).sin();
+
+    for (renderer_entity, renderer, mesh_handle) in renderer_query.iter() {
+        let Ok((snake, body, transform, power_ups, protected)) = snake_query.get(renderer.owner) else {
+            commands.entity(renderer_entity).despawn_recursive();
+            continue;
+        };
+        let Some(mesh) = meshes.get_mut(mesh_handle) else {
+            continue;
+        };
+
+        points.clear();
+        points.push(transform.translation.truncate());
+        points.extend_from_slice(&body.points);
+
+        let skin = skin(snake.skin);
+        let brightness = 1.0 + renderer.boost_glow * BOOST_GLOW_STRENGTH * pulse;
+        let blink_faded = (time.elapsed_seconds() * SPAWN_PROTECTION_BLINK_SPEED).sin() < 0.0;
+        let alpha = if has_power_up(power_ups, PowerUp::Ghost) {
+            GHOST_ALPHA
+        } else if protected && blink_faded {
+            SPAWN_PROTECTION_ALPHA
+        } else {
+            1.0
+        };
+        fill_snake_body_mesh(mesh, &points, body.radius, |index| {
+            let [red, green, blue, _] = skin_vertex_color(skin, index);
+            [red * brightness, green * brightness, blue * brightness, alpha]
+        });
+    }
+}
+
+/// Fades the body glow in while a snake boosts and back out once it stops.
+pub fn update_boost_glow(time: Res<Time>, mut renderer_query: Query<&mut SnakeBodyRenderer>, snake_query: Query<&Snake>)
+{
+    let step = BOOST_GLOW_FADE_SPEED * time.delta_seconds();
+
+    for mut renderer in renderer_query.iter_mut() {
+        let target = match snake_query.get(renderer.owner) {
+            Ok(snake) if snake.is_boosting => 1.0,
+            _ => 0.0,
+        };
+        renderer.boost_glow += (target - renderer.boost_glow).clamp(-step, step);
+    }
+}
+
+/// Places the eyes around the front of the head and points the pupils where the snake is steering.
+pub fn update_snake_eyes(
+    snake_query: Query<(&Transform, &Snake, &SegmentPositionHistory, &Children)>,
+    mut eye_query: Query<(&SnakeEye, &mut Transform, &Children), Without<Snake>>,
+    mut pupil_query: Query<&mut Transform, (With<SnakePupil>, Without<SnakeEye>, Without<Snake>)>,
+)
+{
+    for (transform, snake, history, children) in snake_query.iter() {
+        let heading = history
+            .positions
+            .get(POSITIONS_PER_SEGMENT as usize)
+            .and_then(|previous| (transform.translation - *previous).truncate().try_normalize())
+            .or(snake.steering.try_normalize())
+            .unwrap_or(Vec2::X);
+        let steering = snake.steering.try_normalize().unwrap_or(heading);
+
+        // The eyes are children of the head, so work in its local space in case the head is rotated
+        let to_local = transform.rotation.inverse();
+        let forward = (to_local * heading.extend(0.0)).truncate();
+        let look = (to_local * steering.extend(0.0)).truncate();
+
+        for &child in children {
+            let Ok((eye, mut eye_transform, eye_children)) = eye_query.get_mut(child) else {
+                continue;
+            };
+
+            let position = forward * EYE_FORWARD_OFFSET + forward.perp() * eye.side * EYE_SIDE_OFFSET;
+            eye_transform.translation = position.extend(eye_transform.translation.z);
+
+            for &pupil in eye_children {
+                if let Ok(mut pupil_transform) = pupil_query.get_mut(pupil) {
+                    pupil_transform.translation = (look * PUPIL_OFFSET).extend(pupil_transform.translation.z);
+                }
+            }
+        }
+    }
+}
+
+/// The linear colour of a segment, brightened past 1.0 for glowing skins so the bloom picks it up.
+pub fn skin_vertex_color(skin: &Skin, index: usize) -> [f32; 4]
+{
+    let [red, green, blue] = skin.color_at(index);
+    let color = Color::srgb(red, green, blue).to_linear() * (1.0 + skin.glow);
+    [color.red, color.green, color.blue, 1.0]
+}
@@ -0,0 +1,25 @@
+pub mod components;
+pub mod resources;
+pub mod systems;
+
+use bevy::prelude::*;
+use systems::*;
+
+/// Lets players watch the match without a snake, either after dying or by joining with `--spectate`.
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<resources::Spectator>()
+            .add_systems(Startup, spawn_spectator_text)
+            .add_systems(
+                Update,
+                (
+                    (spectator_controls, update_spectator_camera).chain().run_if(is_spectating),
+                    update_spectator_text,
+                ),
+            );
+    }
+}
@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpectatorMode
+{
+    /// Follow whoever is currently first on the leaderboard.
+    #[default]
+    FollowLeader,
+    FollowSnake(Entity),
+    FreeCamera,
+}
+
+#[derive(Resource, Default)]
+pub struct Spectator
+{
+    pub mode: SpectatorMode,
+}
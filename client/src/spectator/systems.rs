@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+
+use super::components::*;
+use super::resources::*;
+use crate::constants::*;
+use crate::core::components::Snake;
+use crate::core::resources::CameraTarget;
+use crate::core::states::GameState;
+use crate::player::components::Player;
+
+/// We are spectating whenever there is no living local player.
+pub fn is_spectating(player_query: Query<(), (With<Player>, With<Snake>)>) -> bool
+{
+    player_query.is_empty()
+}
+
+/// Spectators have no menu to leave, so they join the running match straight away.
+pub fn skip_main_menu(mut next_state: ResMut<NextState<GameState>>)
+{
+    next_state.set(GameState::Playing);
+}
+
+pub fn spawn_spectator_text(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    commands.spawn((
+        SpectatorText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 20.0,
+                color: TEXT_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+/// Q/E cycle through the living snakes, L goes back to following the leader and F toggles the free camera.
+pub fn spectator_controls(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut spectator: ResMut<Spectator>,
+    camera_target: Res<CameraTarget>,
+    snake_query: Query<Entity, With<Snake>>,
+)
+{
+    if keyboard_input.just_pressed(KeyCode::KeyF) {
+        spectator.mode = match spectator.mode {
+            SpectatorMode::FreeCamera => SpectatorMode::FollowLeader,
+            _ => SpectatorMode::FreeCamera,
+        };
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyL) {
+        spectator.mode = SpectatorMode::FollowLeader;
+    }
+
+    let step: isize = if keyboard_input.just_pressed(KeyCode::KeyE) {
+        1
+    } else if keyboard_input.just_pressed(KeyCode::KeyQ) {
+        -1
+    } else {
+        return;
+    };
+
+    // Sort so the cycle order stays the same from frame to frame
+    let mut snakes: Vec<Entity> = snake_query.iter().collect();
+    if snakes.is_empty() {
+        return;
+    }
+    snakes.sort();
+
+    let next = match camera_target
+        .0
+        .and_then(|current| snakes.iter().position(|&snake| snake == current))
+    {
+        Some(current) => (current as isize + step).rem_euclid(snakes.len() as isize) as usize,
+        None => 0,
+    };
+    spectator.mode = SpectatorMode::FollowSnake(snakes[next]);
+}
+
+pub fn update_spectator_camera(
+    mut spectator: ResMut<Spectator>,
+    mut camera_target: ResMut<CameraTarget>,
+    snake_query: Query<(Entity, &Snake)>,
+)
+{
+    // Fall back to the leader once the snake we were watching dies
+    if let SpectatorMode::FollowSnake(entity) = spectator.mode {
+        if !snake_query.contains(entity) {
+            spectator.mode = SpectatorMode::FollowLeader;
+        }
+    }
+
+    camera_target.0 = match spectator.mode {
+        SpectatorMode::FollowLeader => snake_query
+            .iter()
+            .max_by_key(|(_, snake)| snake.score())
+            .map(|(entity, _)| entity),
+        SpectatorMode::FollowSnake(entity) => Some(entity),
+        SpectatorMode::FreeCamera => None,
+    };
+}
+
+pub fn update_spectator_text(
+    spectator: Res<Spectator>,
+    camera_target: Res<CameraTarget>,
+    player_query: Query<(), (With<Player>, With<Snake>)>,
+    name_query: Query<&Name>,
+    mut text_query: Query<&mut Text, With<SpectatorText>>,
+)
+{
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    if !player_query.is_empty() {
+        text.sections[0].value.clear();
+        return;
+    }
+
+    let watching = match spectator.mode {
+        SpectatorMode::FreeCamera => "free camera".to_string(),
+        _ => camera_target
+            .0
+            .and_then(|target| name_query.get(target).ok())
+            .map_or_else(|| "Bot".to_string(), |name| name.to_string()),
+    };
+
+    text.sections[0].value =
+        format!("Spectating: {watching}\n[Q/E] cycle snakes  [L] follow leader  [F] free camera  [WASD/Wheel] move camera");
+}
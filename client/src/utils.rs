@@ -2,6 +2,9 @@ use bevy::color::Color;
 use bevy::math::Vec2;
 use bevy::prelude::{Commands, Component, DespawnRecursiveExt, Entity, Query, With};
 use rand::Rng;
+use slither_common::skins::{SkinId, SKINS};
+
+use crate::constants::SNAKE_PALETTE;
 
 /// Despawn's all entities with the given component
 pub fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands)
@@ -22,17 +25,55 @@ pub fn generate_random_position_within_radius(radius: f32) -> Vec2
 
 pub fn generate_random_color() -> Color
 {
-    let colors = vec![
-        Color::srgb(1.0, 0.0, 0.0),  // Red
-        Color::srgb(0.0, 1.0, 0.0),  // Green
-        Color::srgb(0.0, 0.0, 1.0),  // Blue
-        Color::srgb(1.0, 1.0, 0.0),  // Yellow
-        Color::srgb(1.0, 0.65, 0.0), // Orange
-        Color::srgb(0.5, 0.0, 0.5),  // Purple
-        Color::srgb(0.0, 1.0, 1.0),  // Cyan
-        Color::srgb(1.0, 0.75, 0.8), // Pink
+    let mut rng = rand::thread_rng();
+    SNAKE_PALETTE[rng.gen_range(0..SNAKE_PALETTE.len())]
+}
+
+pub fn generate_random_skin() -> SkinId
+{
+    let mut rng = rand::thread_rng();
+    rng.gen_range(0..SKINS.len()) as SkinId
+}
+
+/// Converts the sRGB triples used by skins and replays into a colour.
+pub fn rgb_to_color(rgb: [f32; 3]) -> Color
+{
+    Color::srgb(rgb[0], rgb[1], rgb[2])
+}
+
+pub fn generate_bot_name() -> String
+{
+    let names = [
+        "Viper",
+        "Cobra",
+        "Mamba",
+        "Python",
+        "Adder",
+        "Boa",
+        "Krait",
+        "Taipan",
+        "Asp",
+        "Racer",
+        "Sidewinder",
+        "Copperhead",
+        "Rattler",
+        "Anaconda",
+        "Noodle",
+        "Slinky",
     ];
 
     let mut rng = rand::thread_rng();
-    colors[rng.gen_range(0..colors.len())]
+    format!("Bot {}", names[rng.gen_range(0..names.len())])
+}
+
+/// Starts fast and slows down towards the end. `t` goes from 0 to 1.
+pub fn ease_out_cubic(t: f32) -> f32
+{
+    1.0 - (1.0 - t.clamp(0.0, 1.0)).powi(3)
+}
+
+/// Starts slow and speeds up towards the end. `t` goes from 0 to 1.
+pub fn ease_in_cubic(t: f32) -> f32
+{
+    t.clamp(0.0, 1.0).powi(3)
 }
@@ -0,0 +1,672 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+
+/// File extension of game config files.
+pub const CONFIG_EXTENSION: &str = "ron";
+
+/// Balance settings shared by the client and the server, loaded from a RON file.
+///
+/// Every field has a default, so a config file only needs to list the values it changes.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig
+{
+    pub rules: RulesConfig,
+    pub arena: ArenaConfig,
+    pub snake: SnakeConfig,
+    pub camera: CameraConfig,
+    pub orbs: OrbConfig,
+    pub bots: BotConfig,
+}
+
+/// Most teams a match can be split into.
+pub const MAX_TEAMS: u8 = 4;
+
+/// How a match is played, picked in the main menu or by the server's config.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode
+{
+    /// Endless free for all.
+    #[default]
+    Classic,
+    /// Free for all against the clock, ending with a podium of the biggest snakes.
+    TimedMatch,
+    /// Snakes play in coloured teams and cannot eat their teammates.
+    Teams,
+    /// Nobody respawns and the last snake alive wins.
+    BattleRoyale,
+}
+
+impl GameMode
+{
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Classic,
+        GameMode::TimedMatch,
+        GameMode::Teams,
+        GameMode::BattleRoyale,
+    ];
+
+    pub fn label(self) -> &'static str
+    {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::TimedMatch => "Timed match",
+            GameMode::Teams => "Teams",
+            GameMode::BattleRoyale => "Battle royale",
+        }
+    }
+
+    pub fn has_time_limit(self) -> bool
+    {
+        self == GameMode::TimedMatch
+    }
+
+    pub fn uses_teams(self) -> bool
+    {
+        self == GameMode::Teams
+    }
+
+    /// The match ends once a single snake is left.
+    pub fn last_snake_standing(self) -> bool
+    {
+        self == GameMode::BattleRoyale
+    }
+
+    /// The arena closes in over the course of the match.
+    pub fn shrinks_arena(self) -> bool
+    {
+        self == GameMode::BattleRoyale
+    }
+
+    /// Whether bots that die are replaced.
+    pub fn respawns_bots(self) -> bool
+    {
+        self != GameMode::BattleRoyale
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RulesConfig
+{
+    pub mode: GameMode,
+    /// Seconds a timed match lasts.
+    pub match_duration: f32,
+    /// Teams snakes are split into in team mode.
+    pub team_count: u8,
+    /// Whether teammates can eat each other.
+    pub friendly_fire: bool,
+}
+
+impl Default for RulesConfig
+{
+    fn default() -> Self
+    {
+        Self {
+            mode: GameMode::Classic,
+            match_duration: 300.0,
+            team_count: 2,
+            friendly_fire: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArenaConfig
+{
+    /// Radius of the circular arena used when no map is named.
+    pub map_radius: f32,
+    /// Smallest radius a shrinking arena closes in to.
+    pub min_radius: f32,
+    /// Played in order when the arena shrinks, each taking an equal step from the full size to `min_radius`.
+    pub shrink_phases: Vec<ShrinkPhase>,
+    /// Map file with obstacles and zones, relative to the maps folder. `None` leaves the arena empty.
+    pub map: Option<String>,
+}
+
+impl Default for ArenaConfig
+{
+    fn default() -> Self
+    {
+        Self {
+            map_radius: 1000.0,
+            min_radius: 250.0,
+            shrink_phases: vec![
+                ShrinkPhase {
+                    hold: 60.0,
+                    shrink: 30.0,
+                },
+                ShrinkPhase {
+                    hold: 45.0,
+                    shrink: 30.0,
+                },
+                ShrinkPhase {
+                    hold: 30.0,
+                    shrink: 30.0,
+                },
+            ],
+            map: None,
+        }
+    }
+}
+
+impl ArenaConfig
+{
+    /// Radius of a shrinking arena that started at `full_radius`, `elapsed` seconds after it started closing in.
+    pub fn radius_at(&self, full_radius: f32, elapsed: f32) -> f32
+    {
+        let step = (full_radius - self.min_radius).max(0.0) / self.shrink_phases.len().max(1) as f32;
+        let mut radius = full_radius;
+        let mut phase_start = 0.0;
+
+        for phase in &self.shrink_phases {
+            let shrink_start = phase_start + phase.hold;
+            if elapsed < shrink_start {
+                return radius;
+            }
+
+            let progress = (elapsed - shrink_start) / phase.shrink;
+            if progress < 1.0 {
+                return radius - step * progress;
+            }
+
+            radius -= step;
+            phase_start = shrink_start + phase.shrink;
+        }
+
+        radius
+    }
+}
+
+/// One step of a shrinking arena: it holds its size for a while, then closes in to the next size.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ShrinkPhase
+{
+    /// Seconds the arena keeps its size.
+    pub hold: f32,
+    /// Seconds it then takes to close in.
+    pub shrink: f32,
+}
+
+/// How a stat changes with mass: `base + per_mass * mass^exponent`, kept within `min..=max`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GrowthCurve
+{
+    pub base: f32,
+    pub per_mass: f32,
+    pub exponent: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl GrowthCurve
+{
+    pub fn at(&self, mass: f32) -> f32
+    {
+        (self.base + self.per_mass * mass.max(0.0).powf(self.exponent)).clamp(self.min, self.max)
+    }
+
+    fn validate(&self, name: &str, problems: &mut Vec<String>)
+    {
+        let values = [self.base, self.per_mass, self.exponent, self.min, self.max];
+        if values.iter().any(|value| !value.is_finite()) {
+            problems.push(format!("{name}: every value must be a finite number"));
+        } else if self.min > self.max {
+            problems.push(format!("{name}: min ({}) is above max ({})", self.min, self.max));
+        } else if self.exponent <= 0.0 {
+            problems.push(format!("{name}: exponent must be above zero"));
+        }
+    }
+}
+
+/// Everything about a snake's size is derived from its mass through these curves.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnakeConfig
+{
+    /// Mass a snake needs before it can boost.
+    pub mass_needed_for_boosting: f32,
+    /// Mass burned per second of boosting.
+    pub boost_mass_cost: f32,
+    pub boost_speed_multiplier: f32,
+    /// Body segments behind the head.
+    pub segments: GrowthCurve,
+    pub radius: GrowthCurve,
+    /// Pixels per second before boosting and power ups.
+    pub speed: GrowthCurve,
+}
+
+impl Default for SnakeConfig
+{
+    fn default() -> Self
+    {
+        Self {
+            mass_needed_for_boosting: 5.0,
+            boost_mass_cost: 1.0,
+            boost_speed_multiplier: 2.0,
+            segments: GrowthCurve {
+                base: 1.0,
+                per_mass: 1.0,
+                exponent: 1.0,
+                min: 1.0,
+                max: 19_999.0,
+            },
+            radius: GrowthCurve {
+                base: 12.5,
+                per_mass: 0.008,
+                exponent: 1.0,
+                min: 12.5,
+                max: 60.0,
+            },
+            speed: GrowthCurve {
+                base: 100.0,
+                per_mass: -0.01,
+                exponent: 1.0,
+                min: 70.0,
+                max: 100.0,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraConfig
+{
+    /// Orthographic scale of the camera following a snake, bigger zooms further out.
+    pub zoom: GrowthCurve,
+}
+
+impl Default for CameraConfig
+{
+    fn default() -> Self
+    {
+        Self {
+            zoom: GrowthCurve {
+                base: 1.0,
+                per_mass: 0.00032,
+                exponent: 1.0,
+                min: 0.5,
+                max: 3.0,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct OrbConfig
+{
+    /// Most natural orbs the arena holds at once.
+    pub max_count: usize,
+    /// Natural orbs kept in the arena for each living snake.
+    pub per_snake: usize,
+    /// Natural orbs spawned per second while below the target.
+    pub respawn_rate: f32,
+    /// Chance for each natural orb to be a power orb instead.
+    pub power_orb_chance: f64,
+    /// Seconds before an orb dropped while boosting disappears.
+    pub boost_orb_lifetime: f32,
+    /// Seconds before a death orb disappears.
+    pub death_orb_lifetime: f32,
+    /// Share of a dead snake's mass dropped as death orbs.
+    pub death_mass_share: f32,
+    pub magnet_base_radius: f32,
+    pub magnet_radius_per_snake_radius: f32,
+    pub magnet_pull_speed: f32,
+}
+
+impl Default for OrbConfig
+{
+    fn default() -> Self
+    {
+        Self {
+            max_count: 5000,
+            per_snake: 50,
+            respawn_rate: 150.0,
+            power_orb_chance: 0.005,
+            boost_orb_lifetime: 20.0,
+            death_orb_lifetime: 45.0,
+            death_mass_share: 0.5,
+            magnet_base_radius: 30.0,
+            magnet_radius_per_snake_radius: 1.5,
+            magnet_pull_speed: 250.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotConfig
+{
+    pub enabled: bool,
+    /// Bots spawned when the match starts, and kept in the arena in modes that respawn them.
+    pub spawn_amount: usize,
+}
+
+impl Default for BotConfig
+{
+    fn default() -> Self
+    {
+        Self {
+            enabled: true,
+            spawn_amount: 5,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError
+{
+    Io(io::Error),
+    Parse(String),
+    /// The file parsed but some values make no sense, one message per problem.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            ConfigError::Io(err) => write!(f, "config io error: {err}"),
+            ConfigError::Parse(err) => write!(f, "config syntax error: {err}"),
+            ConfigError::Invalid(problems) => write!(f, "invalid config: {}", problems.join("; ")),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError
+{
+    fn from(err: io::Error) -> Self
+    {
+        ConfigError::Io(err)
+    }
+}
+
+impl GameConfig
+{
+    /// Parses and validates a config, so a bad file never reaches the game.
+    pub fn from_ron(text: &str) -> Result<Self, ConfigError>
+    {
+        let config: GameConfig = ron::from_str(text).map_err(|err| ConfigError::Parse(err.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn to_ron(&self) -> String
+    {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).expect("game config always serializes")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError>
+    {
+        Self::from_ron(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConfigError>
+    {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_ron())?;
+        Ok(())
+    }
+
+    /// Checks every value and reports all problems at once.
+    pub fn validate(&self) -> Result<(), ConfigError>
+    {
+        let mut problems = Vec::new();
+        let mut require = |ok: bool, problem: &str| {
+            if !ok {
+                problems.push(problem.to_string());
+            }
+        };
+        let positive = |value: f32| value.is_finite() && value > 0.0;
+        let non_negative = |value: f32| value.is_finite() && value >= 0.0;
+
+        require(positive(self.rules.match_duration), "rules.match_duration must be above zero");
+        require(
+            (2..=MAX_TEAMS).contains(&self.rules.team_count),
+            &format!("rules.team_count must be between 2 and {MAX_TEAMS}"),
+        );
+        require(positive(self.arena.map_radius), "arena.map_radius must be above zero");
+        require(positive(self.arena.min_radius), "arena.min_radius must be above zero");
+        require(
+            !positive(self.arena.map_radius) || self.arena.min_radius <= self.arena.map_radius,
+            "arena.min_radius cannot be larger than arena.map_radius",
+        );
+        require(
+            self.arena
+                .shrink_phases
+                .iter()
+                .all(|phase| non_negative(phase.hold) && positive(phase.shrink)),
+            "arena.shrink_phases: hold cannot be negative and shrink must be above zero",
+        );
+        require(
+            positive(self.snake.boost_speed_multiplier),
+            "snake.boost_speed_multiplier must be above zero",
+        );
+        require(
+            non_negative(self.snake.boost_mass_cost) && non_negative(self.snake.mass_needed_for_boosting),
+            "snake boost costs cannot be negative",
+        );
+        require(non_negative(self.orbs.respawn_rate), "orbs.respawn_rate cannot be negative");
+        require(
+            (0.0..=1.0).contains(&self.orbs.power_orb_chance),
+            "orbs.power_orb_chance must be between 0 and 1",
+        );
+        require(
+            (0.0..=1.0).contains(&self.orbs.death_mass_share),
+            "orbs.death_mass_share must be between 0 and 1",
+        );
+        require(
+            positive(self.orbs.boost_orb_lifetime) && positive(self.orbs.death_orb_lifetime),
+            "orb lifetimes must be above zero",
+        );
+        require(
+            non_negative(self.orbs.magnet_base_radius) && non_negative(self.orbs.magnet_radius_per_snake_radius),
+            "orb magnet radii cannot be negative",
+        );
+        require(
+            positive(self.orbs.magnet_pull_speed),
+            "orbs.magnet_pull_speed must be above zero",
+        );
+        require(
+            self.snake.segments.min >= 0.0 && self.snake.radius.min > 0.0 && self.snake.speed.min > 0.0,
+            "snakes need a segment count of at least zero and a radius and speed above zero",
+        );
+        require(self.camera.zoom.min > 0.0, "camera.zoom: min must be above zero");
+
+        self.snake.segments.validate("snake.segments", &mut problems);
+        self.snake.radius.validate("snake.radius", &mut problems);
+        self.snake.speed.validate("snake.speed", &mut problems);
+        self.camera.zoom.validate("camera.zoom", &mut problems);
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+/// Notices edits to a config file by polling its modification time, so the client and the server can apply
+/// balance changes without restarting.
+pub struct ConfigWatcher
+{
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher
+{
+    /// Starts watching from the file's current state, so only later edits are reported.
+    pub fn new(path: impl Into<PathBuf>) -> Self
+    {
+        let path = path.into();
+        let modified = modified_time(&path);
+        Self { path, modified }
+    }
+
+    pub fn path(&self) -> &Path
+    {
+        &self.path
+    }
+
+    /// Loads the config again if the file changed since the last poll. A deleted file is not a change.
+    pub fn poll(&mut self) -> Option<Result<GameConfig, ConfigError>>
+    {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        modified?;
+        Some(GameConfig::load(&self.path))
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime>
+{
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::fs::File;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid_and_round_trip()
+    {
+        let config = GameConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(GameConfig::from_ron(&config.to_ron()).unwrap(), config);
+    }
+
+    #[test]
+    fn missing_values_fall_back_to_defaults()
+    {
+        let config = GameConfig::from_ron("(arena: (map_radius: 1500.0), bots: (enabled: false))").unwrap();
+
+        assert_eq!(config.arena.map_radius, 1500.0);
+        assert!(!config.bots.enabled);
+        assert_eq!(config.bots.spawn_amount, BotConfig::default().spawn_amount);
+        assert_eq!(config.orbs, OrbConfig::default());
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_bad_syntax()
+    {
+        assert!(matches!(
+            GameConfig::from_ron("(arena: (map_raduis: 1500.0))"),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(GameConfig::from_ron("(arena: "), Err(ConfigError::Parse(_))));
+    }
+
+    #[test]
+    fn reports_every_invalid_value()
+    {
+        let text = "(arena: (map_radius: -5.0), orbs: (power_orb_chance: 2.0))";
+        let Err(ConfigError::Invalid(problems)) = GameConfig::from_ron(text) else {
+            panic!("config should be invalid");
+        };
+
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("arena.map_radius"));
+        assert!(problems[1].contains("power_orb_chance"));
+    }
+
+    #[test]
+    fn reads_the_game_mode_by_name()
+    {
+        let config = GameConfig::from_ron("(rules: (mode: BattleRoyale))").unwrap();
+        assert_eq!(config.rules.mode, GameMode::BattleRoyale);
+        assert!(!config.rules.mode.respawns_bots());
+
+        let Err(ConfigError::Invalid(problems)) = GameConfig::from_ron("(rules: (mode: Teams, team_count: 9))") else {
+            panic!("config should be invalid");
+        };
+        assert!(problems[0].contains("rules.team_count"));
+    }
+
+    #[test]
+    fn arena_shrinks_in_equal_steps_between_holds()
+    {
+        let arena = ArenaConfig {
+            map_radius: 1000.0,
+            min_radius: 400.0,
+            shrink_phases: vec![
+                ShrinkPhase {
+                    hold: 10.0,
+                    shrink: 10.0
+                };
+                2
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(arena.radius_at(1000.0, 0.0), 1000.0);
+        assert_eq!(arena.radius_at(1000.0, 15.0), 850.0);
+        assert_eq!(arena.radius_at(1000.0, 25.0), 700.0);
+        assert_eq!(arena.radius_at(1000.0, 35.0), 550.0);
+        assert_eq!(arena.radius_at(1000.0, 100.0), 400.0);
+        // Arenas already smaller than the minimum never shrink
+        assert_eq!(arena.radius_at(300.0, 100.0), 300.0);
+    }
+
+    #[test]
+    fn growth_curves_stay_within_their_limits()
+    {
+        let curve = SnakeConfig::default().speed;
+
+        assert_eq!(curve.at(0.0), 100.0);
+        assert_eq!(curve.at(1.0e9), 70.0);
+        // Negative mass is treated as none at all
+        assert_eq!(curve.at(-10.0), 100.0);
+    }
+
+    #[test]
+    fn watcher_reports_edits_once()
+    {
+        let path = std::env::temp_dir().join(format!("slither_config_{}.ron", std::process::id()));
+        let touch = |text: &str, seconds: u64| {
+            std::fs::write(&path, text).unwrap();
+            // Set the time by hand, file systems with coarse timestamps would otherwise miss quick edits
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+                .unwrap();
+        };
+
+        touch("()", 1);
+        let mut watcher = ConfigWatcher::new(&path);
+        assert!(watcher.poll().is_none());
+
+        touch("(arena: (map_radius: 750.0))", 2);
+        assert_eq!(watcher.poll().unwrap().unwrap().arena.map_radius, 750.0);
+        assert!(watcher.poll().is_none());
+
+        touch("(arena: (map_radius: ", 3);
+        assert!(matches!(watcher.poll(), Some(Err(ConfigError::Parse(_)))));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(watcher.poll().is_none());
+    }
+}
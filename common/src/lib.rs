@@ -1,3 +1,10 @@
+pub mod config;
+pub mod map;
+pub mod profile;
+pub mod ranking;
+pub mod replay;
+pub mod skins;
+
 pub fn add(left: u64, right: u64) -> u64
 {
     left + right
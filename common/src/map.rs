@@ -0,0 +1,635 @@
+use std::path::Path;
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+
+/// File extension of map files.
+pub const MAP_EXTENSION: &str = "ron";
+
+/// Bump this whenever the layout of [`ArenaMap`] changes.
+pub const MAP_VERSION: u32 = 1;
+
+/// The outline of the arena. Snakes cannot leave it and nothing spawns outside it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ArenaShape
+{
+    /// A circle of this radius around the origin.
+    Circle(f32),
+    /// A convex polygon around the origin, corners in order.
+    Polygon(Vec<[f32; 2]>),
+}
+
+/// What is drawn under the arena.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Background
+{
+    /// An sRGB colour.
+    Color([f32; 3]),
+    /// An image path relative to the assets folder, stretched over the arena.
+    Texture(String),
+}
+
+impl Default for Background
+{
+    fn default() -> Self
+    {
+        Background::Color([0.1, 0.1, 0.1])
+    }
+}
+
+/// Solid circle that kills any snake whose head runs into it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Obstacle
+{
+    pub center: [f32; 2],
+    pub radius: f32,
+}
+
+/// Circle that slows down every snake inside it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SlowZone
+{
+    pub center: [f32; 2],
+    pub radius: f32,
+    /// Multiplies the speed of snakes inside, below 1.
+    pub speed_multiplier: f32,
+}
+
+/// Straight strip that speeds up every snake on it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SpeedLane
+{
+    pub from: [f32; 2],
+    pub to: [f32; 2],
+    pub width: f32,
+    /// Multiplies the speed of snakes on the lane, above 1.
+    pub speed_multiplier: f32,
+}
+
+/// Circle where a share of the natural orbs spawn.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FeedingGround
+{
+    pub center: [f32; 2],
+    pub radius: f32,
+    /// Share of all natural orbs spawned here, between 0 and 1.
+    pub orb_share: f32,
+}
+
+/// A whole arena, loaded from a RON map file.
+///
+/// Only the version and shape are required, every feature list can be left out.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ArenaMap
+{
+    /// Must be [`MAP_VERSION`], so an old client never misreads a newer map.
+    pub version: u32,
+    pub shape: ArenaShape,
+    #[serde(default)]
+    pub background: Background,
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+    #[serde(default)]
+    pub slow_zones: Vec<SlowZone>,
+    #[serde(default)]
+    pub speed_lanes: Vec<SpeedLane>,
+    #[serde(default)]
+    pub feeding_grounds: Vec<FeedingGround>,
+    /// Where snakes enter the arena. Empty lets them spawn anywhere.
+    #[serde(default)]
+    pub spawn_points: Vec<[f32; 2]>,
+    /// Multiplies the number of natural orbs the config asks for.
+    #[serde(default = "default_orb_density")]
+    pub orb_density: f32,
+}
+
+fn default_orb_density() -> f32
+{
+    1.0
+}
+
+#[derive(Debug)]
+pub enum MapError
+{
+    Io(io::Error),
+    Parse(String),
+    /// The file parsed but describes an impossible map, one message per problem.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for MapError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            MapError::Io(err) => write!(f, "map io error: {err}"),
+            MapError::Parse(err) => write!(f, "map syntax error: {err}"),
+            MapError::Invalid(problems) => write!(f, "invalid map: {}", problems.join("; ")),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl From<io::Error> for MapError
+{
+    fn from(err: io::Error) -> Self
+    {
+        MapError::Io(err)
+    }
+}
+
+fn distance_squared(a: [f32; 2], b: [f32; 2]) -> f32
+{
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)
+}
+
+fn within(point: [f32; 2], center: [f32; 2], radius: f32) -> bool
+{
+    distance_squared(point, center) <= radius * radius
+}
+
+impl ArenaShape
+{
+    /// Distance from the origin to the furthest point of the arena.
+    pub fn radius(&self) -> f32
+    {
+        match self {
+            ArenaShape::Circle(radius) => *radius,
+            ArenaShape::Polygon(corners) => corners
+                .iter()
+                .map(|&corner| distance_squared(corner, [0.0, 0.0]).sqrt())
+                .fold(0.0, f32::max),
+        }
+    }
+
+    /// Whether a circle of `margin` at `point` fits inside the arena.
+    pub fn contains(&self, point: [f32; 2], margin: f32) -> bool
+    {
+        self.edge_distance(point) >= margin
+    }
+
+    /// Distance from `point` to the nearest edge, negative outside the arena.
+    pub fn edge_distance(&self, point: [f32; 2]) -> f32
+    {
+        match self {
+            ArenaShape::Circle(radius) => radius - distance_squared(point, [0.0, 0.0]).sqrt(),
+            ArenaShape::Polygon(corners) => {
+                let orientation = polygon_orientation(corners);
+                polygon_edges(corners)
+                    .map(|(from, to)| {
+                        let edge = [to[0] - from[0], to[1] - from[1]];
+                        let length = (edge[0] * edge[0] + edge[1] * edge[1]).sqrt();
+                        let cross = edge[0] * (point[1] - from[1]) - edge[1] * (point[0] - from[0]);
+                        // Signed distance from the edge, positive on the inside
+                        cross * orientation / length
+                    })
+                    .fold(f32::INFINITY, f32::min)
+            }
+        }
+    }
+
+    /// The closest point to `point` where a circle of `margin` fits inside the arena, so things pushing against
+    /// the edge slide along it instead of getting stuck.
+    pub fn confine(&self, point: [f32; 2], margin: f32) -> [f32; 2]
+    {
+        match self {
+            ArenaShape::Circle(radius) => {
+                let distance = distance_squared(point, [0.0, 0.0]).sqrt();
+                let limit = (radius - margin).max(0.0);
+                if distance <= limit {
+                    return point;
+                }
+                if distance == 0.0 {
+                    return [0.0, 0.0];
+                }
+                [point[0] * limit / distance, point[1] * limit / distance]
+            }
+            ArenaShape::Polygon(corners) => {
+                let orientation = polygon_orientation(corners);
+                let mut point = point;
+                // Pushing out of one edge can push into a neighbouring one near a corner, so go round a few
+                // times. The polygon is convex, so this settles quickly.
+                for _ in 0..CONFINE_PASSES {
+                    let mut moved = false;
+                    for (from, to) in polygon_edges(corners) {
+                        let edge = [to[0] - from[0], to[1] - from[1]];
+                        let length = (edge[0] * edge[0] + edge[1] * edge[1]).sqrt();
+                        let cross = edge[0] * (point[1] - from[1]) - edge[1] * (point[0] - from[0]);
+                        let distance = cross * orientation / length;
+                        if distance < margin {
+                            let inward = [-edge[1] * orientation / length, edge[0] * orientation / length];
+                            point = [
+                                point[0] + inward[0] * (margin - distance),
+                                point[1] + inward[1] * (margin - distance),
+                            ];
+                            moved = true;
+                        }
+                    }
+                    if !moved {
+                        break;
+                    }
+                }
+                point
+            }
+        }
+    }
+
+    /// `count` points spread evenly along the edge, starting at the first corner, to draw the outline with.
+    pub fn outline(&self, count: usize) -> Vec<[f32; 2]>
+    {
+        match self {
+            ArenaShape::Circle(radius) => (0..count)
+                .map(|i| {
+                    let angle = i as f32 / count as f32 * std::f32::consts::TAU;
+                    [radius * angle.cos(), radius * angle.sin()]
+                })
+                .collect(),
+            ArenaShape::Polygon(corners) => {
+                let edges: Vec<([f32; 2], [f32; 2], f32)> = polygon_edges(corners)
+                    .map(|(from, to)| (from, to, distance_squared(from, to).sqrt()))
+                    .collect();
+                let perimeter: f32 = edges.iter().map(|&(_, _, length)| length).sum();
+                let mut edges = edges.into_iter();
+                let Some(mut edge) = edges.next() else {
+                    return Vec::new();
+                };
+                // How far along the perimeter the current edge starts
+                let mut edge_start = 0.0;
+
+                (0..count)
+                    .map(|i| {
+                        let along = i as f32 / count as f32 * perimeter;
+                        while along > edge_start + edge.2 {
+                            let Some(next) = edges.next() else {
+                                break;
+                            };
+                            edge_start += edge.2;
+                            edge = next;
+                        }
+                        let (from, to, length) = edge;
+                        let t = if length > 0.0 {
+                            ((along - edge_start) / length).min(1.0)
+                        } else {
+                            0.0
+                        };
+                        [from[0] + (to[0] - from[0]) * t, from[1] + (to[1] - from[1]) * t]
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// How often [`ArenaShape::confine`] goes round a polygon's edges at most.
+const CONFINE_PASSES: usize = 4;
+
+fn polygon_edges(corners: &[[f32; 2]]) -> impl Iterator<Item = ([f32; 2], [f32; 2])> + '_
+{
+    corners.iter().copied().zip(corners.iter().copied().cycle().skip(1))
+}
+
+/// 1 for counter-clockwise corners, -1 for clockwise ones.
+fn polygon_orientation(corners: &[[f32; 2]]) -> f32
+{
+    let area: f32 = polygon_edges(corners)
+        .map(|(from, to)| from[0] * to[1] - to[0] * from[1])
+        .sum();
+    area.signum()
+}
+
+/// Every turn goes the same way and the polygon has an area.
+fn is_convex(corners: &[[f32; 2]]) -> bool
+{
+    let orientation = polygon_orientation(corners);
+    let mut turns = corners.iter().enumerate().map(|(index, &corner)| {
+        let next = corners[(index + 1) % corners.len()];
+        let after = corners[(index + 2) % corners.len()];
+        (next[0] - corner[0]) * (after[1] - next[1]) - (next[1] - corner[1]) * (after[0] - next[0])
+    });
+    orientation != 0.0 && turns.all(|turn| turn * orientation >= 0.0)
+}
+
+impl Obstacle
+{
+    /// Whether a circle of `radius` at `point` touches the obstacle.
+    pub fn touches(&self, point: [f32; 2], radius: f32) -> bool
+    {
+        within(point, self.center, self.radius + radius)
+    }
+}
+
+impl SpeedLane
+{
+    pub fn contains(&self, point: [f32; 2]) -> bool
+    {
+        let lane = [self.to[0] - self.from[0], self.to[1] - self.from[1]];
+        let offset = [point[0] - self.from[0], point[1] - self.from[1]];
+        let length_squared = lane[0] * lane[0] + lane[1] * lane[1];
+
+        // Closest point on the lane's centre line
+        let along = if length_squared > 0.0 {
+            ((offset[0] * lane[0] + offset[1] * lane[1]) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let closest = [self.from[0] + lane[0] * along, self.from[1] + lane[1] * along];
+
+        within(point, closest, self.width * 0.5)
+    }
+}
+
+impl ArenaMap
+{
+    /// An empty circular arena, used when the config names no map.
+    pub fn circle(radius: f32) -> Self
+    {
+        Self {
+            version: MAP_VERSION,
+            shape: ArenaShape::Circle(radius),
+            background: Background::default(),
+            obstacles: Vec::new(),
+            slow_zones: Vec::new(),
+            speed_lanes: Vec::new(),
+            feeding_grounds: Vec::new(),
+            spawn_points: Vec::new(),
+            orb_density: 1.0,
+        }
+    }
+
+    /// Parses and validates a map, so a broken file never reaches the game.
+    pub fn from_ron(text: &str) -> Result<Self, MapError>
+    {
+        let map: ArenaMap = ron::from_str(text).map_err(|err| MapError::Parse(err.to_string()))?;
+        map.validate()?;
+        Ok(map)
+    }
+
+    pub fn to_ron(&self) -> String
+    {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).expect("maps always serialize")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MapError>
+    {
+        Self::from_ron(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MapError>
+    {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_ron())?;
+        Ok(())
+    }
+
+    /// Whether a circle of `radius` at `point` touches any obstacle.
+    pub fn hits_obstacle(&self, point: [f32; 2], radius: f32) -> bool
+    {
+        self.obstacles.iter().any(|obstacle| obstacle.touches(point, radius))
+    }
+
+    /// Combined effect of every slow zone and speed lane at `point`.
+    pub fn speed_multiplier_at(&self, point: [f32; 2]) -> f32
+    {
+        let slow = self
+            .slow_zones
+            .iter()
+            .filter(|zone| within(point, zone.center, zone.radius))
+            .map(|zone| zone.speed_multiplier);
+        let fast = self
+            .speed_lanes
+            .iter()
+            .filter(|lane| lane.contains(point))
+            .map(|lane| lane.speed_multiplier);
+
+        slow.chain(fast).product()
+    }
+
+    /// Picks the feeding ground a natural orb spawns in from a roll between 0 and 1, or `None` when the orb
+    /// spawns anywhere in the arena.
+    pub fn feeding_ground_for(&self, roll: f32) -> Option<&FeedingGround>
+    {
+        let mut threshold = 0.0;
+        self.feeding_grounds.iter().find(|ground| {
+            threshold += ground.orb_share;
+            roll < threshold
+        })
+    }
+
+    /// Checks every feature and reports all problems at once.
+    pub fn validate(&self) -> Result<(), MapError>
+    {
+        let positive = |value: f32| value.is_finite() && value > 0.0;
+        let finite = |point: [f32; 2]| point.iter().all(|value| value.is_finite());
+
+        // Nothing else can be trusted in a file written for another version
+        if self.version != MAP_VERSION {
+            return Err(MapError::Invalid(vec![format!(
+                "version {} is not supported, this game reads version {MAP_VERSION}",
+                self.version
+            )]));
+        }
+
+        let mut problems = Vec::new();
+
+        let shape_ok = match &self.shape {
+            ArenaShape::Circle(radius) => positive(*radius),
+            ArenaShape::Polygon(corners) => {
+                corners.len() >= 3 && corners.iter().all(|&corner| finite(corner)) && is_convex(corners)
+            }
+        };
+        if !shape_ok {
+            problems.push("shape: needs a circle radius above zero or a convex polygon of at least 3 corners".to_string());
+        }
+        if let Background::Color(color) = self.background {
+            if !color.iter().all(|channel| (0.0..=1.0).contains(channel)) {
+                problems.push("background: colour channels must be between 0 and 1".to_string());
+            }
+        }
+        if !positive(self.orb_density) {
+            problems.push("orb_density must be above zero".to_string());
+        }
+
+        for (index, obstacle) in self.obstacles.iter().enumerate() {
+            if !finite(obstacle.center) || !positive(obstacle.radius) {
+                problems.push(format!("obstacles[{index}]: needs a finite center and a radius above zero"));
+            }
+        }
+        for (index, zone) in self.slow_zones.iter().enumerate() {
+            if !finite(zone.center) || !positive(zone.radius) {
+                problems.push(format!("slow_zones[{index}]: needs a finite center and a radius above zero"));
+            }
+            if !(positive(zone.speed_multiplier) && zone.speed_multiplier < 1.0) {
+                problems.push(format!("slow_zones[{index}]: speed_multiplier must be between 0 and 1"));
+            }
+        }
+        for (index, lane) in self.speed_lanes.iter().enumerate() {
+            if !finite(lane.from) || !finite(lane.to) || !positive(lane.width) {
+                problems.push(format!("speed_lanes[{index}]: needs finite ends and a width above zero"));
+            }
+            if !(lane.speed_multiplier.is_finite() && lane.speed_multiplier > 1.0) {
+                problems.push(format!("speed_lanes[{index}]: speed_multiplier must be above 1"));
+            }
+        }
+        for (index, ground) in self.feeding_grounds.iter().enumerate() {
+            if !finite(ground.center) || !positive(ground.radius) {
+                problems.push(format!(
+                    "feeding_grounds[{index}]: needs a finite center and a radius above zero"
+                ));
+            }
+            if !positive(ground.orb_share) {
+                problems.push(format!("feeding_grounds[{index}]: orb_share must be above zero"));
+            }
+        }
+        if self.feeding_grounds.iter().map(|ground| ground.orb_share).sum::<f32>() > 1.0 {
+            problems.push("feeding_grounds: orb shares cannot add up to more than 1".to_string());
+        }
+        for (index, &point) in self.spawn_points.iter().enumerate() {
+            let inside = shape_ok && self.shape.contains(point, 0.0);
+            if !finite(point) || !inside || self.hits_obstacle(point, 0.0) {
+                problems.push(format!(
+                    "spawn_points[{index}]: must be inside the arena and outside every obstacle"
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(MapError::Invalid(problems))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn lane() -> SpeedLane
+    {
+        SpeedLane {
+            from: [0.0, 0.0],
+            to: [100.0, 0.0],
+            width: 20.0,
+            speed_multiplier: 1.5,
+        }
+    }
+
+    #[test]
+    fn zones_stack_their_speed_multipliers()
+    {
+        let map = ArenaMap {
+            slow_zones: vec![SlowZone {
+                center: [100.0, 0.0],
+                radius: 50.0,
+                speed_multiplier: 0.5,
+            }],
+            speed_lanes: vec![lane()],
+            ..ArenaMap::circle(1000.0)
+        };
+
+        assert_eq!(map.speed_multiplier_at([20.0, 5.0]), 1.5);
+        assert_eq!(map.speed_multiplier_at([90.0, 0.0]), 0.75);
+        assert_eq!(map.speed_multiplier_at([120.0, 20.0]), 0.5);
+        assert_eq!(map.speed_multiplier_at([50.0, 40.0]), 1.0);
+    }
+
+    #[test]
+    fn feeding_grounds_split_the_roll_by_share()
+    {
+        let ground = |orb_share| FeedingGround {
+            center: [0.0, 0.0],
+            radius: 10.0,
+            orb_share,
+        };
+        let map = ArenaMap {
+            feeding_grounds: vec![ground(0.2), ground(0.3)],
+            ..ArenaMap::circle(1000.0)
+        };
+
+        assert_eq!(map.feeding_ground_for(0.1).unwrap().orb_share, 0.2);
+        assert_eq!(map.feeding_ground_for(0.4).unwrap().orb_share, 0.3);
+        assert!(map.feeding_ground_for(0.6).is_none());
+    }
+
+    #[test]
+    fn reports_every_broken_feature()
+    {
+        let text = "(version: 1, shape: Circle(500.0), obstacles: [(center: (0.0, 0.0), radius: -1.0)], speed_lanes: \
+                    [(from: (0.0, 0.0), to: (1.0, 0.0), width: 5.0, speed_multiplier: 0.5)])";
+        let Err(MapError::Invalid(problems)) = ArenaMap::from_ron(text) else {
+            panic!("map should be invalid");
+        };
+
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("obstacles[0]"));
+        assert!(problems[1].starts_with("speed_lanes[0]"));
+    }
+
+    #[test]
+    fn rejects_maps_from_other_versions()
+    {
+        let Err(MapError::Invalid(problems)) = ArenaMap::from_ron("(version: 99, shape: Circle(500.0))") else {
+            panic!("map should be rejected");
+        };
+        assert!(problems[0].contains("version 99"));
+
+        let Err(MapError::Parse(err)) = ArenaMap::from_ron("(shape: Circle(500.0))") else {
+            panic!("map without a version should not parse");
+        };
+        assert!(err.contains("version"));
+    }
+
+    #[test]
+    fn polygons_must_be_convex_and_contain_their_spawn_points()
+    {
+        let square = ArenaShape::Polygon(vec![[-100.0, -100.0], [100.0, -100.0], [100.0, 100.0], [-100.0, 100.0]]);
+        assert!(square.contains([90.0, 0.0], 5.0));
+        assert!(!square.contains([90.0, 0.0], 20.0));
+        assert!(!square.contains([150.0, 0.0], 0.0));
+        assert_eq!(square.edge_distance([90.0, 50.0]), 10.0);
+        assert_eq!(square.radius(), 100.0 * 2.0f32.sqrt());
+        // Pushing out of the side slides along it, pushing out of a corner ends in the corner
+        assert_eq!(square.confine([120.0, 30.0], 10.0), [90.0, 30.0]);
+        assert_eq!(square.confine([120.0, 130.0], 10.0), [90.0, 90.0]);
+        assert_eq!(square.confine([0.0, 0.0], 10.0), [0.0, 0.0]);
+        assert_eq!(
+            square.outline(8),
+            [
+                [-100.0, -100.0],
+                [0.0, -100.0],
+                [100.0, -100.0],
+                [100.0, 0.0],
+                [100.0, 100.0],
+                [0.0, 100.0],
+                [-100.0, 100.0],
+                [-100.0, 0.0]
+            ]
+        );
+
+        let mut map = ArenaMap {
+            shape: square,
+            spawn_points: vec![[0.0, 0.0], [300.0, 0.0]],
+            ..ArenaMap::circle(1000.0)
+        };
+        let Err(MapError::Invalid(problems)) = map.validate() else {
+            panic!("spawn point outside the arena should be rejected");
+        };
+        assert_eq!(
+            problems,
+            ["spawn_points[1]: must be inside the arena and outside every obstacle"]
+        );
+
+        map.shape = ArenaShape::Polygon(vec![[0.0, 0.0], [100.0, 0.0], [10.0, 10.0], [0.0, 100.0]]);
+        map.spawn_points.clear();
+        assert!(map.validate().is_err());
+    }
+}
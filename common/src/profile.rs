@@ -0,0 +1,221 @@
+use std::path::Path;
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameMode;
+
+/// Bump this whenever the layout of [`PlayerProfile`] changes in a way old files can't be read with.
+pub const PROFILE_VERSION: u32 = 1;
+
+/// How many finished games the profile remembers.
+pub const RECENT_GAMES: usize = 10;
+
+/// The outcome of one game of the local player.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GameRecord
+{
+    pub mode: GameMode,
+    pub score: u32,
+    pub length: u32,
+    pub kills: u32,
+    /// Seconds the snake was alive.
+    pub duration: f32,
+    /// Seconds since the unix epoch when the game ended.
+    pub ended_at: u64,
+}
+
+/// Best results and totals of the local player, kept between sessions.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct PlayerProfile
+{
+    pub version: u32,
+    pub best_score: u32,
+    pub longest_length: u32,
+    pub total_kills: u32,
+    pub games_played: u32,
+    /// Seconds spent alive over every game.
+    pub playtime: f32,
+    /// The most recent game first.
+    pub recent_games: Vec<GameRecord>,
+    /// The server account the player signed up for, if any.
+    pub account: Option<SavedAccount>,
+}
+
+/// What it takes to sign back in to a server account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SavedAccount
+{
+    pub name: String,
+    pub token: String,
+}
+
+impl Default for PlayerProfile
+{
+    fn default() -> Self
+    {
+        Self {
+            version: PROFILE_VERSION,
+            best_score: 0,
+            longest_length: 0,
+            total_kills: 0,
+            games_played: 0,
+            playtime: 0.0,
+            recent_games: Vec::new(),
+            account: None,
+        }
+    }
+}
+
+/// Read ahead of the rest so a file from another version is reported as such rather than as a parse error.
+#[derive(Deserialize)]
+struct ProfileVersion
+{
+    #[serde(default)]
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum ProfileError
+{
+    Io(io::Error),
+    Parse(String),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ProfileError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            ProfileError::Io(err) => write!(f, "profile io error: {err}"),
+            ProfileError::Parse(err) => write!(f, "corrupt profile: {err}"),
+            ProfileError::UnsupportedVersion(version) => {
+                write!(f, "unsupported profile version {version} (expected {PROFILE_VERSION})")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl From<io::Error> for ProfileError
+{
+    fn from(err: io::Error) -> Self
+    {
+        ProfileError::Io(err)
+    }
+}
+
+impl PlayerProfile
+{
+    /// Adds a finished game to the totals and the recent games.
+    pub fn record(&mut self, game: GameRecord)
+    {
+        self.best_score = self.best_score.max(game.score);
+        self.longest_length = self.longest_length.max(game.length);
+        self.total_kills += game.kills;
+        self.games_played += 1;
+        self.playtime += game.duration;
+
+        self.recent_games.insert(0, game);
+        self.recent_games.truncate(RECENT_GAMES);
+    }
+
+    /// Fields missing from the file keep their defaults, so only broken syntax or another version fail.
+    pub fn from_ron(text: &str) -> Result<Self, ProfileError>
+    {
+        let parse_error = |err: ron::error::SpannedError| ProfileError::Parse(err.to_string());
+        let ProfileVersion { version } = ron::from_str(text).map_err(parse_error)?;
+        if version != PROFILE_VERSION {
+            return Err(ProfileError::UnsupportedVersion(version));
+        }
+        ron::from_str(text).map_err(parse_error)
+    }
+
+    pub fn to_ron(&self) -> String
+    {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).expect("player profile always serializes")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ProfileError>
+    {
+        Self::from_ron(&std::fs::read_to_string(path)?)
+    }
+
+    /// Writes to a temporary file first and moves it over the old one, so a crash mid-write can't leave a
+    /// half written profile behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ProfileError>
+    {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, self.to_ron())?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn game(score: u32, kills: u32) -> GameRecord
+    {
+        GameRecord {
+            mode: GameMode::Classic,
+            score,
+            length: score / 2,
+            kills,
+            duration: 60.0,
+            ended_at: 0,
+        }
+    }
+
+    #[test]
+    fn keeps_the_bests_and_the_latest_games()
+    {
+        let mut profile = PlayerProfile::default();
+        for score in 0..RECENT_GAMES as u32 + 2 {
+            profile.record(game(score * 10, 1));
+        }
+        profile.record(game(5, 2));
+
+        assert_eq!(profile.best_score, (RECENT_GAMES as u32 + 1) * 10);
+        assert_eq!(profile.longest_length, (RECENT_GAMES as u32 + 1) * 5);
+        assert_eq!(profile.total_kills, RECENT_GAMES as u32 + 4);
+        assert_eq!(profile.games_played, RECENT_GAMES as u32 + 3);
+        assert_eq!(profile.recent_games.len(), RECENT_GAMES);
+        assert_eq!(profile.recent_games[0].score, 5);
+    }
+
+    #[test]
+    fn round_trips_through_ron()
+    {
+        let mut profile = PlayerProfile::default();
+        profile.record(game(120, 3));
+
+        assert_eq!(PlayerProfile::from_ron(&profile.to_ron()).unwrap(), profile);
+    }
+
+    #[test]
+    fn tolerates_missing_fields_but_not_other_versions_or_garbage()
+    {
+        let partial = PlayerProfile::from_ron("(version: 1, best_score: 40)").unwrap();
+        assert_eq!(partial.best_score, 40);
+        assert!(partial.recent_games.is_empty());
+
+        assert!(matches!(
+            PlayerProfile::from_ron("(version: 2)"),
+            Err(ProfileError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            PlayerProfile::from_ron("(version: 1, best_sc"),
+            Err(ProfileError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// How many names each top list holds.
+pub const TOP_LIST_SIZE: usize = 10;
+
+/// A score someone reached at some point, the raw material of the top lists.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RankedScore
+{
+    pub name: String,
+    pub score: u32,
+    /// Seconds since the unix epoch.
+    pub achieved_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LeaderboardEntry
+{
+    pub name: String,
+    pub score: u32,
+}
+
+/// The window a top list covers. Days and weeks follow UTC and weeks start on Monday.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Period
+{
+    Daily,
+    Weekly,
+    AllTime,
+}
+
+impl Period
+{
+    /// Whether a score from `achieved_at` still counts at `now`.
+    pub fn contains(self, achieved_at: u64, now: u64) -> bool
+    {
+        let day = |time: u64| time / SECONDS_PER_DAY;
+        // The unix epoch was a Thursday
+        let week = |time: u64| (day(time) + 3) / 7;
+        match self {
+            Period::Daily => day(achieved_at) == day(now),
+            Period::Weekly => week(achieved_at) == week(now),
+            Period::AllTime => true,
+        }
+    }
+}
+
+/// The best score of each name within the period, highest first.
+pub fn top_scores<'a>(scores: impl IntoIterator<Item = &'a RankedScore>, period: Period, now: u64) -> Vec<LeaderboardEntry>
+{
+    let mut best: Vec<LeaderboardEntry> = Vec::new();
+    for score in scores.into_iter().filter(|score| period.contains(score.achieved_at, now)) {
+        match best.iter_mut().find(|entry| entry.name == score.name) {
+            Some(entry) => entry.score = entry.score.max(score.score),
+            None => best.push(LeaderboardEntry {
+                name: score.name.clone(),
+                score: score.score,
+            }),
+        }
+    }
+
+    best.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    best.truncate(TOP_LIST_SIZE);
+    best
+}
+
+/// The daily, weekly and all-time top lists together, as shown between rounds.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Leaderboards
+{
+    pub daily: Vec<LeaderboardEntry>,
+    pub weekly: Vec<LeaderboardEntry>,
+    pub all_time: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboards
+{
+    pub fn from_scores(scores: &[RankedScore], now: u64) -> Self
+    {
+        Self {
+            daily: top_scores(scores, Period::Daily, now),
+            weekly: top_scores(scores, Period::Weekly, now),
+            all_time: top_scores(scores, Period::AllTime, now),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // Wednesday 2024-01-03, noon UTC
+    const NOW: u64 = 1_704_283_200;
+
+    fn score(name: &str, score: u32, days_ago: u64) -> RankedScore
+    {
+        RankedScore {
+            name: name.into(),
+            score,
+            achieved_at: NOW - days_ago * SECONDS_PER_DAY,
+        }
+    }
+
+    #[test]
+    fn periods_follow_calendar_days_and_monday_weeks()
+    {
+        let monday_morning = NOW - 2 * SECONDS_PER_DAY - 11 * 60 * 60;
+        let sunday_night = monday_morning - 2 * 60 * 60;
+
+        assert!(Period::Daily.contains(NOW - 60, NOW));
+        assert!(!Period::Daily.contains(NOW - SECONDS_PER_DAY, NOW));
+        assert!(Period::Weekly.contains(monday_morning, NOW));
+        assert!(!Period::Weekly.contains(sunday_night, NOW));
+        assert!(Period::AllTime.contains(0, NOW));
+    }
+
+    #[test]
+    fn each_name_appears_once_with_its_best_score_in_the_period()
+    {
+        let scores = [
+            score("viper", 50, 0),
+            score("viper", 80, 1),
+            score("adder", 60, 0),
+            score("mamba", 500, 30),
+        ];
+
+        let boards = Leaderboards::from_scores(&scores, NOW);
+        let names = |list: &[LeaderboardEntry]| -> Vec<(String, u32)> {
+            list.iter().map(|entry| (entry.name.clone(), entry.score)).collect()
+        };
+
+        assert_eq!(names(&boards.daily), [("adder".into(), 60), ("viper".into(), 50)]);
+        assert_eq!(names(&boards.weekly), [("viper".into(), 80), ("adder".into(), 60)]);
+        assert_eq!(
+            boards.all_time[0],
+            LeaderboardEntry {
+                name: "mamba".into(),
+                score: 500
+            }
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::skins::SkinId;
+
+/// Every replay file starts with these bytes so we can reject random files early.
+pub const REPLAY_MAGIC: [u8; 4] = *b"SWRP";
+
+/// Bump this whenever the layout of [`Replay`] changes.
+///
+/// After the magic and the version a file holds the header followed by one frame after another until the end, so
+/// recorders can append frames as the match goes on.
+pub const REPLAY_VERSION: u16 = 4;
+
+/// File extension used for replay files.
+pub const REPLAY_EXTENSION: &str = "swr";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayHeader
+{
+    /// Who wrote the file. Only the client records for now, the server has no match of its own to record until it
+    /// simulates one.
+    pub recorded_by: String,
+    pub map_radius: f32,
+    /// Seconds of match time between two frames.
+    pub snapshot_interval: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SnakeSnapshot
+{
+    /// Stable id of the snake for the whole match, used to match snakes between frames.
+    pub id: u64,
+    pub name: String,
+    pub color: [f32; 3],
+    pub skin: SkinId,
+    pub radius: f32,
+    pub score: u32,
+    /// Head first, then every body segment in order.
+    pub body: Vec<[f32; 2]>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrbSnapshot
+{
+    pub position: [f32; 2],
+    pub radius: f32,
+    pub color: [f32; 3],
+}
+
+/// Gameplay events that happened since the previous frame.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ReplayEvent
+{
+    SnakeDied
+    {
+        victim: u64,
+        victim_name: String,
+        killer_name: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ReplayFrame
+{
+    /// Match time in seconds when this frame was taken.
+    pub time: f32,
+    pub snakes: Vec<SnakeSnapshot>,
+    pub orbs: Vec<OrbSnapshot>,
+    pub events: Vec<ReplayEvent>,
+}
+
+/// A recorded match made of periodic snapshots of the whole world.
+///
+/// Snapshots are used instead of inputs so a replay plays back the same no matter which side
+/// recorded it, even though the simulation itself is not deterministic.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Replay
+{
+    pub header: ReplayHeader,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError
+{
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u16),
+    Encode(String),
+    Decode(String),
+}
+
+impl fmt::Display for ReplayError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            ReplayError::Io(err) => write!(f, "replay io error: {err}"),
+            ReplayError::BadMagic => write!(f, "not a slither wars replay file"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay version {version} (expected {REPLAY_VERSION})")
+            }
+            ReplayError::Encode(err) => write!(f, "failed to encode replay: {err}"),
+            ReplayError::Decode(err) => write!(f, "corrupt replay file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError
+{
+    fn from(err: io::Error) -> Self
+    {
+        ReplayError::Io(err)
+    }
+}
+
+impl Replay
+{
+    pub fn new(header: ReplayHeader) -> Self
+    {
+        Self {
+            header,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn push_frame(&mut self, frame: ReplayFrame)
+    {
+        self.frames.push(frame);
+    }
+
+    /// Length of the recording in seconds.
+    pub fn duration(&self) -> f32
+    {
+        self.frames.last().map_or(0.0, |frame| frame.time)
+    }
+
+    /// Returns the index of the last frame taken at or before `time`.
+    pub fn frame_index_at(&self, time: f32) -> usize
+    {
+        self.frames.partition_point(|frame| frame.time <= time).saturating_sub(1)
+    }
+
+    pub fn write_to(&self, writer: impl Write) -> Result<(), ReplayError>
+    {
+        let mut writer = ReplayWriter::new(writer, &self.header)?;
+        for frame in &self.frames {
+            writer.write_frame(frame)?;
+        }
+        writer.flush()
+    }
+
+    /// A file cut off in the middle of a frame, e.g. because the recorder crashed, plays up to its last whole
+    /// frame.
+    pub fn read_from(reader: impl Read) -> Result<Self, ReplayError>
+    {
+        let mut reader = BufReader::new(reader);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != REPLAY_MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let header = bincode::deserialize_from(&mut reader).map_err(|err| ReplayError::Decode(err.to_string()))?;
+        let mut replay = Replay::new(header);
+        while !reader.fill_buf()?.is_empty() {
+            match bincode::deserialize_from(&mut reader) {
+                Ok(frame) => replay.push_frame(frame),
+                Err(err) if matches!(&*err, bincode::ErrorKind::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof) => {
+                    break;
+                }
+                Err(err) => return Err(ReplayError::Decode(err.to_string())),
+            }
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError>
+    {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError>
+    {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Writes a replay frame by frame while the match is still running, so the recording never has to be kept in
+/// memory as a whole.
+pub struct ReplayWriter<W: Write>
+{
+    writer: W,
+    frames_written: usize,
+}
+
+impl ReplayWriter<BufWriter<File>>
+{
+    pub fn create(path: impl AsRef<Path>, header: &ReplayHeader) -> Result<Self, ReplayError>
+    {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::new(BufWriter::new(File::create(path)?), header)
+    }
+}
+
+impl<W: Write> ReplayWriter<W>
+{
+    pub fn new(mut writer: W, header: &ReplayHeader) -> Result<Self, ReplayError>
+    {
+        writer.write_all(&REPLAY_MAGIC)?;
+        writer.write_all(&REPLAY_VERSION.to_le_bytes())?;
+        bincode::serialize_into(&mut writer, header).map_err(|err| ReplayError::Encode(err.to_string()))?;
+        Ok(Self {
+            writer,
+            frames_written: 0,
+        })
+    }
+
+    pub fn write_frame(&mut self, frame: &ReplayFrame) -> Result<(), ReplayError>
+    {
+        bincode::serialize_into(&mut self.writer, frame).map_err(|err| ReplayError::Encode(err.to_string()))?;
+        self.frames_written += 1;
+        Ok(())
+    }
+
+    pub fn frames_written(&self) -> usize
+    {
+        self.frames_written
+    }
+
+    pub fn flush(&mut self) -> Result<(), ReplayError>
+    {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn sample_replay() -> Replay
+    {
+        let mut replay = Replay::new(ReplayHeader {
+            recorded_by: "client".into(),
+            map_radius: 1000.0,
+            snapshot_interval: 0.1,
+        });
+
+        for i in 0..3 {
+            replay.push_frame(ReplayFrame {
+                time: i as f32 * 0.1,
+                snakes: vec![SnakeSnapshot {
+                    id: 7,
+                    name: "Player 1".into(),
+                    color: [1.0, 0.0, 0.0],
+                    skin: 0,
+                    radius: 12.5,
+                    score: i,
+                    body: vec![[i as f32, 0.0], [i as f32 - 25.0, 0.0]],
+                }],
+                orbs: vec![OrbSnapshot {
+                    position: [10.0, 10.0],
+                    radius: 5.0,
+                    color: [0.0, 1.0, 0.0],
+                }],
+                events: vec![ReplayEvent::SnakeDied {
+                    victim: 8,
+                    victim_name: "Bot Viper".into(),
+                    killer_name: Some("Player 1".into()),
+                }],
+            });
+        }
+
+        replay
+    }
+
+    #[test]
+    fn round_trips_through_bytes()
+    {
+        let replay = sample_replay();
+        let mut bytes = Vec::new();
+        replay.write_to(&mut bytes).unwrap();
+
+        assert_eq!(Replay::read_from(bytes.as_slice()).unwrap(), replay);
+    }
+
+    #[test]
+    fn plays_up_to_the_last_whole_frame_of_a_cut_off_file()
+    {
+        let replay = sample_replay();
+        let mut bytes = Vec::new();
+        replay.write_to(&mut bytes).unwrap();
+        bytes.truncate(bytes.len() - 3);
+
+        let read = Replay::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(read.header, replay.header);
+        assert_eq!(read.frames, replay.frames[..2]);
+    }
+
+    #[test]
+    fn rejects_other_versions()
+    {
+        let mut bytes = Vec::new();
+        sample_replay().write_to(&mut bytes).unwrap();
+        bytes[4] = bytes[4].wrapping_add(1);
+
+        assert!(matches!(
+            Replay::read_from(bytes.as_slice()),
+            Err(ReplayError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn finds_frame_for_time()
+    {
+        let replay = sample_replay();
+
+        assert_eq!(replay.frame_index_at(0.0), 0);
+        assert_eq!(replay.frame_index_at(0.15), 1);
+        assert_eq!(replay.frame_index_at(5.0), 2);
+    }
+}
@@ -0,0 +1,118 @@
+/// Index into [`SKINS`]. This is what goes over the network and into replays.
+pub type SkinId = u8;
+
+/// How a snake looks: a pattern of colours repeated along the body and an optional glow.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Skin
+{
+    pub name: &'static str,
+    /// sRGB colours that repeat along the body, starting at the head.
+    pub pattern: &'static [[f32; 3]],
+    /// Number of segments each pattern colour covers before the next one starts.
+    pub stripe_length: usize,
+    /// Extra brightness on top of the colour, picked up by the bloom on the camera. Zero means no glow.
+    pub glow: f32,
+}
+
+impl Skin
+{
+    /// The colour of the segment at `index`, where index 0 is the head.
+    pub fn color_at(&self, index: usize) -> [f32; 3]
+    {
+        self.pattern[(index / self.stripe_length.max(1)) % self.pattern.len()]
+    }
+
+    /// The colour of the head, used wherever a snake is shown as a single colour.
+    pub fn primary_color(&self) -> [f32; 3]
+    {
+        self.pattern[0]
+    }
+}
+
+const fn solid(name: &'static str, color: &'static [[f32; 3]]) -> Skin
+{
+    Skin {
+        name,
+        pattern: color,
+        stripe_length: 1,
+        glow: 0.0,
+    }
+}
+
+/// Every skin a snake can wear. Ids are indices into this list, so only ever append to it.
+pub const SKINS: &[Skin] = &[
+    solid("Red", &[[1.0, 0.0, 0.0]]),
+    solid("Green", &[[0.0, 1.0, 0.0]]),
+    solid("Blue", &[[0.0, 0.0, 1.0]]),
+    solid("Yellow", &[[1.0, 1.0, 0.0]]),
+    solid("Orange", &[[1.0, 0.65, 0.0]]),
+    solid("Purple", &[[0.5, 0.0, 0.5]]),
+    solid("Cyan", &[[0.0, 1.0, 1.0]]),
+    solid("Pink", &[[1.0, 0.75, 0.8]]),
+    Skin {
+        name: "Coral",
+        pattern: &[[0.9, 0.1, 0.1], [0.05, 0.05, 0.05], [1.0, 0.9, 0.3], [0.05, 0.05, 0.05]],
+        stripe_length: 2,
+        glow: 0.0,
+    },
+    Skin {
+        name: "Bumblebee",
+        pattern: &[[1.0, 0.8, 0.0], [0.1, 0.1, 0.1]],
+        stripe_length: 3,
+        glow: 0.0,
+    },
+    Skin {
+        name: "Rainbow",
+        pattern: &[
+            [1.0, 0.0, 0.0],
+            [1.0, 0.5, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.5, 1.0],
+            [0.5, 0.0, 1.0],
+        ],
+        stripe_length: 1,
+        glow: 0.0,
+    },
+    Skin {
+        name: "Neon",
+        pattern: &[[0.0, 1.0, 0.8], [1.0, 0.0, 0.8]],
+        stripe_length: 4,
+        glow: 1.5,
+    },
+    Skin {
+        name: "Ember",
+        pattern: &[[1.0, 0.3, 0.0], [1.0, 0.6, 0.1], [0.8, 0.1, 0.0]],
+        stripe_length: 2,
+        glow: 0.8,
+    },
+];
+
+/// Looks up a skin, falling back to the first one for ids we do not know, e.g. from a newer client.
+pub fn skin(id: SkinId) -> &'static Skin
+{
+    SKINS.get(id as usize).unwrap_or(&SKINS[0])
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn pattern_repeats_along_the_body()
+    {
+        let bumblebee = SKINS.iter().find(|skin| skin.name == "Bumblebee").unwrap();
+
+        assert_eq!(bumblebee.color_at(0), bumblebee.color_at(2));
+        assert_ne!(bumblebee.color_at(2), bumblebee.color_at(3));
+        assert_eq!(bumblebee.color_at(0), bumblebee.color_at(6));
+    }
+
+    #[test]
+    fn unknown_ids_fall_back_to_the_first_skin()
+    {
+        assert_eq!(skin(SkinId::MAX), &SKINS[0]);
+        assert!(SKINS.len() <= SkinId::MAX as usize);
+    }
+}
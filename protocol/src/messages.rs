@@ -0,0 +1,308 @@
+use std::io::{self, Read, Write};
+
+use serde::{Deserialize, Serialize};
+use slither_common::config::GameConfig;
+use slither_common::ranking::Leaderboards;
+use slither_common::skins::SkinId;
+
+/// Messages sent from a client to the server.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ClientMessage
+{
+    /// Sent on connecting and again whenever the player picks another skin.
+    Join(JoinRequest),
+    /// Signs in to an existing account, or creates one when no token is given.
+    Login(LoginRequest),
+    /// Asks for the daily, weekly and all-time top lists.
+    FetchLeaderboards,
+    /// Reports how a game of the signed in player went, for their stats and the top lists.
+    SubmitResult(GameResult),
+    /// Where the client's snake is, sent at the minimap's low rate while it is alive so the server can show it
+    /// on everyone else's minimap.
+    ReportSnake(SnakeReport),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SnakeReport
+{
+    pub position: [f32; 2],
+    pub radius: f32,
+    pub score: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GameResult
+{
+    pub score: u32,
+    pub kills: u32,
+    /// Seconds the snake was alive.
+    pub duration: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LoginRequest
+{
+    pub name: String,
+    /// The token the server handed out when the account was created.
+    pub token: Option<String>,
+}
+
+/// The name and skin the other players see the client's snake with.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct JoinRequest
+{
+    pub name: String,
+    pub skin: SkinId,
+}
+
+/// Messages sent from the server to connected clients.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ServerMessage
+{
+    /// The largest snakes of the other connected clients, sent to each client at a low rate.
+    MinimapSummary(MinimapSummary),
+    /// The balance settings the match is played with, sent when a client connects and again whenever the server's
+    /// rules file is edited. Clients use them in place of their own config.
+    Rules(Box<GameConfig>),
+    /// The map the match is played on, sent after the rules when a client connects and again whenever an edit to
+    /// the rules switches maps.
+    LoadMap(MapChoice),
+    /// The account the client is signed in to. Clients keep the token to sign in again next time.
+    LoggedIn
+    {
+        name: String,
+        token: String,
+    },
+    /// Why a login was refused, e.g. a name that belongs to someone else.
+    LoginRejected(String),
+    Leaderboards(Leaderboards),
+    /// A request the server could not carry out, e.g. because its storage backend is down.
+    RequestFailed(String),
+    /// A player joined or picked another skin. The id is the one their snake has in minimap summaries, clients
+    /// keep the skin around to draw the snake with.
+    SnakeJoined
+    {
+        id: u64,
+        name: String,
+        skin: SkinId,
+    },
+}
+
+/// Names a map file in the maps folder that every client ships with.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MapChoice
+{
+    pub name: String,
+    /// Map format version the server loaded the file with, so clients that cannot read it can say so.
+    pub version: u32,
+}
+
+/// A low-rate overview of the arena used to draw the minimap without full snapshots.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MinimapSummary
+{
+    pub map_radius: f32,
+    /// The largest snakes in the arena, biggest first.
+    pub snakes: Vec<MinimapSnake>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MinimapSnake
+{
+    pub id: u64,
+    pub position: [f32; 2],
+    pub radius: f32,
+    pub score: u32,
+}
+
+/// Messages bigger than this are refused rather than read into memory.
+pub const MAX_MESSAGE_LENGTH: u32 = 1 << 20;
+
+/// Writes a message to a stream, prefixed with its length so the other side knows where it ends.
+fn write_frame(mut writer: impl Write, bytes: &[u8]) -> io::Result<()>
+{
+    let length = u32::try_from(bytes.len())
+        .ok()
+        .filter(|&length| length <= MAX_MESSAGE_LENGTH)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "message too long"))?;
+    writer.write_all(&length.to_le_bytes())?;
+    writer.write_all(bytes)?;
+    writer.flush()
+}
+
+fn read_frame(mut reader: impl Read) -> io::Result<Vec<u8>>
+{
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length);
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "message too long"));
+    }
+
+    let mut bytes = vec![0; length as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_data(err: bincode::Error) -> io::Error
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+impl ClientMessage
+{
+    pub fn encode(&self) -> Result<Vec<u8>, bincode::Error>
+    {
+        bincode::serialize(self)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, bincode::Error>
+    {
+        bincode::deserialize(bytes)
+    }
+
+    pub fn write_to(&self, writer: impl Write) -> io::Result<()>
+    {
+        write_frame(writer, &self.encode().map_err(invalid_data)?)
+    }
+
+    pub fn read_from(reader: impl Read) -> io::Result<Self>
+    {
+        Self::decode(&read_frame(reader)?).map_err(invalid_data)
+    }
+}
+
+impl ServerMessage
+{
+    pub fn encode(&self) -> Result<Vec<u8>, bincode::Error>
+    {
+        bincode::serialize(self)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, bincode::Error>
+    {
+        bincode::deserialize(bytes)
+    }
+
+    pub fn write_to(&self, writer: impl Write) -> io::Result<()>
+    {
+        write_frame(writer, &self.encode().map_err(invalid_data)?)
+    }
+
+    pub fn read_from(reader: impl Read) -> io::Result<Self>
+    {
+        Self::decode(&read_frame(reader)?).map_err(invalid_data)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn minimap_summary_round_trips()
+    {
+        let message = ServerMessage::MinimapSummary(MinimapSummary {
+            map_radius: 1000.0,
+            snakes: vec![MinimapSnake {
+                id: 1,
+                position: [10.0, -20.0],
+                radius: 14.5,
+                score: 300,
+            }],
+        });
+
+        let bytes = message.encode().unwrap();
+        assert_eq!(ServerMessage::decode(&bytes).unwrap(), message);
+
+        let report = ClientMessage::ReportSnake(SnakeReport {
+            position: [10.0, -20.0],
+            radius: 14.5,
+            score: 300,
+        });
+        assert_eq!(ClientMessage::decode(&report.encode().unwrap()).unwrap(), report);
+    }
+
+    #[test]
+    fn rules_round_trip()
+    {
+        let mut config = GameConfig::default();
+        config.arena.map_radius = 1500.0;
+        config.bots.enabled = false;
+
+        let message = ServerMessage::Rules(Box::new(config));
+        assert_eq!(ServerMessage::decode(&message.encode().unwrap()).unwrap(), message);
+    }
+
+    #[test]
+    fn map_choice_round_trips()
+    {
+        let message = ServerMessage::LoadMap(MapChoice {
+            name: "crossroads.ron".into(),
+            version: slither_common::map::MAP_VERSION,
+        });
+        assert_eq!(ServerMessage::decode(&message.encode().unwrap()).unwrap(), message);
+    }
+
+    #[test]
+    fn accounts_and_leaderboards_round_trip()
+    {
+        let login = ClientMessage::Login(LoginRequest {
+            name: "Player 1".into(),
+            token: Some("0123abcd".into()),
+        });
+        assert_eq!(ClientMessage::decode(&login.encode().unwrap()).unwrap(), login);
+
+        let boards = ServerMessage::Leaderboards(Leaderboards {
+            daily: vec![slither_common::ranking::LeaderboardEntry {
+                name: "Player 1".into(),
+                score: 120,
+            }],
+            ..Default::default()
+        });
+        assert_eq!(ServerMessage::decode(&boards.encode().unwrap()).unwrap(), boards);
+    }
+
+    #[test]
+    fn messages_keep_their_boundaries_on_a_stream()
+    {
+        let mut stream = Vec::new();
+        let first = ClientMessage::FetchLeaderboards;
+        let second = ClientMessage::SubmitResult(GameResult {
+            score: 120,
+            kills: 2,
+            duration: 60.0,
+        });
+        first.write_to(&mut stream).unwrap();
+        second.write_to(&mut stream).unwrap();
+
+        let mut reader = stream.as_slice();
+        assert_eq!(ClientMessage::read_from(&mut reader).unwrap(), first);
+        assert_eq!(ClientMessage::read_from(&mut reader).unwrap(), second);
+        assert!(ClientMessage::read_from(&mut reader).is_err());
+
+        let oversized = (MAX_MESSAGE_LENGTH + 1).to_le_bytes();
+        assert_eq!(
+            ServerMessage::read_from(oversized.as_slice()).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn skins_round_trip()
+    {
+        let join = ClientMessage::Join(JoinRequest {
+            name: "Player 1".into(),
+            skin: 9,
+        });
+        assert_eq!(ClientMessage::decode(&join.encode().unwrap()).unwrap(), join);
+
+        let joined = ServerMessage::SnakeJoined {
+            id: 4,
+            name: "Player 1".into(),
+            skin: 9,
+        };
+        assert_eq!(ServerMessage::decode(&joined.encode().unwrap()).unwrap(), joined);
+    }
+}
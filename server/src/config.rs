@@ -0,0 +1,169 @@
+use std::path::{Path, PathBuf};
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+use slither_common::config::GameConfig;
+use slither_common::map::{ArenaMap, MapError};
+use slither_protocol::messages::MapChoice;
+
+use crate::storage::StorageConfig;
+
+/// Settings of the server itself, as opposed to the game rules it sends to clients.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ServerConfig
+{
+    /// The address clients connect to.
+    pub listen: String,
+    /// The balance settings sent to clients, in the same format as the client's config. Watched for edits.
+    pub rules: PathBuf,
+    /// The folder the maps named by the rules are loaded from, holding the same files the clients ship with.
+    pub maps: PathBuf,
+    /// Where accounts, match results and leaderboards are kept. Switching backends only takes a change here.
+    pub storage: StorageConfig,
+}
+
+impl Default for ServerConfig
+{
+    fn default() -> Self
+    {
+        Self {
+            listen: "0.0.0.0:7777".into(),
+            rules: PathBuf::from("game.ron"),
+            maps: PathBuf::from("../client/assets/maps"),
+            storage: StorageConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ServerConfigError
+{
+    Io(io::Error),
+    Parse(String),
+}
+
+impl fmt::Display for ServerConfigError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            ServerConfigError::Io(err) => write!(f, "server config io error: {err}"),
+            ServerConfigError::Parse(err) => write!(f, "server config syntax error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ServerConfigError {}
+
+impl ServerConfig
+{
+    pub fn from_ron(text: &str) -> Result<Self, ServerConfigError>
+    {
+        ron::from_str(text).map_err(|err| ServerConfigError::Parse(err.to_string()))
+    }
+
+    /// A missing file means the defaults, a broken one is an error.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ServerConfigError>
+    {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::from_ron(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(ServerConfigError::Io(err)),
+        }
+    }
+
+    /// Loads and validates the map the rules name, so clients are only ever sent to a map that works.
+    pub fn choose_map(&self, rules: &GameConfig) -> Result<Option<ChosenMap>, MapError>
+    {
+        let Some(name) = &rules.arena.map else {
+            return Ok(None);
+        };
+
+        let map = ArenaMap::load(self.maps.join(name))?;
+        Ok(Some(ChosenMap {
+            choice: MapChoice {
+                name: name.clone(),
+                version: map.version,
+            },
+            radius: map.shape.radius(),
+        }))
+    }
+}
+
+/// A map the rules name, loaded and validated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChosenMap
+{
+    /// What clients are told to load.
+    pub choice: MapChoice,
+    /// Distance from the origin to the furthest point of the map, which the minimap is scaled to.
+    pub radius: f32,
+}
+
+#[cfg(test)]
+mod tests
+{
+    use slither_common::map::MAP_VERSION;
+
+    use super::*;
+
+    #[test]
+    fn picks_the_storage_backend()
+    {
+        let http = ServerConfig::from_ron(r#"(storage: Http("http://localhost:8080/api"))"#).unwrap();
+        assert_eq!(http.storage, StorageConfig::Http("http://localhost:8080/api".into()));
+
+        let file = ServerConfig::from_ron(r#"(storage: File("saves/accounts.ron"))"#).unwrap();
+        assert_eq!(file.storage, StorageConfig::File(PathBuf::from("saves/accounts.ron")));
+
+        assert_eq!(ServerConfig::from_ron("()").unwrap(), ServerConfig::default());
+        assert!(ServerConfig::from_ron("(storage: Postgres)").is_err());
+    }
+
+    #[test]
+    fn shipped_config_is_valid()
+    {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let config = ServerConfig::load(directory.join("server.ron")).unwrap();
+        assert_eq!(config, ServerConfig::default());
+
+        // Every map the clients ship with can be named by the rules
+        let mut rules = GameConfig::load(directory.join(&config.rules)).unwrap();
+        let config = ServerConfig {
+            maps: directory.join(&config.maps),
+            ..config
+        };
+        let mut maps = 0;
+        for entry in std::fs::read_dir(&config.maps).unwrap() {
+            rules.arena.map = Some(entry.unwrap().file_name().to_string_lossy().into_owned());
+            assert!(config.choose_map(&rules).unwrap().is_some());
+            maps += 1;
+        }
+        assert!(maps > 0);
+    }
+
+    #[test]
+    fn maps_named_by_the_rules_must_load()
+    {
+        let config = ServerConfig {
+            maps: std::env::temp_dir().join(format!("slither-server-maps-{}", std::process::id())),
+            ..ServerConfig::default()
+        };
+        ArenaMap::circle(1200.0).save(config.maps.join("round.ron")).unwrap();
+
+        let mut rules = GameConfig::default();
+        assert_eq!(config.choose_map(&rules).unwrap(), None);
+
+        rules.arena.map = Some("round.ron".into());
+        let map = config.choose_map(&rules).unwrap().unwrap();
+        assert_eq!(map.choice.name, "round.ron");
+        assert_eq!(map.choice.version, MAP_VERSION);
+        assert_eq!(map.radius, 1200.0);
+
+        rules.arena.map = Some("missing.ron".into());
+        assert!(config.choose_map(&rules).is_err());
+
+        std::fs::remove_dir_all(&config.maps).unwrap();
+    }
+}
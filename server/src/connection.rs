@@ -0,0 +1,625 @@
+use std::cmp::Reverse;
+use std::io::{self, BufReader};
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use slither_common::config::GameConfig;
+use slither_protocol::messages::{ClientMessage, JoinRequest, MinimapSnake, MinimapSummary, ServerMessage, SnakeReport};
+
+use crate::config::ChosenMap;
+use crate::handlers::{self, Session};
+use crate::storage::Storage;
+
+/// Everything the connections share.
+pub struct ServerState
+{
+    pub storage: Box<dyn Storage>,
+    /// The validated rules matches are played with.
+    pub rules: Mutex<GameConfig>,
+    /// The map the rules name, already loaded and validated. Always locked after the rules.
+    pub map: Mutex<Option<ChosenMap>>,
+    /// Every connected client, locked after the rules and map. Replies go through each client's writer lock so
+    /// messages never interleave on a stream.
+    clients: Mutex<Vec<Client>>,
+    next_id: AtomicU64,
+}
+
+type ClientWriter = Arc<Mutex<TcpStream>>;
+
+struct Client
+{
+    id: u64,
+    writer: ClientWriter,
+    /// The client's snake as it last reported it, and when.
+    report: Option<(SnakeReport, Instant)>,
+    /// The name and skin the client last joined with.
+    joined: Option<JoinRequest>,
+}
+
+/// How long a client may take to accept a message before it counts as gone.
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// A snake that hasn't been reported for this long has died or its client has stopped playing.
+const REPORT_MAX_AGE: Duration = Duration::from_secs(3);
+/// How many snakes each minimap summary lists.
+const SUMMARY_SNAKES: usize = 10;
+
+impl ServerState
+{
+    pub fn new(storage: Box<dyn Storage>, rules: GameConfig, map: Option<ChosenMap>) -> Self
+    {
+        Self {
+            storage,
+            rules: Mutex::new(rules),
+            map: Mutex::new(map),
+            clients: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Switches to new rules and sends them to every connected client, followed by the map when it changed.
+    /// Returns how many clients were reached. A client that can't be written to is disconnected.
+    pub fn update_rules(&self, rules: GameConfig, map: Option<ChosenMap>) -> usize
+    {
+        let mut messages = vec![ServerMessage::Rules(Box::new(rules.clone()))];
+        let deliveries = {
+            let mut current_rules = lock(&self.rules);
+            let mut current_map = lock(&self.map);
+            if let Some(map) = map
+                .as_ref()
+                .filter(|&map| current_map.as_ref().map(|current| &current.choice) != Some(&map.choice))
+            {
+                messages.push(ServerMessage::LoadMap(map.choice.clone()));
+            }
+            *current_rules = rules;
+            *current_map = map;
+            lock(&self.clients)
+                .iter()
+                .map(|client| (client.id, client.writer.clone(), messages.clone()))
+                .collect()
+        };
+        self.deliver(deliveries)
+    }
+
+    /// Sends every connected client the largest snakes the other clients reported, for its minimap. Returns how
+    /// many clients were reached. A client that can't be written to is disconnected.
+    pub fn send_minimap_summaries(&self, now: Instant) -> usize
+    {
+        let map_radius = {
+            let rules = lock(&self.rules);
+            let map = lock(&self.map);
+            map.as_ref().map_or(rules.arena.map_radius, |map| map.radius)
+        };
+
+        let deliveries = {
+            let clients = lock(&self.clients);
+            let mut snakes: Vec<MinimapSnake> = clients
+                .iter()
+                .filter_map(|client| {
+                    let (report, at) = client.report.as_ref()?;
+                    (now.saturating_duration_since(*at) <= REPORT_MAX_AGE).then_some(MinimapSnake {
+                        id: client.id,
+                        position: report.position,
+                        radius: report.radius,
+                        score: report.score,
+                    })
+                })
+                .collect();
+            snakes.sort_by_key(|snake| Reverse(snake.score));
+
+            clients
+                .iter()
+                .map(|client| {
+                    let summary = MinimapSummary {
+                        map_radius,
+                        snakes: snakes
+                            .iter()
+                            .filter(|snake| snake.id != client.id)
+                            .take(SUMMARY_SNAKES)
+                            .cloned()
+                            .collect(),
+                    };
+                    (client.id, client.writer.clone(), vec![ServerMessage::MinimapSummary(summary)])
+                })
+                .collect()
+        };
+        self.deliver(deliveries)
+    }
+
+    /// Writes each client its messages without holding anything shared, so a slow client only holds up this
+    /// delivery and not new connections or the next one. Clients that fail are disconnected, the rest are counted.
+    fn deliver(&self, deliveries: Vec<(u64, ClientWriter, Vec<ServerMessage>)>) -> usize
+    {
+        let (reached, failed): (Vec<_>, Vec<_>) = deliveries.into_iter().partition(|(_, writer, messages)| {
+            let mut stream = lock(writer);
+            messages.iter().all(|message| message.write_to(&mut *stream).is_ok())
+        });
+
+        for (_, writer, _) in &failed {
+            // A message cut off halfway leaves the stream unusable, closing it ends the client's connection
+            let _ = lock(writer).shutdown(Shutdown::Both);
+        }
+        lock(&self.clients).retain(|client| !failed.iter().any(|(id, _, _)| *id == client.id));
+        reached.len()
+    }
+
+    /// Tells the other clients the name and skin the client joined with, and the client those of everyone who
+    /// joined before it.
+    fn join(&self, id: u64, request: JoinRequest)
+    {
+        let deliveries = {
+            let mut clients = lock(&self.clients);
+            let Some(joiner) = clients.iter_mut().find(|client| client.id == id) else {
+                return;
+            };
+            joiner.joined = Some(request.clone());
+            let joiner_writer = joiner.writer.clone();
+
+            let announcement = ServerMessage::SnakeJoined {
+                id,
+                name: request.name,
+                skin: request.skin,
+            };
+            let others = clients.iter().filter(|client| client.id != id);
+            let earlier = others
+                .clone()
+                .filter_map(|client| {
+                    let joined = client.joined.clone()?;
+                    Some(ServerMessage::SnakeJoined {
+                        id: client.id,
+                        name: joined.name,
+                        skin: joined.skin,
+                    })
+                })
+                .collect();
+
+            let mut deliveries: Vec<_> = others
+                .map(|client| (client.id, client.writer.clone(), vec![announcement.clone()]))
+                .collect();
+            deliveries.push((id, joiner_writer, earlier));
+            deliveries
+        };
+        self.deliver(deliveries);
+    }
+
+    fn report_snake(&self, id: u64, report: SnakeReport, now: Instant)
+    {
+        if let Some(client) = lock(&self.clients).iter_mut().find(|client| client.id == id) {
+            client.report = Some((report, now));
+        }
+    }
+}
+
+/// Sends the client the current rules and map, then answers its messages until it disconnects. A clean disconnect is `Ok`,
+/// anything else is left to the caller to report.
+pub fn serve_client(stream: TcpStream, state: &ServerState) -> io::Result<()>
+{
+    stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT))?;
+    let reader = BufReader::new(stream.try_clone()?);
+    let writer = Arc::new(Mutex::new(stream));
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+
+    let result = welcome(id, &writer, state).and_then(|()| answer_messages(id, reader, &writer, state));
+    lock(&state.clients).retain(|client| client.id != id);
+    result
+}
+
+/// Registers the client for updates and sends it the current rules and map. Its stream stays locked from
+/// registering until they are written, so an update in between waits and arrives after them rather than being
+/// missed or overtaken.
+fn welcome(id: u64, writer: &ClientWriter, state: &ServerState) -> io::Result<()>
+{
+    let mut stream = lock(writer);
+    let (rules, map) = {
+        let rules = lock(&state.rules);
+        let map = lock(&state.map);
+        lock(&state.clients).push(Client {
+            id,
+            writer: writer.clone(),
+            report: None,
+            joined: None,
+        });
+        (rules.clone(), map.clone())
+    };
+
+    ServerMessage::Rules(Box::new(rules)).write_to(&mut *stream)?;
+    if let Some(map) = map {
+        ServerMessage::LoadMap(map.choice).write_to(&mut *stream)?;
+    }
+    Ok(())
+}
+
+fn answer_messages(
+    id: u64,
+    mut reader: BufReader<TcpStream>,
+    writer: &Mutex<TcpStream>,
+    state: &ServerState,
+) -> io::Result<()>
+{
+    let mut session = Session::default();
+
+    loop {
+        let message = match ClientMessage::read_from(&mut reader) {
+            Ok(message) => message,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        let reply = match message {
+            ClientMessage::Join(request) => {
+                state.join(id, request);
+                None
+            }
+            ClientMessage::ReportSnake(report) => {
+                state.report_snake(id, report, Instant::now());
+                None
+            }
+            message => handlers::respond(state.storage.as_ref(), &mut session, &message, unix_time()),
+        };
+        if let Some(reply) = reply {
+            reply.write_to(&mut *lock(writer))?;
+        }
+    }
+}
+
+/// A connection that panicked halfway through can't have left anything half written, as the rules and map are
+/// replaced whole and messages are written whole, so a poisoned lock is still safe to use.
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T>
+{
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn unix_time() -> u64
+{
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc;
+
+    use slither_common::ranking::Leaderboards;
+    use slither_protocol::messages::{LoginRequest, MapChoice};
+
+    use super::*;
+    use crate::storage::{Account, MatchResult, MemoryStorage, StoreError};
+
+    /// A backend whose logins hang until the test lets them through, like a remote one that stopped answering.
+    struct StalledLogins
+    {
+        release: Mutex<mpsc::Receiver<()>>,
+    }
+
+    impl Storage for StalledLogins
+    {
+        fn login(&self, _name: &str, _token: Option<&str>) -> Result<Account, StoreError>
+        {
+            let _ = lock(&self.release).recv();
+            Err(StoreError::UnknownAccount)
+        }
+
+        fn record_match(&self, _token: &str, _result: MatchResult) -> Result<(), StoreError>
+        {
+            Ok(())
+        }
+
+        fn leaderboards(&self, _now: u64) -> Result<Leaderboards, StoreError>
+        {
+            Ok(Leaderboards::default())
+        }
+    }
+
+    #[test]
+    fn a_stalled_request_does_not_hold_up_other_clients()
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let (release, stalled) = mpsc::channel();
+        let state = Arc::new(ServerState::new(
+            Box::new(StalledLogins {
+                release: Mutex::new(stalled),
+            }),
+            GameConfig::default(),
+            None,
+        ));
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let state = state.clone();
+                std::thread::spawn(move || serve_client(stream.unwrap(), &state));
+            }
+        });
+
+        let mut waiting = TcpStream::connect(address).unwrap();
+        ServerMessage::read_from(&mut waiting).unwrap();
+        ClientMessage::Login(LoginRequest {
+            name: "Viper".into(),
+            token: None,
+        })
+        .write_to(&mut waiting)
+        .unwrap();
+
+        let mut other = TcpStream::connect(address).unwrap();
+        ServerMessage::read_from(&mut other).unwrap();
+        ClientMessage::FetchLeaderboards.write_to(&mut other).unwrap();
+        assert!(matches!(
+            ServerMessage::read_from(&mut other).unwrap(),
+            ServerMessage::Leaderboards(_)
+        ));
+
+        release.send(()).unwrap();
+        assert!(matches!(
+            ServerMessage::read_from(&mut waiting).unwrap(),
+            ServerMessage::LoginRejected(_)
+        ));
+    }
+
+    #[test]
+    fn clients_sign_in_and_fetch_leaderboards_over_tcp()
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let state = Arc::new(ServerState::new(
+            Box::new(Mutex::new(MemoryStorage::default())),
+            GameConfig::default(),
+            None,
+        ));
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            serve_client(stream, &state)
+        });
+
+        let mut client = TcpStream::connect(address).unwrap();
+        assert!(matches!(
+            ServerMessage::read_from(&mut client).unwrap(),
+            ServerMessage::Rules(_)
+        ));
+        ClientMessage::Login(LoginRequest {
+            name: "Viper".into(),
+            token: None,
+        })
+        .write_to(&mut client)
+        .unwrap();
+        ClientMessage::FetchLeaderboards.write_to(&mut client).unwrap();
+
+        assert!(matches!(
+            ServerMessage::read_from(&mut client).unwrap(),
+            ServerMessage::LoggedIn { .. }
+        ));
+        assert!(matches!(
+            ServerMessage::read_from(&mut client).unwrap(),
+            ServerMessage::Leaderboards(_)
+        ));
+
+        drop(client);
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn rule_changes_reach_every_connected_client()
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let state = Arc::new(ServerState::new(
+            Box::new(Mutex::new(MemoryStorage::default())),
+            GameConfig::default(),
+            None,
+        ));
+        let server_state = state.clone();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            serve_client(stream, &server_state)
+        });
+
+        let mut client = TcpStream::connect(address).unwrap();
+        assert_eq!(
+            ServerMessage::read_from(&mut client).unwrap(),
+            ServerMessage::Rules(Box::default())
+        );
+
+        let mut rules = GameConfig::default();
+        rules.arena.map_radius = 1500.0;
+        assert_eq!(state.update_rules(rules.clone(), None), 1);
+        assert_eq!(
+            ServerMessage::read_from(&mut client).unwrap(),
+            ServerMessage::Rules(Box::new(rules.clone()))
+        );
+
+        let map = ChosenMap {
+            choice: MapChoice {
+                name: "crossroads.ron".into(),
+                version: slither_common::map::MAP_VERSION,
+            },
+            radius: 1600.0,
+        };
+        rules.arena.map = Some(map.choice.name.clone());
+        assert_eq!(state.update_rules(rules.clone(), Some(map.clone())), 1);
+        assert_eq!(
+            ServerMessage::read_from(&mut client).unwrap(),
+            ServerMessage::Rules(Box::new(rules.clone()))
+        );
+        assert_eq!(
+            ServerMessage::read_from(&mut client).unwrap(),
+            ServerMessage::LoadMap(map.choice.clone())
+        );
+
+        // Only a map switch is announced, the rules changing around the same map are not
+        rules.arena.map_radius = 1200.0;
+        assert_eq!(state.update_rules(rules.clone(), Some(map)), 1);
+        assert_eq!(
+            ServerMessage::read_from(&mut client).unwrap(),
+            ServerMessage::Rules(Box::new(rules))
+        );
+        ClientMessage::FetchLeaderboards.write_to(&mut client).unwrap();
+        assert!(matches!(
+            ServerMessage::read_from(&mut client).unwrap(),
+            ServerMessage::Leaderboards(_)
+        ));
+
+        drop(client);
+        server.join().unwrap().unwrap();
+        assert_eq!(state.update_rules(GameConfig::default(), None), 0);
+    }
+
+    #[test]
+    fn each_client_sees_the_other_players_on_its_minimap()
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let state = Arc::new(ServerState::new(
+            Box::new(Mutex::new(MemoryStorage::default())),
+            GameConfig::default(),
+            None,
+        ));
+        let server_state = state.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(3) {
+                let state = server_state.clone();
+                std::thread::spawn(move || serve_client(stream.unwrap(), &state));
+            }
+        });
+
+        let mut clients: Vec<TcpStream> = (0..3).map(|_| TcpStream::connect(address).unwrap()).collect();
+        for (score, client) in [300, 100].into_iter().zip(&mut clients) {
+            ServerMessage::read_from(&mut *client).unwrap();
+            ClientMessage::ReportSnake(SnakeReport {
+                position: [score as f32, 0.0],
+                radius: 12.0,
+                score,
+            })
+            .write_to(&mut *client)
+            .unwrap();
+            // Reports get no reply, the leaderboards answer shows the report before it was handled
+            ClientMessage::FetchLeaderboards.write_to(&mut *client).unwrap();
+            ServerMessage::read_from(&mut *client).unwrap();
+        }
+        ServerMessage::read_from(&mut clients[2]).unwrap();
+
+        let summary = |client: &mut TcpStream| match ServerMessage::read_from(client).unwrap() {
+            ServerMessage::MinimapSummary(summary) => summary,
+            other => panic!("expected a minimap summary, got {other:?}"),
+        };
+        let scores = |summary: &MinimapSummary| summary.snakes.iter().map(|snake| snake.score).collect::<Vec<_>>();
+
+        assert_eq!(state.send_minimap_summaries(Instant::now()), 3);
+        let first = summary(&mut clients[0]);
+        assert_eq!(first.map_radius, GameConfig::default().arena.map_radius);
+        assert_eq!(scores(&first), [100]);
+        assert_eq!(scores(&summary(&mut clients[1])), [300]);
+        let watcher = summary(&mut clients[2]);
+        assert_eq!(scores(&watcher), [300, 100]);
+        assert_eq!(watcher.snakes[0].position, [300.0, 0.0]);
+
+        // Snakes that stopped being reported have left the arena
+        assert_eq!(state.send_minimap_summaries(Instant::now() + REPORT_MAX_AGE * 2), 3);
+        assert!(summary(&mut clients[2]).snakes.is_empty());
+    }
+
+    #[test]
+    fn joining_players_and_their_skins_are_announced_to_everyone()
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let state = Arc::new(ServerState::new(
+            Box::new(Mutex::new(MemoryStorage::default())),
+            GameConfig::default(),
+            None,
+        ));
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let state = state.clone();
+                std::thread::spawn(move || serve_client(stream.unwrap(), &state));
+            }
+        });
+
+        let mut first = TcpStream::connect(address).unwrap();
+        ServerMessage::read_from(&mut first).unwrap();
+        let mut second = TcpStream::connect(address).unwrap();
+        ServerMessage::read_from(&mut second).unwrap();
+
+        ClientMessage::Join(JoinRequest {
+            name: "Viper".into(),
+            skin: 9,
+        })
+        .write_to(&mut first)
+        .unwrap();
+        let ServerMessage::SnakeJoined {
+            id: first_id,
+            name,
+            skin,
+        } = ServerMessage::read_from(&mut second).unwrap()
+        else {
+            panic!("expected the first player to be announced");
+        };
+        assert_eq!((name.as_str(), skin), ("Viper", 9));
+
+        // A late joiner hears about everyone already in the arena
+        ClientMessage::Join(JoinRequest {
+            name: "Cobra".into(),
+            skin: 2,
+        })
+        .write_to(&mut second)
+        .unwrap();
+        assert_eq!(
+            ServerMessage::read_from(&mut second).unwrap(),
+            ServerMessage::SnakeJoined {
+                id: first_id,
+                name: "Viper".into(),
+                skin: 9,
+            }
+        );
+        let ServerMessage::SnakeJoined { id, name, skin } = ServerMessage::read_from(&mut first).unwrap() else {
+            panic!("expected the second player to be announced");
+        };
+        assert_ne!(id, first_id);
+        assert_eq!((name.as_str(), skin), ("Cobra", 2));
+    }
+
+    #[test]
+    fn a_client_that_stops_reading_does_not_hold_up_new_connections()
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let state = Arc::new(ServerState::new(
+            Box::new(Mutex::new(MemoryStorage::default())),
+            GameConfig::default(),
+            None,
+        ));
+        let server_state = state.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let state = server_state.clone();
+                std::thread::spawn(move || serve_client(stream.unwrap(), &state));
+            }
+        });
+
+        // Updates pile up unread until writing to this client blocks
+        let _stalled = TcpStream::connect(address).unwrap();
+        let stop = Arc::new(AtomicBool::new(false));
+        let updates = {
+            let (state, stop) = (state.clone(), stop.clone());
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    state.update_rules(GameConfig::default(), None);
+                }
+            })
+        };
+        std::thread::sleep(Duration::from_millis(300));
+
+        let mut newcomer = TcpStream::connect(address).unwrap();
+        newcomer.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        assert!(matches!(
+            ServerMessage::read_from(&mut newcomer).unwrap(),
+            ServerMessage::Rules(_)
+        ));
+
+        stop.store(true, Ordering::Relaxed);
+        drop(newcomer);
+        updates.join().unwrap();
+    }
+}
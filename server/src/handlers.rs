@@ -0,0 +1,127 @@
+use slither_protocol::messages::{ClientMessage, ServerMessage};
+
+use crate::storage::{MatchResult, Storage};
+
+/// What the server remembers about one connected client between its messages.
+#[derive(Default, Debug)]
+pub struct Session
+{
+    /// The token of the account the client signed in to, if it did.
+    pub token: Option<String>,
+}
+
+/// Answers the client messages that only need the storage backend. `None` for messages the connection
+/// handles itself and for requests that need no answer.
+pub fn respond(storage: &dyn Storage, session: &mut Session, message: &ClientMessage, now: u64) -> Option<ServerMessage>
+{
+    match message {
+        ClientMessage::Join(_) | ClientMessage::ReportSnake(_) => None,
+        ClientMessage::Login(request) => Some(match storage.login(&request.name, request.token.as_deref()) {
+            Ok(account) => {
+                session.token = Some(account.token.clone());
+                ServerMessage::LoggedIn {
+                    name: account.name,
+                    token: account.token,
+                }
+            }
+            Err(err) => ServerMessage::LoginRejected(err.to_string()),
+        }),
+        ClientMessage::FetchLeaderboards => Some(match storage.leaderboards(now) {
+            Ok(boards) => ServerMessage::Leaderboards(boards),
+            Err(err) => ServerMessage::RequestFailed(format!("could not fetch the leaderboards: {err}")),
+        }),
+        ClientMessage::SubmitResult(game) => {
+            let Some(token) = &session.token else {
+                return Some(ServerMessage::RequestFailed("sign in before submitting results".into()));
+            };
+            let result = MatchResult {
+                score: game.score,
+                kills: game.kills,
+                duration: game.duration,
+                ended_at: now,
+            };
+            storage
+                .record_match(token, result)
+                .err()
+                .map(|err| ServerMessage::RequestFailed(format!("could not record the game: {err}")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::sync::Mutex;
+
+    use slither_common::ranking::LeaderboardEntry;
+    use slither_protocol::messages::{GameResult, LoginRequest};
+
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn logins_hand_out_tokens_that_sign_back_in()
+    {
+        let storage = Mutex::new(MemoryStorage::default());
+        let mut session = Session::default();
+        let login = |token: Option<String>| {
+            ClientMessage::Login(LoginRequest {
+                name: "Viper".into(),
+                token,
+            })
+        };
+
+        let Some(ServerMessage::LoggedIn { token, .. }) = respond(&storage, &mut session, &login(None), 0) else {
+            panic!("a new name should get an account");
+        };
+        assert!(matches!(
+            respond(&storage, &mut session, &login(Some(token)), 0),
+            Some(ServerMessage::LoggedIn { .. })
+        ));
+        assert!(matches!(
+            respond(&storage, &mut session, &login(None), 0),
+            Some(ServerMessage::LoginRejected(_))
+        ));
+        assert_eq!(
+            respond(&storage, &mut session, &ClientMessage::FetchLeaderboards, 0),
+            Some(ServerMessage::Leaderboards(Default::default()))
+        );
+    }
+
+    #[test]
+    fn results_count_for_the_signed_in_account()
+    {
+        let storage = Mutex::new(MemoryStorage::default());
+        let mut session = Session::default();
+        let submit = ClientMessage::SubmitResult(GameResult {
+            score: 120,
+            kills: 2,
+            duration: 60.0,
+        });
+
+        assert!(matches!(
+            respond(&storage, &mut session, &submit, 0),
+            Some(ServerMessage::RequestFailed(_))
+        ));
+
+        let login = ClientMessage::Login(LoginRequest {
+            name: "Viper".into(),
+            token: None,
+        });
+        respond(&storage, &mut session, &login, 0);
+        assert_eq!(respond(&storage, &mut session, &submit, 0), None);
+
+        let Some(ServerMessage::Leaderboards(boards)) =
+            respond(&storage, &mut session, &ClientMessage::FetchLeaderboards, 0)
+        else {
+            panic!("the leaderboards should be sent back");
+        };
+        assert_eq!(
+            boards.daily,
+            [LeaderboardEntry {
+                name: "Viper".into(),
+                score: 120
+            }]
+        );
+    }
+}
@@ -0,0 +1,4 @@
+pub mod config;
+pub mod connection;
+pub mod handlers;
+pub mod storage;
@@ -1,3 +1,111 @@
-fn main() {
-    println!("Hello, world!");
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use slither_common::config::{ConfigWatcher, GameConfig};
+use slither_wars_server::config::ServerConfig;
+use slither_wars_server::connection::{serve_client, ServerState};
+
+const CONFIG_PATH: &str = "server.ron";
+const RULES_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Matches the rate clients report their snakes at, the minimap doesn't need more.
+const MINIMAP_INTERVAL: Duration = Duration::from_millis(500);
+
+fn main()
+{
+    let args: Vec<String> = std::env::args().collect();
+    let config_path = args
+        .iter()
+        .position(|arg| arg == "--config")
+        .and_then(|index| args.get(index + 1))
+        .map_or(CONFIG_PATH, String::as_str);
+
+    let config = match ServerConfig::load(config_path) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to load the server config {config_path}: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    // Refuse to start on rules the clients would reject, rather than finding out once players connect
+    let rules = match GameConfig::load(&config.rules) {
+        Ok(rules) => rules,
+        Err(err) => {
+            eprintln!("Failed to load the game rules {}: {err}", config.rules.display());
+            std::process::exit(1);
+        }
+    };
+
+    let map = match config.choose_map(&rules) {
+        Ok(map) => map,
+        Err(err) => {
+            eprintln!("Failed to load the map the game rules name: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    let state = match config.storage.open() {
+        Ok(storage) => {
+            println!("Opened {:?} storage", config.storage);
+            Arc::new(ServerState::new(storage, rules, map))
+        }
+        Err(err) => {
+            eprintln!("Failed to open {:?} storage: {err}", config.storage);
+            std::process::exit(1);
+        }
+    };
+
+    let listener = match TcpListener::bind(&config.listen) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("Failed to listen on {}: {err}", config.listen);
+            std::process::exit(1);
+        }
+    };
+    println!("Listening on {}", config.listen);
+
+    let watched_state = state.clone();
+    let mut watcher = ConfigWatcher::new(&config.rules);
+    let path = config.rules.display().to_string();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(RULES_POLL_INTERVAL);
+        match watcher.poll() {
+            Some(Ok(rules)) => match config.choose_map(&rules) {
+                Ok(map) => {
+                    let reached = watched_state.update_rules(rules, map);
+                    println!("Reloaded {path} and sent it to {reached} clients");
+                }
+                Err(err) => eprintln!("Kept the current rules, the map {path} names failed to load: {err}"),
+            },
+            Some(Err(err)) => eprintln!("Kept the current rules, {path} is invalid: {err}"),
+            None => {}
+        }
+    });
+
+    let minimap_state = state.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(MINIMAP_INTERVAL);
+        minimap_state.send_minimap_summaries(Instant::now());
+    });
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Failed to accept a connection: {err}");
+                continue;
+            }
+        };
+
+        let state = state.clone();
+        std::thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map_or_else(|_| "unknown".to_string(), |address| address.to_string());
+            if let Err(err) = serve_client(stream, &state) {
+                eprintln!("Connection to {peer} failed: {err}");
+            }
+        });
+    }
 }
@@ -0,0 +1,148 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use slither_common::ranking::Leaderboards;
+
+use super::memory::STORE_VERSION;
+use super::{lock, Account, MatchResult, MemoryStorage, Storage, StoreError};
+
+/// Keeps everything in a RON file next to the server, written after every change.
+pub struct FileStorage
+{
+    path: PathBuf,
+    /// Held across the write as well, so changes reach the file in the order they were made.
+    data: Mutex<MemoryStorage>,
+}
+
+impl FileStorage
+{
+    /// Opens the store at `path`, starting an empty one if the file does not exist yet.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, StoreError>
+    {
+        let path = path.into();
+        let data = match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let data: MemoryStorage = ron::from_str(&text).map_err(|err| StoreError::Parse(err.to_string()))?;
+                if data.version != STORE_VERSION {
+                    return Err(StoreError::UnsupportedVersion(data.version));
+                }
+                data
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => MemoryStorage::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            path,
+            data: Mutex::new(data),
+        })
+    }
+
+    pub fn path(&self) -> &Path
+    {
+        &self.path
+    }
+
+    /// Makes a change to a copy of the store and only keeps it once it is saved, so a failed write never leaves
+    /// the server holding accounts or scores the file doesn't.
+    fn change<T>(&self, change: impl FnOnce(&mut MemoryStorage) -> Result<T, StoreError>) -> Result<T, StoreError>
+    {
+        let mut current = lock(&self.data);
+        let mut data = current.clone();
+        let outcome = change(&mut data)?;
+        self.save(&data)?;
+        *current = data;
+        Ok(outcome)
+    }
+
+    /// Writes to a temporary file first and moves it over the old one, so a crash mid-write keeps the last
+    /// good store.
+    fn save(&self, data: &MemoryStorage) -> Result<(), StoreError>
+    {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = ron::ser::to_string_pretty(data, ron::ser::PrettyConfig::default())
+            .map_err(|err| StoreError::Serialize(err.to_string()))?;
+        let temporary = self.path.with_extension("tmp");
+        std::fs::write(&temporary, text)?;
+        std::fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Storage for FileStorage
+{
+    fn login(&self, name: &str, token: Option<&str>) -> Result<Account, StoreError>
+    {
+        // Signing back in changes nothing, only new accounts need saving
+        if token.is_some() {
+            return lock(&self.data).login(name, token);
+        }
+        self.change(|data| data.login(name, None))
+    }
+
+    fn record_match(&self, token: &str, result: MatchResult) -> Result<(), StoreError>
+    {
+        self.change(|data| data.record_match(token, result))
+    }
+
+    fn leaderboards(&self, now: u64) -> Result<Leaderboards, StoreError>
+    {
+        lock(&self.data).leaderboards(now)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn accounts_and_results_survive_a_restart()
+    {
+        let path = std::env::temp_dir().join(format!("slither-store-{}.ron", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let storage = FileStorage::open(&path).unwrap();
+        let account = storage.login("Viper", None).unwrap();
+        let result = MatchResult {
+            score: 120,
+            kills: 2,
+            duration: 60.0,
+            ended_at: 0,
+        };
+        storage.record_match(&account.token, result).unwrap();
+
+        let reopened = FileStorage::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(reopened.login("", Some(&account.token)).unwrap().best_score, 120);
+        assert_eq!(reopened.leaderboards(0).unwrap().all_time[0].score, 120);
+    }
+
+    #[test]
+    fn failed_saves_change_nothing()
+    {
+        let path = std::env::temp_dir().join(format!("slither-store-unsaved-{}.ron", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let storage = FileStorage::open(&path).unwrap();
+        let account = storage.login("Viper", None).unwrap();
+
+        // A directory where the temporary file goes makes every save fail
+        let blocker = path.with_extension("tmp");
+        std::fs::create_dir_all(&blocker).unwrap();
+        let result = MatchResult {
+            score: 120,
+            kills: 2,
+            duration: 60.0,
+            ended_at: 0,
+        };
+        assert!(storage.login("Cobra", None).is_err());
+        assert!(storage.record_match(&account.token, result).is_err());
+        std::fs::remove_dir(&blocker).unwrap();
+
+        assert!(storage.login("Cobra", None).is_ok());
+        assert_eq!(storage.login("", Some(&account.token)).unwrap().games_played, 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,361 @@
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use slither_common::ranking::Leaderboards;
+
+use super::{Account, MatchResult, Storage, StoreError};
+
+/// How long a request may take before the backend counts as unreachable.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Talks to a remote backend over plain HTTP with JSON bodies. Any service can stand behind it as long as it
+/// answers these routes, relative to the base url:
+///
+/// - `POST /login` with `{"name", "token"}` answers with the account. 401 for an unknown token, 409 for a taken
+///   name and 422 for an invalid one.
+/// - `POST /matches` with `{"token", "result"}`. 401 for an unknown token.
+/// - `GET /leaderboards?now=<unix seconds>` answers with the daily, weekly and all-time lists.
+pub struct HttpStorage
+{
+    /// `host:port` to connect to.
+    address: String,
+    host: String,
+    /// Path every route is appended to, without a trailing slash.
+    base_path: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LoginBody
+{
+    pub name: String,
+    pub token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MatchBody
+{
+    pub token: String,
+    pub result: MatchResult,
+}
+
+struct Response
+{
+    status: u16,
+    body: Vec<u8>,
+}
+
+impl HttpStorage
+{
+    /// Accepts urls like `http://localhost:8080/api`. Only plain http is supported.
+    pub fn new(url: &str) -> Result<Self, StoreError>
+    {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| StoreError::Http(format!("{url}: only http:// urls are supported")))?;
+        let (host, base_path) = rest.split_once('/').map_or((rest, ""), |(host, path)| (host, path));
+        if host.is_empty() {
+            return Err(StoreError::Http(format!("{url}: missing host")));
+        }
+
+        let address = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{host}:80")
+        };
+        let base_path = base_path.trim_end_matches('/');
+        Ok(Self {
+            address,
+            host: host.to_string(),
+            base_path: if base_path.is_empty() {
+                String::new()
+            } else {
+                format!("/{base_path}")
+            },
+        })
+    }
+
+    /// Tries every address the host resolves to, each for at most [`HTTP_TIMEOUT`], so an unreachable backend
+    /// fails its requests instead of hanging them.
+    fn connect(&self) -> std::io::Result<TcpStream>
+    {
+        let mut last_error = None;
+        for address in self.address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, HTTP_TIMEOUT) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_error = Some(err),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no address to connect to")))
+    }
+
+    fn request(&self, method: &str, route: &str, body: Option<Vec<u8>>) -> Result<Response, StoreError>
+    {
+        let http_error = |err: std::io::Error| StoreError::Http(format!("{}: {err}", self.address));
+        let mut stream = self.connect().map_err(http_error)?;
+        stream.set_read_timeout(Some(HTTP_TIMEOUT)).map_err(http_error)?;
+        stream.set_write_timeout(Some(HTTP_TIMEOUT)).map_err(http_error)?;
+
+        let body = body.unwrap_or_default();
+        let head = format!(
+            "{method} {}{route} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: \
+             {}\r\nConnection: close\r\n\r\n",
+            self.base_path,
+            self.host,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).map_err(http_error)?;
+        stream.write_all(&body).map_err(http_error)?;
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).map_err(http_error)?;
+        parse_response(&raw)
+    }
+
+    fn send(&self, method: &str, route: &str, body: Option<&impl Serialize>) -> Result<Response, StoreError>
+    {
+        let body = body
+            .map(serde_json::to_vec)
+            .transpose()
+            .map_err(|err| StoreError::Http(err.to_string()))?;
+        self.request(method, route, body)
+    }
+}
+
+impl Response
+{
+    /// Turns any status outside 2xx into an error that names the route.
+    fn check(&self, route: &str) -> Result<(), StoreError>
+    {
+        if (200..300).contains(&self.status) {
+            Ok(())
+        } else {
+            Err(StoreError::Http(format!(
+                "{route} answered {}: {}",
+                self.status,
+                String::from_utf8_lossy(&self.body)
+            )))
+        }
+    }
+
+    fn json<T: DeserializeOwned>(&self, route: &str) -> Result<T, StoreError>
+    {
+        self.check(route)?;
+        serde_json::from_slice(&self.body).map_err(|err| StoreError::Http(format!("{route}: {err}")))
+    }
+}
+
+/// Splits a response into its status and body, undoing chunked encoding if the server used it.
+fn parse_response(raw: &[u8]) -> Result<Response, StoreError>
+{
+    let malformed = || StoreError::Http("malformed response".to_string());
+    let split = raw
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let head = String::from_utf8_lossy(&raw[..split]);
+    let mut body = raw[split + 4..].to_vec();
+
+    let mut lines = head.lines();
+    let status = lines
+        .next()
+        .and_then(|status_line| status_line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(malformed)?;
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding") && value.trim().eq_ignore_ascii_case("chunked")
+        })
+    });
+
+    if chunked {
+        let mut decoded = Vec::new();
+        let mut rest = body.as_slice();
+        loop {
+            let line_end = rest.windows(2).position(|window| window == b"\r\n").ok_or_else(malformed)?;
+            let size_text = String::from_utf8_lossy(&rest[..line_end]);
+            let size =
+                usize::from_str_radix(size_text.split(';').next().unwrap_or("").trim(), 16).map_err(|_| malformed())?;
+            rest = &rest[line_end + 2..];
+            if size == 0 {
+                break;
+            }
+            decoded.extend_from_slice(rest.get(..size).ok_or_else(malformed)?);
+            rest = rest.get(size + 2..).ok_or_else(malformed)?;
+        }
+        body = decoded;
+    }
+
+    Ok(Response { status, body })
+}
+
+impl Storage for HttpStorage
+{
+    fn login(&self, name: &str, token: Option<&str>) -> Result<Account, StoreError>
+    {
+        let body = LoginBody {
+            name: name.to_string(),
+            token: token.map(str::to_string),
+        };
+        let response = self.send("POST", "/login", Some(&body))?;
+        match response.status {
+            401 => Err(StoreError::UnknownAccount),
+            409 => Err(StoreError::NameTaken(name.to_string())),
+            422 => Err(StoreError::InvalidName(name.to_string())),
+            _ => response.json("/login"),
+        }
+    }
+
+    fn record_match(&self, token: &str, result: MatchResult) -> Result<(), StoreError>
+    {
+        let body = MatchBody {
+            token: token.to_string(),
+            result,
+        };
+        let response = self.send("POST", "/matches", Some(&body))?;
+        match response.status {
+            401 => Err(StoreError::UnknownAccount),
+            _ => response.check("/matches"),
+        }
+    }
+
+    fn leaderboards(&self, now: u64) -> Result<Leaderboards, StoreError>
+    {
+        self.send("GET", &format!("/leaderboards?now={now}"), None::<&()>)?
+            .json("/leaderboards")
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    /// A stand-in backend that serves `requests` requests from a [`MemoryStorage`] and then stops.
+    fn spawn_mock_backend(requests: usize) -> String
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            let mut backend = MemoryStorage::default();
+            for stream in listener.incoming().take(requests) {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(length) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let status = |result: Result<(), StoreError>| match result {
+                    Ok(()) => 200,
+                    Err(StoreError::UnknownAccount) => 401,
+                    Err(StoreError::NameTaken(_)) => 409,
+                    Err(StoreError::InvalidName(_)) => 422,
+                    Err(_) => 500,
+                };
+                let target = request_line.split_whitespace().nth(1).unwrap();
+                let (code, reply, chunked) = match target.split_once('?').map_or(target, |(path, _)| path) {
+                    "/api/login" => {
+                        let login: LoginBody = serde_json::from_slice(&body).unwrap();
+                        match backend.login(&login.name, login.token.as_deref()) {
+                            Ok(account) => (200, serde_json::to_string(&account).unwrap(), false),
+                            Err(err) => (status(Err(err)), String::new(), false),
+                        }
+                    }
+                    "/api/matches" => {
+                        let game: MatchBody = serde_json::from_slice(&body).unwrap();
+                        (status(backend.record_match(&game.token, game.result)), String::new(), false)
+                    }
+                    "/api/leaderboards" => {
+                        let now = target.rsplit('=').next().unwrap().parse().unwrap();
+                        let boards = backend.leaderboards(now).unwrap();
+                        (200, serde_json::to_string(&boards).unwrap(), true)
+                    }
+                    _ => (404, String::new(), false),
+                };
+
+                let stream = reader.get_mut();
+                if chunked {
+                    let (first, second) = reply.split_at(reply.len() / 2);
+                    write!(
+                        stream,
+                        "HTTP/1.1 {code} OK\r\nTransfer-Encoding: \
+                         chunked\r\n\r\n{:x}\r\n{first}\r\n{:x}\r\n{second}\r\n0\r\n\r\n",
+                        first.len(),
+                        second.len()
+                    )
+                    .unwrap();
+                } else {
+                    write!(stream, "HTTP/1.1 {code} OK\r\nContent-Length: {}\r\n\r\n{reply}", reply.len()).unwrap();
+                }
+            }
+        });
+
+        url
+    }
+
+    #[test]
+    fn talks_to_a_backend_over_http()
+    {
+        let storage = HttpStorage::new(&spawn_mock_backend(5)).unwrap();
+
+        let account = storage.login("Viper", None).unwrap();
+        assert!(matches!(storage.login("Viper", None), Err(StoreError::NameTaken(_))));
+        assert!(matches!(
+            storage.record_match(
+                "guess",
+                MatchResult {
+                    score: 1,
+                    kills: 0,
+                    duration: 1.0,
+                    ended_at: 0,
+                }
+            ),
+            Err(StoreError::UnknownAccount)
+        ));
+        storage
+            .record_match(
+                &account.token,
+                MatchResult {
+                    score: 75,
+                    kills: 3,
+                    duration: 42.0,
+                    ended_at: 100,
+                },
+            )
+            .unwrap();
+
+        let boards = storage.leaderboards(100).unwrap();
+        assert_eq!(boards.daily[0].name, "Viper");
+        assert_eq!(boards.all_time[0].score, 75);
+    }
+
+    #[test]
+    fn rejects_urls_it_cannot_reach()
+    {
+        assert!(HttpStorage::new("https://example.com").is_err());
+        assert!(HttpStorage::new("http:///api").is_err());
+
+        let storage = HttpStorage::new("http://example.com/api/v1/").unwrap();
+        assert_eq!(storage.address, "example.com:80");
+        assert_eq!(storage.base_path, "/api/v1");
+    }
+}
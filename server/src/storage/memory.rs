@@ -0,0 +1,212 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use slither_common::ranking::{top_scores, Leaderboards, Period, RankedScore};
+
+use super::{lock, Account, MatchResult, Storage, StoreError, MAX_NAME_LENGTH};
+
+/// Bump this whenever the layout of the stored data changes.
+pub const STORE_VERSION: u32 = 1;
+
+/// Keeps everything in memory. The other local backends build on it and only add where the data is kept.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct MemoryStorage
+{
+    pub version: u32,
+    pub accounts: Vec<Account>,
+    /// Scores from this week, which the daily and weekly lists are built from. The all-time list only needs
+    /// each account's best.
+    pub recent_scores: Vec<RankedScore>,
+}
+
+impl Default for MemoryStorage
+{
+    fn default() -> Self
+    {
+        Self {
+            version: STORE_VERSION,
+            accounts: Vec::new(),
+            recent_scores: Vec::new(),
+        }
+    }
+}
+
+impl MemoryStorage
+{
+    pub fn login(&mut self, name: &str, token: Option<&str>) -> Result<Account, StoreError>
+    {
+        let name = name.trim();
+        if let Some(token) = token {
+            return self
+                .accounts
+                .iter()
+                .find(|account| account.token == token)
+                .cloned()
+                .ok_or(StoreError::UnknownAccount);
+        }
+
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(StoreError::InvalidName(name.to_string()));
+        }
+        if self.accounts.iter().any(|account| account.name.eq_ignore_ascii_case(name)) {
+            return Err(StoreError::NameTaken(name.to_string()));
+        }
+
+        let account = Account {
+            name: name.to_string(),
+            token: new_token(),
+            best_score: 0,
+            total_kills: 0,
+            games_played: 0,
+            playtime: 0.0,
+        };
+        self.accounts.push(account.clone());
+        Ok(account)
+    }
+
+    pub fn record_match(&mut self, token: &str, result: MatchResult) -> Result<(), StoreError>
+    {
+        let account = self
+            .accounts
+            .iter_mut()
+            .find(|account| account.token == token)
+            .ok_or(StoreError::UnknownAccount)?;
+        account.best_score = account.best_score.max(result.score);
+        account.total_kills += result.kills;
+        account.games_played += 1;
+        account.playtime += result.duration;
+
+        let name = account.name.clone();
+        self.recent_scores.push(RankedScore {
+            name,
+            score: result.score,
+            achieved_at: result.ended_at,
+        });
+        // Scores from before this week can no longer reach the daily or weekly lists
+        self.recent_scores
+            .retain(|score| Period::Weekly.contains(score.achieved_at, result.ended_at));
+        Ok(())
+    }
+
+    pub fn leaderboards(&self, now: u64) -> Result<Leaderboards, StoreError>
+    {
+        let bests: Vec<RankedScore> = self
+            .accounts
+            .iter()
+            .filter(|account| account.games_played > 0)
+            .map(|account| RankedScore {
+                name: account.name.clone(),
+                score: account.best_score,
+                achieved_at: now,
+            })
+            .collect();
+
+        Ok(Leaderboards {
+            daily: top_scores(&self.recent_scores, Period::Daily, now),
+            weekly: top_scores(&self.recent_scores, Period::Weekly, now),
+            all_time: top_scores(&bests, Period::AllTime, now),
+        })
+    }
+}
+
+/// The memory backend, locked so every connection can share it.
+impl Storage for Mutex<MemoryStorage>
+{
+    fn login(&self, name: &str, token: Option<&str>) -> Result<Account, StoreError>
+    {
+        lock(self).login(name, token)
+    }
+
+    fn record_match(&self, token: &str, result: MatchResult) -> Result<(), StoreError>
+    {
+        lock(self).record_match(token, result)
+    }
+
+    fn leaderboards(&self, now: u64) -> Result<Leaderboards, StoreError>
+    {
+        lock(self).leaderboards(now)
+    }
+}
+
+/// 32 hex characters from the standard library's randomly keyed hasher. Good enough to tell players apart
+/// offline, not meant to stand up to an attacker.
+fn new_token() -> String
+{
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos());
+    let state = RandomState::new();
+    (0..2u8)
+        .map(|half| {
+            let mut hasher = state.build_hasher();
+            hasher.write_u8(half);
+            hasher.write_u128(nanos);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use slither_common::ranking::LeaderboardEntry;
+
+    use super::*;
+
+    // Wednesday 2024-01-03, noon UTC
+    const NOW: u64 = 1_704_283_200;
+
+    fn result(score: u32, ended_at: u64) -> MatchResult
+    {
+        MatchResult {
+            score,
+            kills: 1,
+            duration: 90.0,
+            ended_at,
+        }
+    }
+
+    #[test]
+    fn names_belong_to_whoever_holds_the_token()
+    {
+        let mut storage = MemoryStorage::default();
+        let account = storage.login("Viper", None).unwrap();
+
+        assert_eq!(storage.login("", Some(&account.token)).unwrap(), account);
+        assert!(matches!(storage.login("viper", None), Err(StoreError::NameTaken(_))));
+        assert!(matches!(
+            storage.login("Viper", Some("guess")),
+            Err(StoreError::UnknownAccount)
+        ));
+        assert!(matches!(storage.login("  ", None), Err(StoreError::InvalidName(_))));
+    }
+
+    #[test]
+    fn results_fill_the_top_lists()
+    {
+        let mut storage = MemoryStorage::default();
+        let viper = storage.login("Viper", None).unwrap();
+        let adder = storage.login("Adder", None).unwrap();
+        storage
+            .record_match(&viper.token, result(300, NOW - 8 * 24 * 60 * 60))
+            .unwrap();
+        storage.record_match(&viper.token, result(40, NOW)).unwrap();
+        storage.record_match(&adder.token, result(90, NOW - 24 * 60 * 60)).unwrap();
+
+        let boards = storage.leaderboards(NOW).unwrap();
+        let names = |list: &[LeaderboardEntry]| -> Vec<(String, u32)> {
+            list.iter().map(|entry| (entry.name.clone(), entry.score)).collect()
+        };
+
+        assert_eq!(names(&boards.daily), [("Viper".into(), 40)]);
+        assert_eq!(names(&boards.weekly), [("Adder".into(), 90), ("Viper".into(), 40)]);
+        assert_eq!(names(&boards.all_time), [("Viper".into(), 300), ("Adder".into(), 90)]);
+        assert_eq!(storage.accounts[0].games_played, 2);
+        // Last week's score only lives on in the account's best
+        assert_eq!(storage.recent_scores.len(), 2);
+    }
+}
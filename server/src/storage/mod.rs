@@ -0,0 +1,140 @@
+pub mod file;
+pub mod http;
+pub mod memory;
+
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+use slither_common::ranking::Leaderboards;
+
+pub use self::file::FileStorage;
+pub use self::http::HttpStorage;
+pub use self::memory::MemoryStorage;
+
+pub const MAX_NAME_LENGTH: usize = 16;
+
+/// A player known to the server. The token handed out when the account was made proves who they are.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Account
+{
+    pub name: String,
+    pub token: String,
+    pub best_score: u32,
+    pub total_kills: u32,
+    pub games_played: u32,
+    /// Seconds spent alive over every game.
+    pub playtime: f32,
+}
+
+/// How one game went for one account.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchResult
+{
+    pub score: u32,
+    pub kills: u32,
+    /// Seconds the snake was alive.
+    pub duration: f32,
+    /// Seconds since the unix epoch when the game ended.
+    pub ended_at: u64,
+}
+
+#[derive(Debug)]
+pub enum StoreError
+{
+    Io(io::Error),
+    Parse(String),
+    /// The data could not be turned into the stored format.
+    Serialize(String),
+    UnsupportedVersion(u32),
+    /// The backend could not be reached or answered with something unexpected.
+    Http(String),
+    InvalidName(String),
+    NameTaken(String),
+    UnknownAccount,
+}
+
+impl fmt::Display for StoreError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            StoreError::Io(err) => write!(f, "store io error: {err}"),
+            StoreError::Parse(err) => write!(f, "corrupt store: {err}"),
+            StoreError::Serialize(err) => write!(f, "failed to write store: {err}"),
+            StoreError::UnsupportedVersion(version) => {
+                write!(f, "unsupported store version {version} (expected {})", memory::STORE_VERSION)
+            }
+            StoreError::Http(err) => write!(f, "storage backend error: {err}"),
+            StoreError::InvalidName(name) => {
+                write!(f, "\"{name}\" is not a valid name, use 1 to {MAX_NAME_LENGTH} characters")
+            }
+            StoreError::NameTaken(name) => write!(f, "the name \"{name}\" belongs to another player"),
+            StoreError::UnknownAccount => write!(f, "no account has that token"),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+/// Every backend changes what it keeps in one go, so a request that panicked halfway can't have left it half
+/// changed and a poisoned lock is still safe to use.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T>
+{
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl From<io::Error> for StoreError
+{
+    fn from(err: io::Error) -> Self
+    {
+        StoreError::Io(err)
+    }
+}
+
+/// Where accounts, match results and leaderboards live. The game only talks to this trait, so the backend can
+/// change without touching it. Every connection shares one backend, which locks only what it keeps itself, so a
+/// slow request doesn't hold up the others.
+pub trait Storage: Send + Sync
+{
+    /// Signs in with a token, or makes a new account when there is none. A name can only be claimed once.
+    fn login(&self, name: &str, token: Option<&str>) -> Result<Account, StoreError>;
+
+    /// Adds a finished game to the account's stats and the top lists.
+    fn record_match(&self, token: &str, result: MatchResult) -> Result<(), StoreError>;
+
+    fn leaderboards(&self, now: u64) -> Result<Leaderboards, StoreError>;
+}
+
+/// Which backend the server keeps its data in, read from the server config.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum StorageConfig
+{
+    /// Nothing survives a restart. Handy for tests and throwaway servers.
+    Memory,
+    /// A RON file on the server's disk.
+    File(PathBuf),
+    /// Any backend that speaks the JSON API described on [`HttpStorage`], e.g. `"http://localhost:8080/api"`.
+    Http(String),
+}
+
+impl Default for StorageConfig
+{
+    fn default() -> Self
+    {
+        StorageConfig::File(PathBuf::from("data/accounts.ron"))
+    }
+}
+
+impl StorageConfig
+{
+    pub fn open(&self) -> Result<Box<dyn Storage>, StoreError>
+    {
+        Ok(match self {
+            StorageConfig::Memory => Box::new(Mutex::new(MemoryStorage::default())),
+            StorageConfig::File(path) => Box::new(FileStorage::open(path)?),
+            StorageConfig::Http(url) => Box::new(HttpStorage::new(url)?),
+        })
+    }
+}